@@ -15,10 +15,12 @@ fn main() {
             "contents",
             "design",
             "direction",
+            "e",
             "edif",
             "edifLevel",
             "edifLevel",
             "edifversion",
+            "external",
             "false",
             "hdi_primitives",
             "INOUT",
@@ -35,6 +37,7 @@ fn main() {
             "member",
             "net",
             "NETLIST",
+            "number",
             "OUTPUT",
             "port",
             "portref",
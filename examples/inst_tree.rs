@@ -25,14 +25,12 @@ fn main() -> anyhow::Result<()> {
         };
         println!("{}{}", "  ".repeat(level), path);
 
-        let mut children = inst.instances.into_iter().collect::<Vec<_>>();
-        children.sort_by(|(a, _), (b, _)| a.cmp(b));
-
+        // The children in the order of the source.
         stack.extend(
-            children
-                .into_iter()
+            inst.instances
+                .into_values()
                 .rev()
-                .map(|(_, inst)| (level + 1, inst)),
+                .map(|inst| (level + 1, inst)),
         );
     }
 
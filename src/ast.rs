@@ -1,64 +1,129 @@
 use crate::atom::Atom;
-use fxhash::FxHashMap;
+use std::convert::TryFrom;
+use std::fmt;
 use std::sync::Arc;
 
+/// A map iterating in the order of insertion, which is the order of the source.
+pub type FxIndexMap<K, V> = indexmap::IndexMap<K, V, fxhash::FxBuildHasher>;
+
+/// A set iterating in the order of insertion.
+pub type FxIndexSet<T> = indexmap::IndexSet<T, fxhash::FxBuildHasher>;
+
+/// A range of the source text, as byte offsets, with the line and column of its start.
+///
+/// Nodes which were not read from a source, such as the ones added by editing, have the
+/// default span, at line 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: i32,
+    pub column: i32,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edif {
-    pub libs: FxHashMap<Atom, Library>,
+    pub libs: FxIndexMap<Atom, Library>,
     pub design: Design,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Design {
     pub inst_name: Atom,
     pub cellref: Atom,
     pub libraryref: Atom,
+    pub span: Span,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Library {
     pub name: Atom,
-    pub cells: FxHashMap<Atom, Cell>,
+    /// Whether the library is declared `external`, with its cells defined elsewhere.
+    pub external: bool,
+    pub cells: FxIndexMap<Atom, Cell>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cell {
     pub name: Atom,
     pub view: View,
+    pub span: Span,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct View {
     pub name: Atom,
     pub interface: Interface,
     pub contents: Vec<Content>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Interface {
     pub ports: Vec<Port>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Name {
     pub name: Atom,
     pub rename_from: Option<String>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Port {
     pub kind: PortKind,
     pub dir: Direction,
     pub name: Name,
+    pub span: Span,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PortKind {
     Single,
     Array(i32),
 }
 
+impl PortKind {
+    /// Number of bits of the port.
+    pub fn width(self) -> i32 {
+        match self {
+            PortKind::Single => 1,
+            PortKind::Array(n) => n,
+        }
+    }
+
+    /// The `member` index that refers to the `bit`-th least significant bit of the port.
+    ///
+    /// EDIF numbers array members from the most significant bit, so `(member ret 0)` of
+    /// `ret[1:0]` is `ret[1]`.
+    pub fn member_of_bit(self, bit: i32) -> Option<i32> {
+        match self {
+            PortKind::Single => None,
+            PortKind::Array(n) => Some(n - 1 - bit),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Input,
     Output,
@@ -66,36 +131,159 @@ pub enum Direction {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Content {
     Net(Net),
     Instance(Instance),
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Net {
     pub name: Name,
     pub portrefs: Vec<PortRef>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Instance {
     pub name: Name,
     pub cellref: Atom,
     pub viewref: Atom,
     pub libraryref: Option<Atom>,
-    pub properties: Arc<FxHashMap<Name, Property>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::shared_pairs"))]
+    pub properties: Arc<FxIndexMap<Name, Property>>,
+    /// The span of each `property`, by name.
+    pub property_spans: FxIndexMap<Atom, Span>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+/// The value of a property. A value with several elements, such as `(integer 1 2 3)`, is a
+/// [`List`](Property::List).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Property {
     String(String),
-    Integer(i32),
+    Integer(i64),
+    Number(Number),
     Boolean(bool),
+    List(Vec<Property>),
+}
+
+/// A scaled decimal `mantissa * 10^exponent`, as in `(e 15 -1)` for 1.5.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Number {
+    pub mantissa: i64,
+    pub exponent: i32,
+}
+
+impl Number {
+    /// The value, if it is an integer fitting in 64 bits.
+    pub fn to_i64(self) -> Option<i64> {
+        if self.exponent >= 0 {
+            10i64
+                .checked_pow(self.exponent as u32)?
+                .checked_mul(self.mantissa)
+        } else {
+            match 10i64.checked_pow(self.exponent.unsigned_abs()) {
+                Some(d) => (self.mantissa % d == 0).then_some(self.mantissa / d),
+                None => (self.mantissa == 0).then_some(0),
+            }
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.mantissa as f64 * 10f64.powi(self.exponent)
+    }
+}
+
+/// Formats as a decimal, as in `1.5` or `-0.025`, or in scientific notation, as in `15e-40`,
+/// if the exponent is beyond the digits of an `i64`.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+        if self.mantissa == 0 {
+            write!(f, "0")
+        } else if self.exponent.unsigned_abs() > 19 {
+            write!(f, "{}e{}", self.mantissa, self.exponent)
+        } else if self.exponent >= 0 {
+            write!(
+                f,
+                "{}{}{}",
+                sign,
+                digits,
+                "0".repeat(self.exponent as usize)
+            )
+        } else {
+            let point = self.exponent.unsigned_abs() as usize;
+            let digits = format!(
+                "{}{}",
+                "0".repeat((point + 1).saturating_sub(digits.len())),
+                digits
+            );
+            let (int, frac) = digits.split_at(digits.len() - point);
+            write!(f, "{}{}.{}", sign, int, frac)
+        }
+    }
+}
+
+impl Property {
+    /// The value as an integer: an integer, an integral number, or a decimal or Verilog
+    /// literal of up to 64 bits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Property::Integer(i) => Some(*i),
+            Property::Number(n) => n.to_i64(),
+            Property::String(s) => s.parse().ok().or_else(|| {
+                let bits = s.parse::<crate::literal::BitVec>().ok()?;
+                Some(bits.to_u64()? as i64)
+            }),
+            Property::Boolean(_) | Property::List(_) => None,
+        }
+    }
+
+    /// The value as a boolean: a boolean, an integer 0 or 1, or a string `TRUE` or `FALSE`
+    /// in any case.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Boolean(b) => Some(*b),
+            Property::Integer(0) => Some(false),
+            Property::Integer(1) => Some(true),
+            Property::String(s) if s.eq_ignore_ascii_case("true") => Some(true),
+            Property::String(s) if s.eq_ignore_ascii_case("false") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The value as a bit vector: a Verilog literal, an integer 32 bits wide or 64 bits if it
+    /// does not fit, or a boolean 1 bit wide.
+    pub fn as_bitvec(&self) -> anyhow::Result<crate::literal::BitVec> {
+        use crate::literal::BitVec;
+        let int = |i: i64| match i32::try_from(i) {
+            Ok(i) => BitVec::from_u64(32, i as u32 as u64),
+            Err(_) => BitVec::from_u64(64, i as u64),
+        };
+        match self {
+            Property::String(s) => s.parse(),
+            Property::Integer(i) => Ok(int(*i)),
+            Property::Number(n) => n
+                .to_i64()
+                .map(int)
+                .ok_or_else(|| anyhow::anyhow!("{} is not an integer", n)),
+            Property::Boolean(b) => Ok(BitVec::from_u64(1, *b as u64)),
+            Property::List(_) => anyhow::bail!("a list is not a bit vector"),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PortRef {
     pub port: Atom,
     pub member: Option<i32>,
     pub instance_ref: Option<Atom>,
+    pub span: Span,
 }
@@ -0,0 +1,370 @@
+//! The `edif` command-line tool.
+//!
+//! Inputs may be gzip-compressed, and `-` reads the standard input. The exit code is 0 on
+//! success, 1 when a check fails or `diff` finds differences, and 2 on errors.
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use edif::diff::{self, DiffOptions};
+use edif::dot::{self, DotOptions};
+use edif::lint::{self, Severity};
+use edif::netlist::{self, glob_match, Instance, Netlist};
+use edif::{blif, verilog, writer, yosys};
+use flate2::read::MultiGzDecoder;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "edif", version, about = "Inspects and converts EDIF netlists")]
+struct Cli {
+    /// Print machine-readable JSON.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the instance hierarchy.
+    Tree {
+        input: String,
+        /// The maximum depth to print, the top being at depth 0.
+        #[arg(long)]
+        depth: Option<usize>,
+        /// Print only the instances whose paths match this glob, and their ancestors.
+        #[arg(long)]
+        filter: Option<String>,
+        /// Print the leaf instances too.
+        #[arg(long)]
+        leaves: bool,
+    },
+    /// Print the numbers of instances, nets and cells.
+    Stats { input: String },
+    /// Flatten the hierarchy, and write the netlist in EDIF.
+    Flatten {
+        input: String,
+        /// The output file, instead of the standard output.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Write the netlist in another format.
+    Convert {
+        input: String,
+        #[arg(long)]
+        to: Format,
+        /// The output file, instead of the standard output.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// List the instances and nets whose paths match a glob, as `main/**/x_reg_*`.
+    Query { pattern: String, input: String },
+    /// Check the netlist for structural problems, failing on errors.
+    Lint {
+        input: String,
+        /// Fail on warnings too.
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Compare two netlists, failing if they differ.
+    Diff {
+        old: String,
+        new: String,
+        /// Match the nets with names generated by synthesis, as `p_3_in`, by their pins.
+        #[arg(long)]
+        ignore_generated_names: bool,
+    },
+    /// Check that every port reference resolves.
+    Verify { input: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Edif,
+    Verilog,
+    Json,
+    Blif,
+    Dot,
+}
+
+fn read(path: &str) -> Result<Netlist> {
+    let mut bytes = vec![];
+    if path == "-" {
+        io::stdin().read_to_end(&mut bytes)?;
+    } else {
+        File::open(path)
+            .and_then(|mut f| f.read_to_end(&mut bytes))
+            .with_context(|| format!("cannot read `{}`", path))?;
+    }
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut out = vec![];
+        MultiGzDecoder::new(&bytes[..])
+            .read_to_end(&mut out)
+            .with_context(|| format!("cannot decompress `{}`", path))?;
+        bytes = out;
+    }
+    let s = String::from_utf8(bytes).with_context(|| format!("`{}` is not UTF-8", path))?;
+    netlist::from_str(&s).with_context(|| format!("cannot parse `{}`", path))
+}
+
+fn output(path: Option<&str>) -> Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("cannot create `{}`", path))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout())),
+    })
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+struct Tree<'a> {
+    depth: Option<usize>,
+    filter: Option<&'a str>,
+    leaves: bool,
+}
+
+impl Tree<'_> {
+    /// The tree under `inst` as JSON, or `None` if it is filtered out.
+    fn node(&self, inst: &Instance, depth: usize) -> Option<Value> {
+        let children = if self.depth.is_some_and(|d| depth >= d) {
+            vec![]
+        } else {
+            inst.instances
+                .values()
+                .filter(|c| self.leaves || !c.is_leaf())
+                .filter_map(|c| self.node(c, depth + 1))
+                .collect()
+        };
+        let matched = self
+            .filter
+            .is_none_or(|f| glob_match(f, &inst.path.to_string()));
+        if !matched && children.is_empty() {
+            return None;
+        }
+        Some(json!({
+            "name": &*inst.path.name(),
+            "cell": &*inst.cell,
+            "lib": &*inst.lib,
+            "children": children,
+        }))
+    }
+}
+
+fn print_tree(node: &Value, depth: usize) {
+    println!(
+        "{}{} ({})",
+        "  ".repeat(depth),
+        node["name"].as_str().unwrap(),
+        node["cell"].as_str().unwrap()
+    );
+    for child in node["children"].as_array().unwrap() {
+        print_tree(child, depth + 1);
+    }
+}
+
+fn stats(n: &Netlist) -> Value {
+    let mut cells = std::collections::BTreeMap::<String, usize>::new();
+    let (mut leaves, mut hierarchical, mut nets) = (0, 0, 0);
+    for inst in n.top.walk() {
+        nets += inst.nets.len();
+        if inst.path == n.top.path {
+            continue;
+        }
+        if inst.is_leaf() {
+            leaves += 1;
+            *cells.entry(inst.cell.to_string()).or_default() += 1;
+        } else {
+            hierarchical += 1;
+        }
+    }
+    json!({
+        "top": n.top.cell.to_string(),
+        "ports": n.top.interface.len(),
+        "hierarchical_instances": hierarchical,
+        "leaf_instances": leaves,
+        "nets": nets,
+        "global_nets": n.global_nets().len(),
+        "black_boxes": n.black_boxes().len(),
+        "cells": cells,
+    })
+}
+
+fn run(cli: &Cli) -> Result<bool> {
+    match &cli.command {
+        Command::Tree {
+            input,
+            depth,
+            filter,
+            leaves,
+        } => {
+            let n = read(input)?;
+            let tree = Tree {
+                depth: *depth,
+                filter: filter.as_deref(),
+                leaves: *leaves,
+            };
+            let root = tree.node(&n.top, 0).unwrap_or(Value::Null);
+            if cli.json {
+                print_json(&root)?;
+            } else if !root.is_null() {
+                print_tree(&root, 0);
+            }
+        }
+        Command::Stats { input } => {
+            let stats = stats(&read(input)?);
+            if cli.json {
+                print_json(&stats)?;
+            } else {
+                for (k, v) in stats.as_object().unwrap() {
+                    match v {
+                        Value::String(s) => println!("{:<24}{}", k.replace('_', " "), s),
+                        Value::Object(_) => {}
+                        v => println!("{:<24}{}", k.replace('_', " "), v),
+                    }
+                }
+                let mut cells = stats["cells"]
+                    .as_object()
+                    .unwrap()
+                    .iter()
+                    .collect::<Vec<_>>();
+                cells.sort_by_key(|(_, count)| std::cmp::Reverse(count.as_u64()));
+                for (cell, count) in cells {
+                    println!("  {:<22}{}", cell, count);
+                }
+            }
+        }
+        Command::Flatten { input, output: out } => {
+            let mut n = read(input)?;
+            n.verify_references()?;
+            n.flatten();
+            let mut w = output(out.as_deref())?;
+            writer::write_edif(&n, &mut w)?;
+            w.flush()?;
+        }
+        Command::Convert {
+            input,
+            to,
+            output: out,
+        } => {
+            let n = read(input)?;
+            n.verify_references()?;
+            let mut w = output(out.as_deref())?;
+            match to {
+                Format::Edif => writer::write_edif(&n, &mut w)?,
+                Format::Verilog => verilog::write_verilog(&n, &mut w)?,
+                Format::Json => yosys::write_json(&n, &mut w)?,
+                Format::Blif => blif::write_blif(&n, &mut w)?,
+                Format::Dot => dot::write_dot(&n, &DotOptions::default(), &mut w)?,
+            }
+            w.flush()?;
+        }
+        Command::Query { pattern, input } => {
+            let n = read(input)?;
+            let mut matches = vec![];
+            for inst in n.top.walk() {
+                if glob_match(pattern, &inst.path.to_string()) {
+                    matches.push((inst.path.to_string(), Some(&inst.cell), inst.span));
+                }
+                for (name, net) in &inst.nets {
+                    let path = inst.path.child(name.clone()).to_string();
+                    if glob_match(pattern, &path) {
+                        matches.push((path, None, net.span));
+                    }
+                }
+            }
+            matches.sort_by(|a, b| a.0.cmp(&b.0));
+            if cli.json {
+                let matches = matches
+                    .iter()
+                    .map(|(path, cell, span)| {
+                        let mut m = json!({"path": path, "line": span.line, "column": span.column});
+                        match cell {
+                            Some(cell) => {
+                                m["kind"] = "instance".into();
+                                m["cell"] = cell.to_string().into();
+                            }
+                            None => m["kind"] = "net".into(),
+                        }
+                        m
+                    })
+                    .collect();
+                print_json(&Value::Array(matches))?;
+            } else {
+                for (path, cell, span) in &matches {
+                    match cell {
+                        Some(cell) => println!("{}: instance {} ({})", span, path, cell),
+                        None => println!("{}: net {}", span, path),
+                    }
+                }
+            }
+            return Ok(!matches.is_empty());
+        }
+        Command::Lint {
+            input,
+            deny_warnings,
+        } => {
+            let issues = lint::lint(&read(input)?);
+            let count = |s| issues.iter().filter(|i| i.severity == s).count();
+            let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+            if cli.json {
+                print_json(&issues.iter().map(|i| i.to_json()).collect())?;
+            } else {
+                for issue in &issues {
+                    println!("{}", issue);
+                }
+                println!("{} error(s), {} warning(s)", errors, warnings);
+            }
+            return Ok(errors == 0 && (!deny_warnings || warnings == 0));
+        }
+        Command::Diff {
+            old,
+            new,
+            ignore_generated_names,
+        } => {
+            let options = DiffOptions {
+                ignore_generated_names: *ignore_generated_names,
+            };
+            let d = diff::diff(&read(old)?, &read(new)?, &options);
+            if cli.json {
+                print_json(&d.to_json())?;
+            } else {
+                print!("{}", d);
+            }
+            return Ok(d.is_empty());
+        }
+        Command::Verify { input } => {
+            let result = read(input)?.verify_references();
+            if cli.json {
+                print_json(&json!({
+                    "ok": result.is_ok(),
+                    "error": result.as_ref().err().map(|e| e.to_string()),
+                }))?;
+            } else {
+                match &result {
+                    Ok(()) => println!("ok"),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            return Ok(result.is_ok());
+        }
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! BLIF (Berkeley Logic Interchange Format) writer for [`Netlist`](crate::netlist::Netlist).
+//!
+//! Every hierarchical cell becomes a `.model`. LUTs, buffers, inverters and constant drivers
+//! are written as `.names` covers, and flip-flops with synchronous controls as `.latch`es whose
+//! next-state function is a `.names` cover. Flip-flops with asynchronous controls and unknown
+//! cells are instantiated with `.subckt`, and a `.blackbox` model stub is generated for them
+//! from their interface.
+
+use crate::ast::{Direction, Port};
+use crate::atom::Atom;
+use crate::netlist::{pin_string, Instance, Netlist};
+use crate::primitives::PrimitiveKind;
+use anyhow::{anyhow, Result};
+use fxhash::{FxHashMap, FxHashSet};
+use std::io::Write;
+
+/// Writes `netlist` in BLIF.
+pub fn write_blif<W: Write>(netlist: &Netlist, mut w: W) -> Result<()> {
+    let mut writer = BlifWriter {
+        models: FxHashSet::default(),
+        stubs: FxHashMap::default(),
+    };
+    writer.write_model(&netlist.top, &mut w)?;
+
+    let mut stubs = writer.stubs.into_iter().collect::<Vec<_>>();
+    stubs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, ports) in stubs {
+        writeln!(w)?;
+        writeln!(w, ".model {}", name)?;
+        write_port_list(&mut w, ".inputs", &ports, |d| d != Direction::Output)?;
+        write_port_list(&mut w, ".outputs", &ports, |d| d == Direction::Output)?;
+        writeln!(w, ".blackbox")?;
+        writeln!(w, ".end")?;
+    }
+
+    Ok(())
+}
+
+struct BlifWriter {
+    models: FxHashSet<Atom>,
+    stubs: FxHashMap<Atom, Vec<Port>>,
+}
+
+/// Signal names of the pins in a model.
+struct Signals {
+    of_pin: FxHashMap<(Option<Atom>, Atom, Option<i32>), String>,
+    unconnected: usize,
+}
+
+impl Signals {
+    fn get(&mut self, inst: Option<&Atom>, port: &Atom, member: Option<i32>) -> String {
+        let key = (inst.cloned(), port.clone(), member);
+        if let Some(s) = self.of_pin.get(&key) {
+            return s.clone();
+        }
+        self.unconnected += 1;
+        let s = format!("$unconn{}", self.unconnected);
+        self.of_pin.insert(key, s.clone());
+        s
+    }
+
+    fn pin(&mut self, inst: &Instance, port: &str) -> Result<String> {
+        let port = inst
+            .interface
+            .get(&Atom::from(port))
+            .ok_or_else(|| anyhow!("`{}` does not have port `{}`", inst.path, port))?;
+        Ok(self.get(Some(&inst.path.name()), &port.name.name, None))
+    }
+}
+
+/// The signal name of the `bit`-th bit of `port`.
+fn bit_name(port: &Port, bit: i32) -> String {
+    match port.kind.member_of_bit(bit) {
+        None => port.name.name.to_string(),
+        Some(_) => format!("{}[{}]", port.name.name, bit),
+    }
+}
+
+fn sorted_ports(inst: &Instance) -> Vec<&Port> {
+    let mut ports = inst.interface.values().collect::<Vec<_>>();
+    ports.sort_by(|a, b| a.name.name.cmp(&b.name.name));
+    ports
+}
+
+fn write_port_list<W: Write>(
+    w: &mut W,
+    directive: &str,
+    ports: &[Port],
+    filter: impl Fn(Direction) -> bool,
+) -> Result<()> {
+    write!(w, "{}", directive)?;
+    for port in ports.iter().filter(|p| filter(p.dir)) {
+        for bit in 0..port.kind.width() {
+            write!(w, " {}", bit_name(port, bit))?;
+        }
+    }
+    writeln!(w)?;
+    Ok(())
+}
+
+impl BlifWriter {
+    fn write_model<W: Write>(&mut self, inst: &Instance, w: &mut W) -> Result<()> {
+        if !self.models.insert(inst.cell.clone()) {
+            return Ok(());
+        }
+
+        let ports = sorted_ports(inst);
+
+        let mut signals = Signals {
+            of_pin: FxHashMap::default(),
+            unconnected: 0,
+        };
+        let mut aliases = vec![];
+
+        for port in &ports {
+            for bit in 0..port.kind.width() {
+                signals.of_pin.insert(
+                    (None, port.name.name.clone(), port.kind.member_of_bit(bit)),
+                    bit_name(port, bit),
+                );
+            }
+        }
+
+        let mut nets = inst.nets.iter().collect::<Vec<_>>();
+        nets.sort_by_key(|(name, _)| *name);
+        for (name, net) in nets {
+            let mut own = vec![];
+            for p in &net.ports {
+                let port = inst.pin_port(p)?;
+                if p.instance == inst.path {
+                    let key = (None, p.port.clone(), p.member);
+                    let signal = signals
+                        .of_pin
+                        .get(&key)
+                        .ok_or_else(|| anyhow!("pin `{}` does not exist", pin_string(p)))?;
+                    own.push((port.dir == Direction::Output, signal.clone()));
+                }
+            }
+            own.sort();
+
+            // A net connected to ports of the model takes the name of one of them, preferring
+            // an input port as the driver. The others are driven through buffers.
+            let signal = match own.first() {
+                Some((_, s)) => s.clone(),
+                None => name.to_string(),
+            };
+            for (_, s) in own.iter().skip(1) {
+                aliases.push((signal.clone(), s.clone()));
+            }
+
+            for p in net.ports.iter().filter(|p| p.instance != inst.path) {
+                signals.of_pin.insert(
+                    (Some(p.instance.name()), p.port.clone(), p.member),
+                    signal.clone(),
+                );
+            }
+        }
+
+        writeln!(w, ".model {}", inst.cell)?;
+        let ports = ports.into_iter().cloned().collect::<Vec<_>>();
+        write_port_list(w, ".inputs", &ports, |d| d != Direction::Output)?;
+        write_port_list(w, ".outputs", &ports, |d| d == Direction::Output)?;
+
+        let mut children = inst.instances.iter().collect::<Vec<_>>();
+        children.sort_by_key(|(name, _)| *name);
+
+        for (_, child) in &children {
+            self.write_instance(child, &mut signals, w)?;
+        }
+
+        for (from, to) in aliases {
+            writeln!(w, ".names {} {}", from, to)?;
+            writeln!(w, "1 1")?;
+        }
+
+        writeln!(w, ".end")?;
+
+        for (_, child) in children {
+            if !child.is_leaf() {
+                writeln!(w)?;
+                self.write_model(child, w)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_instance<W: Write>(
+        &mut self,
+        inst: &Instance,
+        signals: &mut Signals,
+        w: &mut W,
+    ) -> Result<()> {
+        match inst.primitive_kind() {
+            Some(PrimitiveKind::Lut(_)) => return self.write_lut(inst, signals, w),
+            Some(PrimitiveKind::Gnd) => {
+                writeln!(w, ".names {}", signals.pin(inst, "G")?)?;
+                return Ok(());
+            }
+            Some(PrimitiveKind::Vcc) => {
+                writeln!(w, ".names {}", signals.pin(inst, "P")?)?;
+                writeln!(w, "1")?;
+                return Ok(());
+            }
+            Some(kind) if kind.is_buffer() || kind == PrimitiveKind::Inv => {
+                let i = signals.pin(inst, "I")?;
+                let o = signals.pin(inst, "O")?;
+                writeln!(w, ".names {} {}", i, o)?;
+                writeln!(w, "{} 1", if kind == PrimitiveKind::Inv { 0 } else { 1 })?;
+                return Ok(());
+            }
+            Some(PrimitiveKind::Fdre) | Some(PrimitiveKind::Fdse) => {
+                return self.write_latch(inst, signals, w)
+            }
+            _ if inst.is_leaf() => {
+                self.stubs
+                    .entry(inst.cell.clone())
+                    .or_insert_with(|| sorted_ports(inst).into_iter().cloned().collect());
+            }
+            _ => {}
+        }
+
+        let name = inst.path.name();
+        write!(w, ".subckt {}", inst.cell)?;
+        for port in sorted_ports(inst) {
+            for bit in 0..port.kind.width() {
+                let member = port.kind.member_of_bit(bit);
+                let s = signals.get(Some(&name), &port.name.name, member);
+                write!(w, " {}={}", bit_name(port, bit), s)?;
+            }
+        }
+        writeln!(w)?;
+
+        Ok(())
+    }
+
+    fn write_lut<W: Write>(
+        &mut self,
+        inst: &Instance,
+        signals: &mut Signals,
+        w: &mut W,
+    ) -> Result<()> {
+        let table = inst
+            .truth_table()?
+            .ok_or_else(|| anyhow!("`{}` is not a LUT", inst.path))?;
+        let k = table.inputs();
+
+        write!(w, ".names")?;
+        for i in 0..k {
+            write!(w, " {}", signals.pin(inst, &format!("I{}", i))?)?;
+        }
+        writeln!(w, " {}", signals.pin(inst, "O")?)?;
+
+        for m in (0..1 << k).filter(|&m| table.eval_index(m)) {
+            let row = (0..k)
+                .map(|i| if m >> i & 1 == 1 { '1' } else { '0' })
+                .collect::<String>();
+            writeln!(w, "{} 1", row)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_latch<W: Write>(
+        &mut self,
+        inst: &Instance,
+        signals: &mut Signals,
+        w: &mut W,
+    ) -> Result<()> {
+        let set = inst.primitive_kind() == Some(PrimitiveKind::Fdse);
+        let init = inst.bit_property("INIT", set)?;
+
+        let c = signals.pin(inst, "C")?;
+        let ce = signals.pin(inst, "CE")?;
+        let d = signals.pin(inst, "D")?;
+        let sr = signals.pin(inst, if set { "S" } else { "R" })?;
+        let q = signals.pin(inst, "Q")?;
+        let next = format!("{}$next", inst.path.name());
+
+        // next = SR ? SET : (CE ? D : Q)
+        writeln!(w, ".names {} {} {} {} {}", sr, ce, d, q, next)?;
+        if set {
+            writeln!(w, "1--- 1")?;
+        }
+        writeln!(w, "011- 1")?;
+        writeln!(w, "00-1 1")?;
+        writeln!(w, ".latch {} {} re {} {}", next, q, c, init as u8)?;
+
+        Ok(())
+    }
+}
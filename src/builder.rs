@@ -0,0 +1,445 @@
+//! Construction of an [`Edif`] from scratch.
+//!
+//! [`EdifBuilder`] adds libraries, cells, and the ports, instances and nets of the current
+//! cell, checking each name and reference as it goes. The first error is kept and returned by
+//! [`build`](EdifBuilder::build), with the cell it occurred in. The names of ports, instances
+//! and nets are [legalized](crate::mangle), keeping the original in a `rename`, and pins refer
+//! to them by the original.
+//!
+//! ```
+//! use edif::ast::{Direction::*, Property};
+//! use edif::builder::{EdifBuilder, Pin};
+//! use edif::netlist::Netlist;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let edif = EdifBuilder::new("top")
+//!     .external("hdi_primitives")
+//!     .cell("LUT2")
+//!     .port("I0", Input, 1)
+//!     .port("I1", Input, 1)
+//!     .port("O", Output, 1)
+//!     .library("work")
+//!     .cell("top")
+//!     .port("a", Input, 2)
+//!     .port("y", Output, 1)
+//!     .instance_of("y_i", "hdi_primitives", "LUT2")
+//!     .property("INIT", Property::String("4'h8".to_string()))
+//!     .net("a[0]", [Pin::port("a").bit(0), Pin::of("y_i", "I0")])
+//!     .net("a[1]", [Pin::port("a").bit(1), Pin::of("y_i", "I1")])
+//!     .net("y", [Pin::of("y_i", "O"), Pin::port("y")])
+//!     .build()?;
+//! Netlist::from_ast(&edif).verify_references()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ast::{
+    Cell, Content, Design, Direction, Edif, FxIndexMap, Instance, Interface, Library, Name, Net,
+    Port, PortKind, PortRef, Property, Span, View,
+};
+use crate::atom::Atom;
+use crate::mangle::{self, Scope};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use fxhash::FxHashMap;
+use std::sync::Arc;
+
+/// A pin of a net: a port of the cell itself or of one of its instances, or a bit of either.
+#[derive(Clone, Debug)]
+pub struct Pin {
+    instance: Option<String>,
+    port: String,
+    bit: Option<i32>,
+}
+
+impl Pin {
+    /// The port `port` of the cell being built.
+    pub fn port(port: &str) -> Self {
+        Pin {
+            instance: None,
+            port: port.to_string(),
+            bit: None,
+        }
+    }
+
+    /// The port `port` of the instance `instance`.
+    pub fn of(instance: &str, port: &str) -> Self {
+        Pin {
+            instance: Some(instance.to_string()),
+            port: port.to_string(),
+            bit: None,
+        }
+    }
+
+    /// The `bit`-th least significant bit of an array port.
+    pub fn bit(self, bit: i32) -> Self {
+        Pin {
+            bit: Some(bit),
+            ..self
+        }
+    }
+}
+
+/// Builds an [`Edif`] whose design is the cell named like it.
+pub struct EdifBuilder {
+    name: Atom,
+    libs: FxIndexMap<Atom, Library>,
+    lib: Option<Atom>,
+    cell: Option<Atom>,
+    /// The index in the contents of the instance the properties are added to.
+    instance: Option<usize>,
+    /// The port names of each cell.
+    ports: FxHashMap<(Atom, Atom), Scope>,
+    /// The instance and net names of the current cell.
+    instances: Scope,
+    nets: Scope,
+    /// The net each pin of the current cell is connected to.
+    connected: FxHashMap<(Option<Atom>, Atom, Option<i32>), Atom>,
+    error: Option<anyhow::Error>,
+}
+
+/// Checks that `name` is an EDIF identifier.
+fn identifier(name: &str) -> Result<Atom> {
+    ensure!(
+        mangle::is_identifier(name),
+        "`{}` is not an EDIF identifier",
+        name
+    );
+    Ok(Atom::from(name))
+}
+
+fn find_port<'a>(cell: &'a Cell, port: &Atom) -> Option<&'a Port> {
+    cell.view
+        .interface
+        .ports
+        .iter()
+        .find(|p| p.name.name == *port)
+}
+
+impl EdifBuilder {
+    pub fn new(name: &str) -> Self {
+        let mut builder = EdifBuilder {
+            name: Atom::from(name),
+            libs: FxIndexMap::default(),
+            lib: None,
+            cell: None,
+            instance: None,
+            ports: FxHashMap::default(),
+            instances: Scope::default(),
+            nets: Scope::default(),
+            connected: FxHashMap::default(),
+            error: None,
+        };
+        if let Err(e) = identifier(name) {
+            builder.error = Some(e);
+        }
+        builder
+    }
+
+    /// Runs `f` unless an earlier step failed, keeping its error.
+    fn step(mut self, f: impl FnOnce(&mut Self) -> Result<()>) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = f(&mut self) {
+                let e = match (&self.lib, &self.cell) {
+                    (Some(lib), Some(cell)) => e.context(format!("in cell `{}/{}`", lib, cell)),
+                    (Some(lib), None) => e.context(format!("in library `{}`", lib)),
+                    _ => e,
+                };
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    fn open_library(self, name: &str, external: bool) -> Self {
+        self.step(|b| {
+            let name = identifier(name)?;
+            let lib = b.libs.entry(name.clone()).or_insert_with(|| Library {
+                name: name.clone(),
+                external,
+                cells: FxIndexMap::default(),
+                span: Span::default(),
+            });
+            ensure!(
+                lib.external == external,
+                "library `{}` is already open as {}",
+                name,
+                if lib.external {
+                    "external"
+                } else {
+                    "a library"
+                }
+            );
+            b.lib = Some(name);
+            b.cell = None;
+            Ok(())
+        })
+    }
+
+    /// Adds the library `name`, or goes back to it, for the cells that follow.
+    pub fn library(self, name: &str) -> Self {
+        self.open_library(name, false)
+    }
+
+    /// Adds the external library `name`, whose cells are black boxes, or goes back to it.
+    pub fn external(self, name: &str) -> Self {
+        self.open_library(name, true)
+    }
+
+    /// Adds the cell `name` to the current library, for the ports, instances and nets that
+    /// follow.
+    pub fn cell(self, name: &str) -> Self {
+        self.step(|b| {
+            let name = identifier(name)?;
+            let lib = b
+                .lib
+                .as_ref()
+                .ok_or_else(|| anyhow!("no library for cell `{}`", name))?;
+            let cells = &mut b.libs[lib].cells;
+            ensure!(!cells.contains_key(&name), "cell `{}` already exists", name);
+            cells.insert(
+                name.clone(),
+                Cell {
+                    name: name.clone(),
+                    view: View {
+                        name: Atom::from("netlist"),
+                        interface: Interface {
+                            ports: vec![],
+                            span: Span::default(),
+                        },
+                        contents: vec![],
+                        span: Span::default(),
+                    },
+                    span: Span::default(),
+                },
+            );
+            b.ports
+                .insert((lib.clone(), name.clone()), Scope::default());
+            b.cell = Some(name);
+            b.instance = None;
+            b.instances = Scope::default();
+            b.nets = Scope::default();
+            b.connected.clear();
+            Ok(())
+        })
+    }
+
+    /// The library and name of the current cell.
+    fn current(&self) -> Result<(Atom, Atom)> {
+        match (&self.lib, &self.cell) {
+            (Some(lib), Some(cell)) => Ok((lib.clone(), cell.clone())),
+            _ => bail!("no cell"),
+        }
+    }
+
+    fn cell_mut(&mut self) -> Result<&mut Cell> {
+        let (lib, cell) = self.current()?;
+        Ok(&mut self.libs[&lib].cells[&cell])
+    }
+
+    /// Adds the port `name` of `width` bits, an array unless it is 1, to the current cell.
+    pub fn port(self, name: &str, dir: Direction, width: i32) -> Self {
+        self.step(|b| {
+            let orig = Atom::from(name);
+            ensure!(width > 0, "port `{}` has a width of {}", name, width);
+            let (lib, cell) = b.current()?;
+            let scope = b.ports.get_mut(&(lib, cell)).unwrap();
+            ensure!(scope.get(&orig).is_none(), "port `{}` already exists", name);
+            let name = scope.name(&orig);
+            b.cell_mut()?.view.interface.ports.push(Port {
+                kind: match width {
+                    1 => PortKind::Single,
+                    n => PortKind::Array(n),
+                },
+                dir,
+                name,
+                span: Span::default(),
+            });
+            Ok(())
+        })
+    }
+
+    /// Adds an instance of the cell `cell` of the current library.
+    pub fn instance(self, name: &str, cell: &str) -> Self {
+        match self.lib.clone() {
+            Some(lib) => self.instance_of(name, &lib, cell),
+            None => self.step(|_| bail!("no library for instance `{}`", name)),
+        }
+    }
+
+    /// Adds an instance of the cell `cell` of the library `lib`, which must be defined before.
+    pub fn instance_of(self, name: &str, lib: &str, cell: &str) -> Self {
+        self.step(|b| {
+            let orig = Atom::from(name);
+            let (lib, cellref) = (Atom::from(lib), Atom::from(cell));
+            ensure!(
+                b.libs
+                    .get(&lib)
+                    .is_some_and(|l| l.cells.contains_key(&cellref)),
+                "cell `{}/{}` of instance `{}` is not defined",
+                lib,
+                cellref,
+                name
+            );
+            ensure!(
+                b.lib.as_ref() != Some(&lib) || b.cell.as_ref() != Some(&cellref),
+                "cell `{}` cannot instantiate itself",
+                cellref
+            );
+            ensure!(
+                b.instances.get(&orig).is_none(),
+                "instance `{}` already exists",
+                name
+            );
+            let name = b.instances.name(&orig);
+            let contents = &mut b.cell_mut()?.view.contents;
+            contents.push(Content::Instance(Instance {
+                name,
+                cellref,
+                viewref: Atom::from("netlist"),
+                libraryref: Some(lib),
+                properties: Arc::default(),
+                property_spans: FxIndexMap::default(),
+                span: Span::default(),
+            }));
+            b.instance = Some(contents.len() - 1);
+            Ok(())
+        })
+    }
+
+    /// Sets the property `name` of the last instance added.
+    pub fn property(self, name: &str, value: Property) -> Self {
+        self.step(|b| {
+            let name = identifier(name)?;
+            let i = b
+                .instance
+                .ok_or_else(|| anyhow!("no instance for property `{}`", name))?;
+            let inst = match &mut b.cell_mut()?.view.contents[i] {
+                Content::Instance(inst) => inst,
+                Content::Net(_) => unreachable!(),
+            };
+            let props = Arc::make_mut(&mut inst.properties);
+            let name = Name {
+                name,
+                rename_from: None,
+            };
+            ensure!(
+                !props.contains_key(&name),
+                "property `{}` of `{}` already exists",
+                name.name,
+                inst.name.name
+            );
+            props.insert(name, value);
+            Ok(())
+        })
+    }
+
+    /// The port reference of `pin` in the current cell.
+    fn portref(&self, pin: &Pin) -> Result<PortRef> {
+        let lib = self.lib.as_ref().unwrap();
+        let cell = &self.libs[lib].cells[self.cell.as_ref().unwrap()];
+        let (instance, lib, def) = match &pin.instance {
+            None => (None, lib, cell),
+            Some(name) => {
+                let ident = self
+                    .instances
+                    .get(&Atom::from(name.as_str()))
+                    .ok_or_else(|| anyhow!("instance `{}` not found", name))?;
+                let inst = cell
+                    .view
+                    .contents
+                    .iter()
+                    .find_map(|c| match c {
+                        Content::Instance(inst) if inst.name.name == *ident => Some(inst),
+                        _ => None,
+                    })
+                    .unwrap();
+                let lib = inst.libraryref.as_ref().unwrap_or(lib);
+                (
+                    Some(ident.clone()),
+                    lib,
+                    &self.libs[lib].cells[&inst.cellref],
+                )
+            }
+        };
+        let port = self.ports[&(lib.clone(), def.name.clone())]
+            .get(&Atom::from(pin.port.as_str()))
+            .ok_or_else(|| anyhow!("cell `{}` has no port `{}`", def.name, pin.port))?
+            .clone();
+        let def_port = find_port(def, &port).unwrap();
+        let member = match (def_port.kind, pin.bit) {
+            (PortKind::Single, None) => None,
+            (PortKind::Array(n), Some(bit)) if (0..n).contains(&bit) => {
+                def_port.kind.member_of_bit(bit)
+            }
+            (PortKind::Array(n), Some(bit)) => {
+                bail!("bit {} of port `{}` is out of {} bits", bit, pin.port, n)
+            }
+            (PortKind::Array(_), None) => {
+                bail!("port `{}` is an array, and needs a bit", pin.port)
+            }
+            (PortKind::Single, Some(_)) => bail!("port `{}` is not an array", pin.port),
+        };
+        Ok(PortRef {
+            port,
+            member,
+            instance_ref: instance,
+            span: Span::default(),
+        })
+    }
+
+    /// Adds the net `name` connecting `pins` in the current cell. A pin can be connected to one
+    /// net only.
+    pub fn net(self, name: &str, pins: impl IntoIterator<Item = Pin>) -> Self {
+        self.step(|b| {
+            let orig = Atom::from(name);
+            b.current()?;
+            ensure!(b.nets.get(&orig).is_none(), "net `{}` already exists", name);
+
+            let mut portrefs = vec![];
+            for pin in pins {
+                let p = b
+                    .portref(&pin)
+                    .with_context(|| format!("in net `{}`", name))?;
+                let key = (p.instance_ref.clone(), p.port.clone(), p.member);
+                if let Some(other) = b.connected.insert(key, orig.clone()) {
+                    bail!(
+                        "pin `{}` of net `{}` is already connected to net `{}`",
+                        pin.port,
+                        name,
+                        other
+                    );
+                }
+                portrefs.push(p);
+            }
+            let name = b.nets.name(&orig);
+            b.cell_mut()?.view.contents.push(Content::Net(Net {
+                name,
+                portrefs,
+                span: Span::default(),
+            }));
+            Ok(())
+        })
+    }
+
+    /// The document, or the first error.
+    pub fn build(self) -> Result<Edif> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let lib = self
+            .libs
+            .values()
+            .find(|l| l.cells.contains_key(&self.name))
+            .ok_or_else(|| anyhow!("design cell `{}` is not defined", self.name))?;
+        let design = Design {
+            inst_name: self.name.clone(),
+            cellref: self.name.clone(),
+            libraryref: lib.name.clone(),
+            span: Span::default(),
+        };
+        Ok(Edif {
+            libs: self.libs,
+            design,
+        })
+    }
+}
@@ -0,0 +1,893 @@
+//! Binary snapshots of a [`Netlist`](crate::netlist::Netlist), which load without parsing the
+//! EDIF text again.
+//!
+//! A snapshot starts with a header: the magic `EDIFNL`, the format [`VERSION`] and the
+//! [`source_hash`] of the EDIF it was made from, which tells whether it is stale. A table of
+//! the names follows, and then the instance tree, where names are varint IDs into the table,
+//! and spans are relative to the previous one.
+//!
+//! With the `mmap` feature, [`Mapped`] reads a snapshot from a memory-mapped file, giving
+//! read-only access to its instances, nets and properties in place, without loading the
+//! whole netlist.
+
+use crate::ast::{Direction, FxIndexMap, FxIndexSet, Number, Port, PortKind, Property, Span};
+use crate::atom::Atom;
+use crate::netlist::{Instance, Net, Netlist, Path, PortRef};
+use anyhow::{bail, ensure, Context, Result};
+use fxhash::FxHashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fs;
+use std::io::Write;
+use std::path::Path as FsPath;
+
+const MAGIC: &[u8; 6] = b"EDIFNL";
+
+/// The version of the format, bumped on every incompatible change.
+pub const VERSION: u32 = 6;
+
+/// The hash of an EDIF source, recorded in the snapshots made from it.
+pub fn source_hash(source: &[u8]) -> u64 {
+    fxhash::hash64(source)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub source_hash: u64,
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn zigzag(v: i32) -> u64 {
+    ((v << 1) ^ (v >> 31)) as u32 as u64
+}
+
+fn unzigzag(v: u64) -> i32 {
+    let v = v as u32;
+    ((v >> 1) as i32) ^ -((v & 1) as i32)
+}
+
+fn zigzag64(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag64(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+struct Encoder<'a> {
+    ids: FxHashMap<&'a str, u64>,
+    strings: Vec<&'a str>,
+    out: Vec<u8>,
+    /// The last span written, which the next is encoded relative to.
+    span: Span,
+}
+
+impl<'a> Encoder<'a> {
+    fn varint(&mut self, v: u64) {
+        write_varint(&mut self.out, v);
+    }
+
+    fn intern(&mut self, s: &'a str) -> u64 {
+        let next = self.strings.len() as u64;
+        let id = *self.ids.entry(s).or_insert(next);
+        if id == next {
+            self.strings.push(s);
+        }
+        id
+    }
+
+    fn string(&mut self, s: &'a str) {
+        let id = self.intern(s);
+        self.varint(id);
+    }
+
+    /// An optional string, as 0 or the ID plus one.
+    fn opt_string(&mut self, s: Option<&'a str>) {
+        let id = s.map_or(0, |s| self.intern(s) + 1);
+        self.varint(id);
+    }
+
+    fn span(&mut self, span: Span) {
+        self.varint(zigzag64(span.start as i64 - self.span.start as i64));
+        self.varint(span.end.saturating_sub(span.start) as u64);
+        self.varint(zigzag64(span.line as i64 - self.span.line as i64));
+        self.varint(span.column as u64);
+        self.span = span;
+    }
+
+    fn port(&mut self, port: &'a Port) {
+        self.string(&port.name.name);
+        self.opt_string(port.name.rename_from.as_deref());
+        self.out.push(match port.dir {
+            Direction::Input => 0,
+            Direction::Output => 1,
+            Direction::InOut => 2,
+        });
+        match port.kind {
+            PortKind::Single => self.varint(0),
+            PortKind::Array(n) => self.varint(n as u64 + 1),
+        }
+        self.span(port.span);
+    }
+
+    fn property(&mut self, value: &'a Property) {
+        match value {
+            Property::String(s) => {
+                self.out.push(0);
+                self.string(s);
+            }
+            Property::Integer(i) => {
+                self.out.push(1);
+                self.varint(zigzag64(*i));
+            }
+            Property::Boolean(b) => self.out.push(2 + *b as u8),
+            Property::Number(n) => {
+                self.out.push(4);
+                self.varint(zigzag64(n.mantissa));
+                self.varint(zigzag(n.exponent));
+            }
+            Property::List(l) => {
+                self.out.push(5);
+                self.varint(l.len() as u64);
+                for p in l {
+                    self.property(p);
+                }
+            }
+        }
+    }
+
+    fn instance(&mut self, inst: &'a Instance) -> Result<()> {
+        self.string(&inst.lib);
+        self.string(&inst.cell);
+        self.opt_string(inst.rename_from.as_deref());
+        self.out.push(inst.black_box as u8);
+        self.span(inst.span);
+
+        // Everything in map order, which the loader restores.
+        self.varint(inst.interface.len() as u64);
+        for port in inst.interface.values() {
+            self.port(port);
+        }
+
+        self.varint(inst.properties.len() as u64);
+        for (name, value) in &inst.properties {
+            self.string(name);
+            self.property(value);
+        }
+        self.varint(inst.property_spans.len() as u64);
+        for (name, span) in &inst.property_spans {
+            self.string(name);
+            self.span(*span);
+        }
+
+        self.varint(inst.instances.len() as u64);
+        for (name, child) in &inst.instances {
+            ensure!(
+                child.path == inst.path.child(name.clone()),
+                "instance `{}` is stored as `{}`",
+                child.path,
+                name
+            );
+            self.string(name);
+            self.instance(child)?;
+        }
+
+        self.varint(inst.nets.len() as u64);
+        for (name, net) in &inst.nets {
+            self.string(name);
+            self.opt_string(net.rename_from.as_deref());
+            self.span(net.span);
+            self.varint(net.ports.len() as u64);
+            for p in &net.ports {
+                // The instance is the scope itself, or one of its children.
+                if p.instance == inst.path {
+                    self.varint(0);
+                } else if p.instance.parent().as_ref() == Some(&inst.path) {
+                    self.opt_string(Some(&p.instance.as_slice()[p.instance.len() - 1]));
+                } else {
+                    bail!(
+                        "net `{}` of `{}` refers to `{}`",
+                        name,
+                        inst.path,
+                        p.instance
+                    );
+                }
+                self.string(&p.port);
+                match p.member {
+                    Some(m) => self.varint(zigzag(m) + 1),
+                    None => self.varint(0),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a snapshot of `netlist`, made from an EDIF source of the hash `source_hash`.
+pub fn save<W: Write>(netlist: &Netlist, source_hash: u64, mut w: W) -> Result<()> {
+    let mut enc = Encoder {
+        ids: FxHashMap::default(),
+        strings: vec![],
+        out: vec![],
+        span: Span::default(),
+    };
+    let top = &netlist.top;
+    enc.varint(top.path.len() as u64);
+    for c in top.path.as_slice() {
+        enc.string(c);
+    }
+    enc.instance(top)?;
+
+    let mut head = MAGIC.to_vec();
+    head.extend_from_slice(&VERSION.to_le_bytes());
+    head.extend_from_slice(&source_hash.to_le_bytes());
+    write_varint(&mut head, enc.strings.len() as u64);
+    for s in &enc.strings {
+        write_varint(&mut head, s.len() as u64);
+        head.extend_from_slice(s.as_bytes());
+    }
+    w.write_all(&head)?;
+    w.write_all(&enc.out)?;
+    Ok(())
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Decoder { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        ensure!(self.bytes.len() - self.pos >= n, "truncated snapshot");
+        let s = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut v = 0;
+        for shift in (0..64).step_by(7) {
+            let b = self.u8()?;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b < 0x80 {
+                return Ok(v);
+            }
+        }
+        bail!("malformed varint at byte {}", self.pos)
+    }
+
+    fn header(&mut self) -> Result<Header> {
+        ensure!(self.take(MAGIC.len())? == MAGIC, "not a netlist snapshot");
+        let version = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        let source_hash = u64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        Ok(Header {
+            version,
+            source_hash,
+        })
+    }
+
+    fn strings(&mut self) -> Result<Vec<&'a str>> {
+        let n = self.varint()? as usize;
+        let mut strings = Vec::with_capacity(n.min(self.bytes.len()));
+        for _ in 0..n {
+            let len = self.varint()? as usize;
+            strings.push(std::str::from_utf8(self.take(len)?).context("malformed name")?);
+        }
+        Ok(strings)
+    }
+}
+
+/// Decodes a property, looking its strings up with `string`.
+fn property<'a>(
+    de: &mut Decoder<'_>,
+    string: &dyn Fn(usize) -> Result<&'a str>,
+) -> Result<Property> {
+    Ok(match de.u8()? {
+        0 => Property::String(string(de.varint()? as usize)?.to_string()),
+        1 => Property::Integer(unzigzag64(de.varint()?)),
+        2 => Property::Boolean(false),
+        3 => Property::Boolean(true),
+        4 => Property::Number(Number {
+            mantissa: unzigzag64(de.varint()?),
+            exponent: unzigzag(de.varint()?),
+        }),
+        5 => {
+            let len = de.varint()? as usize;
+            let l = (0..len)
+                .map(|_| property(de, string))
+                .collect::<Result<_>>()?;
+            Property::List(l)
+        }
+        t => bail!("invalid property tag {}", t),
+    })
+}
+
+/// Reads the header of the snapshot `bytes`.
+pub fn header(bytes: &[u8]) -> Result<Header> {
+    Decoder::new(bytes).header()
+}
+
+struct Loader<'a> {
+    de: Decoder<'a>,
+    strings: Vec<&'a str>,
+    atoms: Vec<Option<Atom>>,
+    span: Span,
+}
+
+impl Loader<'_> {
+    fn id(&mut self) -> Result<usize> {
+        let id = self.de.varint()? as usize;
+        ensure!(id < self.strings.len(), "name ID {} out of range", id);
+        Ok(id)
+    }
+
+    fn atom(&mut self) -> Result<Atom> {
+        let id = self.id()?;
+        let strings = &self.strings;
+        Ok(self.atoms[id]
+            .get_or_insert_with(|| Atom::from(strings[id]))
+            .clone())
+    }
+
+    fn opt_string(&mut self) -> Result<Option<String>> {
+        match self.de.varint()? {
+            0 => Ok(None),
+            id => {
+                let s = self.strings.get(id as usize - 1);
+                Ok(Some(s.context("name ID out of range")?.to_string()))
+            }
+        }
+    }
+
+    fn span(&mut self) -> Result<Span> {
+        let start = (self.span.start as i64)
+            .checked_add(unzigzag64(self.de.varint()?))
+            .and_then(|s| usize::try_from(s).ok())
+            .context("span start out of range")?;
+        let end = usize::try_from(self.de.varint()?)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .context("span end out of range")?;
+        let line = (self.span.line as i64)
+            .checked_add(unzigzag64(self.de.varint()?))
+            .and_then(|l| i32::try_from(l).ok())
+            .context("span line out of range")?;
+        let column = i32::try_from(self.de.varint()?).context("span column out of range")?;
+        self.span = Span {
+            start,
+            end,
+            line,
+            column,
+        };
+        Ok(self.span)
+    }
+
+    fn port(&mut self) -> Result<Port> {
+        let name = self.atom()?;
+        let rename_from = self.opt_string()?;
+        let dir = match self.de.u8()? {
+            0 => Direction::Input,
+            1 => Direction::Output,
+            2 => Direction::InOut,
+            d => bail!("invalid direction {}", d),
+        };
+        let kind = match self.de.varint()? {
+            0 => PortKind::Single,
+            n => PortKind::Array(i32::try_from(n - 1).context("port width out of range")?),
+        };
+        Ok(Port {
+            kind,
+            dir,
+            name: crate::ast::Name { name, rename_from },
+            span: self.span()?,
+        })
+    }
+
+    fn property(&mut self) -> Result<Property> {
+        let strings = &self.strings;
+        property(&mut self.de, &|id| {
+            strings
+                .get(id)
+                .copied()
+                .with_context(|| format!("name ID {} out of range", id))
+        })
+    }
+
+    fn instance(&mut self, path: Path) -> Result<Instance> {
+        let lib = self.atom()?;
+        let cell = self.atom()?;
+        let rename_from = self.opt_string()?;
+        let black_box = self.de.u8()? != 0;
+        let span = self.span()?;
+
+        let mut interface = FxIndexMap::default();
+        for _ in 0..self.de.varint()? {
+            let port = self.port()?;
+            interface.insert(port.name.name.clone(), port);
+        }
+
+        let mut properties = FxIndexMap::default();
+        for _ in 0..self.de.varint()? {
+            let name = self.atom()?;
+            properties.insert(name, self.property()?);
+        }
+        let mut property_spans = FxIndexMap::default();
+        for _ in 0..self.de.varint()? {
+            let name = self.atom()?;
+            property_spans.insert(name, self.span()?);
+        }
+
+        let mut instances = FxIndexMap::default();
+        for _ in 0..self.de.varint()? {
+            let name = self.atom()?;
+            let child = self.instance(path.child(name.clone()))?;
+            instances.insert(name, child);
+        }
+
+        let mut nets = FxIndexMap::default();
+        for _ in 0..self.de.varint()? {
+            let name = self.atom()?;
+            let rename_from = self.opt_string()?;
+            let span = self.span()?;
+            let n = self.de.varint()?;
+            let mut ports = FxIndexSet::default();
+            for _ in 0..n {
+                let instance = match self.de.varint()? {
+                    0 => path.clone(),
+                    id => {
+                        let id = id as usize - 1;
+                        ensure!(id < self.strings.len(), "name ID {} out of range", id);
+                        path.child(Atom::from(self.strings[id]))
+                    }
+                };
+                let port = self.atom()?;
+                let member = match self.de.varint()? {
+                    0 => None,
+                    m => Some(unzigzag(m - 1)),
+                };
+                ports.insert(PortRef {
+                    instance,
+                    port,
+                    member,
+                });
+            }
+            nets.insert(
+                name,
+                Net {
+                    ports,
+                    rename_from,
+                    span,
+                },
+            );
+        }
+
+        Ok(Instance {
+            path,
+            instances,
+            nets,
+            interface,
+            lib,
+            cell,
+            properties,
+            property_spans,
+            rename_from,
+            black_box,
+            span,
+        })
+    }
+}
+
+/// Reads the snapshot `bytes`, checking only its version.
+pub fn load(bytes: &[u8]) -> Result<Netlist> {
+    let mut de = Decoder::new(bytes);
+    let header = de.header()?;
+    ensure!(
+        header.version == VERSION,
+        "snapshot version {} is not supported, expected {}",
+        header.version,
+        VERSION
+    );
+    let strings = de.strings()?;
+    let mut loader = Loader {
+        atoms: vec![None; strings.len()],
+        strings,
+        de,
+        span: Span::default(),
+    };
+
+    let len = loader.de.varint()?;
+    ensure!(len > 0, "empty top-level path");
+    let mut path = Path::root(loader.atom()?);
+    for _ in 1..len {
+        path = path.child(loader.atom()?);
+    }
+    let top = loader.instance(path)?;
+    ensure!(loader.de.pos == bytes.len(), "trailing bytes in snapshot");
+    Ok(Netlist { top: Box::new(top) })
+}
+
+/// Loads the netlist of the EDIF file `source` from the snapshot `cache` if it is up to date,
+/// or parses `source` and writes a new snapshot.
+pub fn load_or_parse(source: impl AsRef<FsPath>, cache: impl AsRef<FsPath>) -> Result<Netlist> {
+    let (source, cache) = (source.as_ref(), cache.as_ref());
+    let text = fs::read(source).with_context(|| format!("cannot read {}", source.display()))?;
+    let hash = source_hash(&text);
+    if let Ok(bytes) = fs::read(cache) {
+        let fresh = header(&bytes).is_ok_and(|h| h.version == VERSION && h.source_hash == hash);
+        if fresh {
+            if let Ok(netlist) = load(&bytes) {
+                return Ok(netlist);
+            }
+        }
+    }
+
+    let text = std::str::from_utf8(&text).context("EDIF is not UTF-8")?;
+    let netlist = crate::netlist::from_str(text)?;
+    let mut out = vec![];
+    save(&netlist, hash, &mut out)?;
+    fs::write(cache, out).with_context(|| format!("cannot write {}", cache.display()))?;
+    Ok(netlist)
+}
+
+/// A snapshot in a memory-mapped file.
+///
+/// [`top`](Self::top) and [`instance`](Self::instance) give read-only views of the instance
+/// tree, which decode only what is asked for and borrow the names from the mapping.
+/// [`netlist`](Self::netlist) decodes the whole snapshot into an owned [`Netlist`], like
+/// [`load`].
+#[cfg(feature = "mmap")]
+pub struct Mapped {
+    map: memmap2::Mmap,
+    header: Header,
+    /// The positions and lengths of the names.
+    strings: Vec<(usize, usize)>,
+    /// The position of the instance tree.
+    tree: usize,
+}
+
+#[cfg(feature = "mmap")]
+impl Mapped {
+    /// Maps the snapshot at `path`, which must not be modified while mapped.
+    pub fn open(path: impl AsRef<FsPath>) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            fs::File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+        // Safety: the file is only read, and callers must not modify it while it is mapped.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let mut de = Decoder::new(&map);
+        let header = de.header()?;
+        let strings = de
+            .strings()?
+            .into_iter()
+            .map(|s| (s.as_ptr() as usize - map.as_ptr() as usize, s.len()))
+            .collect();
+        let tree = de.pos;
+        Ok(Mapped {
+            map,
+            header,
+            strings,
+            tree,
+        })
+    }
+
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    /// The bytes of the snapshot.
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    fn string(&self, id: usize) -> Result<&str> {
+        let &(pos, len) = self
+            .strings
+            .get(id)
+            .with_context(|| format!("name ID {} out of range", id))?;
+        // Checked when mapped.
+        Ok(std::str::from_utf8(&self.map[pos..pos + len])?)
+    }
+
+    /// The table of names, borrowed from the mapping.
+    pub fn strings(&self) -> Result<Vec<&str>> {
+        (0..self.strings.len()).map(|id| self.string(id)).collect()
+    }
+
+    fn cursor(&self, pos: usize) -> Cursor<'_> {
+        Cursor {
+            mapped: self,
+            de: Decoder {
+                bytes: &self.map,
+                pos,
+            },
+        }
+    }
+
+    /// The top-level instance.
+    pub fn top(&self) -> Result<MappedInstance<'_>> {
+        ensure!(
+            self.header.version == VERSION,
+            "snapshot version {} is not supported, expected {}",
+            self.header.version,
+            VERSION
+        );
+        let mut c = self.cursor(self.tree);
+        let len = c.de.varint()?;
+        ensure!(len > 0, "empty top-level path");
+        let mut name = "";
+        for _ in 0..len {
+            name = c.name()?;
+        }
+        c.instance(name)
+    }
+
+    /// The instance at `path`, if any.
+    pub fn instance(&self, path: &Path) -> Result<Option<MappedInstance<'_>>> {
+        let top = self.top()?;
+        let (root, rest) = path.as_slice().split_first().unwrap();
+        if **root != *top.name {
+            return Ok(None);
+        }
+        let mut inst = top;
+        for name in rest {
+            inst = match inst.instance(name)? {
+                Some(child) => child,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(inst))
+    }
+
+    /// Decodes the netlist, copying its names and nodes out of the mapping.
+    pub fn netlist(&self) -> Result<Netlist> {
+        load(&self.map)
+    }
+}
+
+/// A read-only view of an instance in a [`Mapped`] snapshot.
+#[cfg(feature = "mmap")]
+#[derive(Clone, Copy)]
+pub struct MappedInstance<'a> {
+    mapped: &'a Mapped,
+    name: &'a str,
+    lib: &'a str,
+    cell: &'a str,
+    black_box: bool,
+    /// The position of the properties.
+    properties: usize,
+}
+
+/// A net of a [`MappedInstance`].
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedNet<'a> {
+    pub name: &'a str,
+    pub pins: Vec<MappedPin<'a>>,
+}
+
+/// A pin of a [`MappedNet`]: a port of the instance containing the net, with no `instance`,
+/// or of one of its children.
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedPin<'a> {
+    pub instance: Option<&'a str>,
+    pub port: &'a str,
+    pub member: Option<i32>,
+}
+
+#[cfg(feature = "mmap")]
+impl<'a> MappedInstance<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn lib(&self) -> &'a str {
+        self.lib
+    }
+
+    pub fn cell(&self) -> &'a str {
+        self.cell
+    }
+
+    pub fn is_black_box(&self) -> bool {
+        self.black_box
+    }
+
+    /// The properties, in order.
+    pub fn properties(&self) -> Result<Vec<(&'a str, Property)>> {
+        let mut c = self.mapped.cursor(self.properties);
+        let len = c.de.varint()?;
+        (0..len).map(|_| Ok((c.name()?, c.property()?))).collect()
+    }
+
+    pub fn property(&self, name: &str) -> Result<Option<Property>> {
+        Ok(self
+            .properties()?
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v))
+    }
+
+    /// A cursor at the children.
+    fn children(&self) -> Result<Cursor<'a>> {
+        let mut c = self.mapped.cursor(self.properties);
+        for _ in 0..c.de.varint()? {
+            c.de.varint()?;
+            c.skip_property()?;
+        }
+        for _ in 0..c.de.varint()? {
+            c.de.varint()?;
+            c.skip_span()?;
+        }
+        Ok(c)
+    }
+
+    /// The child instances, in order.
+    pub fn instances(&self) -> Result<Vec<MappedInstance<'a>>> {
+        let mut c = self.children()?;
+        let len = c.de.varint()?;
+        let mut children = vec![];
+        for _ in 0..len {
+            let name = c.name()?;
+            let child = c.instance(name)?;
+            c.skip_instance_rest(&child)?;
+            children.push(child);
+        }
+        Ok(children)
+    }
+
+    /// The child instance `name`, if any.
+    pub fn instance(&self, name: &str) -> Result<Option<MappedInstance<'a>>> {
+        let mut c = self.children()?;
+        for _ in 0..c.de.varint()? {
+            let child_name = c.name()?;
+            let child = c.instance(child_name)?;
+            if child_name == name {
+                return Ok(Some(child));
+            }
+            c.skip_instance_rest(&child)?;
+        }
+        Ok(None)
+    }
+
+    /// The nets, in order.
+    pub fn nets(&self) -> Result<Vec<MappedNet<'a>>> {
+        let mut c = self.children()?;
+        for _ in 0..c.de.varint()? {
+            let name = c.name()?;
+            let child = c.instance(name)?;
+            c.skip_instance_rest(&child)?;
+        }
+        let len = c.de.varint()?;
+        let mut nets = vec![];
+        for _ in 0..len {
+            let name = c.name()?;
+            c.de.varint()?;
+            c.skip_span()?;
+            let pins = (0..c.de.varint()?)
+                .map(|_| {
+                    let instance = match c.de.varint()? {
+                        0 => None,
+                        id => Some(c.mapped.string(id as usize - 1)?),
+                    };
+                    let port = c.name()?;
+                    let member = match c.de.varint()? {
+                        0 => None,
+                        m => Some(unzigzag(m - 1)),
+                    };
+                    Ok(MappedPin {
+                        instance,
+                        port,
+                        member,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            nets.push(MappedNet { name, pins });
+        }
+        Ok(nets)
+    }
+}
+
+/// Reads the instance tree of a [`Mapped`] snapshot.
+#[cfg(feature = "mmap")]
+struct Cursor<'a> {
+    mapped: &'a Mapped,
+    de: Decoder<'a>,
+}
+
+#[cfg(feature = "mmap")]
+impl<'a> Cursor<'a> {
+    fn name(&mut self) -> Result<&'a str> {
+        self.mapped.string(self.de.varint()? as usize)
+    }
+
+    fn property(&mut self) -> Result<Property> {
+        let mapped = self.mapped;
+        property(&mut self.de, &|id| mapped.string(id))
+    }
+
+    fn skip_property(&mut self) -> Result<()> {
+        match self.de.u8()? {
+            0 | 1 => drop(self.de.varint()?),
+            2 | 3 => {}
+            4 => {
+                self.de.varint()?;
+                self.de.varint()?;
+            }
+            5 => {
+                for _ in 0..self.de.varint()? {
+                    self.skip_property()?;
+                }
+            }
+            t => bail!("invalid property tag {}", t),
+        }
+        Ok(())
+    }
+
+    fn skip_span(&mut self) -> Result<()> {
+        for _ in 0..4 {
+            self.de.varint()?;
+        }
+        Ok(())
+    }
+
+    /// Reads an instance up to its properties.
+    fn instance(&mut self, name: &'a str) -> Result<MappedInstance<'a>> {
+        let lib = self.name()?;
+        let cell = self.name()?;
+        self.de.varint()?;
+        let black_box = self.de.u8()? != 0;
+        self.skip_span()?;
+        for _ in 0..self.de.varint()? {
+            self.de.varint()?;
+            self.de.varint()?;
+            self.de.u8()?;
+            self.de.varint()?;
+            self.skip_span()?;
+        }
+        Ok(MappedInstance {
+            mapped: self.mapped,
+            name,
+            lib,
+            cell,
+            black_box,
+            properties: self.de.pos,
+        })
+    }
+
+    /// Skips the rest of `inst`, from its properties on.
+    fn skip_instance_rest(&mut self, inst: &MappedInstance<'a>) -> Result<()> {
+        *self = inst.children()?;
+        for _ in 0..self.de.varint()? {
+            let name = self.name()?;
+            let child = self.instance(name)?;
+            self.skip_instance_rest(&child)?;
+        }
+        for _ in 0..self.de.varint()? {
+            self.de.varint()?;
+            self.de.varint()?;
+            self.skip_span()?;
+            for _ in 0..self.de.varint()? {
+                self.de.varint()?;
+                self.de.varint()?;
+                self.de.varint()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,511 @@
+//! Structural comparison of two netlists.
+//!
+//! Instances, nets and ports are matched by their paths in the hierarchy. Nets with names
+//! generated by synthesis, such as `p_3_in` or `x_reg_n_0_[0]`, can instead be matched by the
+//! pins they connect, so that renumbering between runs is not reported.
+
+use crate::ast::{self, Direction, FxIndexMap, Port, Property, Span};
+use crate::atom::Atom;
+use crate::netlist::{pin_string, Instance, Net, Netlist, Path, PortRef};
+use fxhash::FxHashSet;
+use serde_json::{json, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Match the nets with generated names by their pins instead of their names.
+    pub ignore_generated_names: bool,
+}
+
+/// A change, with the span of its instance, net, port or property in the new netlist, or in the
+/// old one if it was removed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// An instance, with its descendants, only in the new netlist.
+    InstanceAdded {
+        path: Path,
+        cell: Atom,
+        span: Span,
+    },
+    /// An instance, with its descendants, only in the old netlist.
+    InstanceRemoved {
+        path: Path,
+        cell: Atom,
+        span: Span,
+    },
+    CellChanged {
+        path: Path,
+        from: (Atom, Atom),
+        to: (Atom, Atom),
+        span: Span,
+    },
+    PropertyChanged {
+        path: Path,
+        name: Atom,
+        from: Option<String>,
+        to: Option<String>,
+        span: Span,
+    },
+    NetAdded {
+        net: Path,
+        span: Span,
+    },
+    NetRemoved {
+        net: Path,
+        span: Span,
+    },
+    /// A net connecting different pins. `net` is the path in the new netlist.
+    NetChanged {
+        net: Path,
+        added: Vec<PortRef>,
+        removed: Vec<PortRef>,
+        span: Span,
+    },
+    PortAdded {
+        path: Path,
+        port: Atom,
+        span: Span,
+    },
+    PortRemoved {
+        path: Path,
+        port: Atom,
+        span: Span,
+    },
+    PortChanged {
+        path: Path,
+        port: Atom,
+        from: (Direction, i32),
+        to: (Direction, i32),
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetlistDiff {
+    pub changes: Vec<Change>,
+}
+
+/// Compares two netlists.
+pub fn diff(old: &Netlist, new: &Netlist, options: &DiffOptions) -> NetlistDiff {
+    let mut diff = NetlistDiff::default();
+    diff.instance(&old.top, &new.top, options);
+    diff
+}
+
+/// Compares the netlists of two EDIF documents.
+pub fn diff_edif(old: &ast::Edif, new: &ast::Edif, options: &DiffOptions) -> NetlistDiff {
+    diff(&Netlist::from_ast(old), &Netlist::from_ast(new), options)
+}
+
+/// Whether `name` looks generated by Vivado, as `p_0_in`, `n_1_0` or `x_reg_n_0_[0]`.
+pub fn is_generated_name(name: &str) -> bool {
+    fn numbered(s: &str) -> bool {
+        let digits = s.bytes().take_while(u8::is_ascii_digit).count();
+        digits > 0 && matches!(s.as_bytes().get(digits), None | Some(b'_') | Some(b'['))
+    }
+    let mut rest = name;
+    if let Some(s) = rest.strip_prefix("p_").or_else(|| rest.strip_prefix("n_")) {
+        if numbered(s) {
+            return true;
+        }
+    }
+    while let Some(i) = rest.find("_n_") {
+        rest = &rest[i + 3..];
+        if numbered(rest) {
+            return true;
+        }
+    }
+    false
+}
+
+pub(crate) fn property_string(p: &Property) -> String {
+    match p {
+        Property::String(s) => s.clone(),
+        Property::Integer(i) => i.to_string(),
+        Property::Number(n) => n.to_string(),
+        Property::Boolean(b) => b.to_string(),
+        Property::List(l) => {
+            let l = l.iter().map(property_string).collect::<Vec<_>>();
+            format!("({})", l.join(" "))
+        }
+    }
+}
+
+fn net_name(name: &Atom, net: &Net) -> String {
+    net.rename_from.clone().unwrap_or_else(|| name.to_string())
+}
+
+fn sorted_pins(net: &Net) -> Vec<PortRef> {
+    let mut pins = net.ports.iter().cloned().collect::<Vec<_>>();
+    pins.sort();
+    pins
+}
+
+fn sorted<V>(map: &FxIndexMap<Atom, V>) -> Vec<(&Atom, &V)> {
+    let mut v = map.iter().collect::<Vec<_>>();
+    v.sort_by_key(|(name, _)| *name);
+    v
+}
+
+impl Change {
+    /// The span of the instance, net, port or property changed.
+    pub fn span(&self) -> Span {
+        match self {
+            Change::InstanceAdded { span, .. }
+            | Change::InstanceRemoved { span, .. }
+            | Change::CellChanged { span, .. }
+            | Change::PropertyChanged { span, .. }
+            | Change::NetAdded { span, .. }
+            | Change::NetRemoved { span, .. }
+            | Change::NetChanged { span, .. }
+            | Change::PortAdded { span, .. }
+            | Change::PortRemoved { span, .. }
+            | Change::PortChanged { span, .. } => *span,
+        }
+    }
+}
+
+impl NetlistDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn instance(&mut self, old: &Instance, new: &Instance, options: &DiffOptions) {
+        let path = &new.path;
+
+        if (&old.lib, &old.cell) != (&new.lib, &new.cell) {
+            self.changes.push(Change::CellChanged {
+                path: path.clone(),
+                from: (old.lib.clone(), old.cell.clone()),
+                to: (new.lib.clone(), new.cell.clone()),
+                span: new.span,
+            });
+        }
+
+        let mut names = old
+            .properties
+            .keys()
+            .chain(new.properties.keys())
+            .collect::<FxHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let from = old.properties.get(name).map(property_string);
+            let to = new.properties.get(name).map(property_string);
+            if from != to {
+                let span = new
+                    .property_spans
+                    .get(name)
+                    .or_else(|| old.property_spans.get(name))
+                    .copied()
+                    .unwrap_or(new.span);
+                self.changes.push(Change::PropertyChanged {
+                    path: path.clone(),
+                    name: name.clone(),
+                    from,
+                    to,
+                    span,
+                });
+            }
+        }
+
+        self.interface(path, &old.interface, &new.interface);
+        self.nets(path, old, new, options);
+
+        for (name, child) in sorted(&old.instances) {
+            match new.instances.get(name) {
+                Some(new_child) => self.instance(child, new_child, options),
+                None => self.changes.push(Change::InstanceRemoved {
+                    path: child.path.clone(),
+                    cell: child.cell.clone(),
+                    span: child.span,
+                }),
+            }
+        }
+        for (name, child) in sorted(&new.instances) {
+            if !old.instances.contains_key(name) {
+                self.changes.push(Change::InstanceAdded {
+                    path: child.path.clone(),
+                    cell: child.cell.clone(),
+                    span: child.span,
+                });
+            }
+        }
+    }
+
+    fn interface(
+        &mut self,
+        path: &Path,
+        old: &FxIndexMap<Atom, Port>,
+        new: &FxIndexMap<Atom, Port>,
+    ) {
+        for (name, port) in sorted(old) {
+            match new.get(name) {
+                Some(new_port) => {
+                    let from = (port.dir, port.kind.width());
+                    let to = (new_port.dir, new_port.kind.width());
+                    if from != to {
+                        self.changes.push(Change::PortChanged {
+                            path: path.clone(),
+                            port: name.clone(),
+                            from,
+                            to,
+                            span: new_port.span,
+                        });
+                    }
+                }
+                None => self.changes.push(Change::PortRemoved {
+                    path: path.clone(),
+                    port: name.clone(),
+                    span: port.span,
+                }),
+            }
+        }
+        for (name, port) in sorted(new) {
+            if !old.contains_key(name) {
+                self.changes.push(Change::PortAdded {
+                    path: path.clone(),
+                    port: name.clone(),
+                    span: port.span,
+                });
+            }
+        }
+    }
+
+    fn nets(&mut self, path: &Path, old: &Instance, new: &Instance, options: &DiffOptions) {
+        let generated = |name: &Atom, net: &Net| {
+            options.ignore_generated_names && is_generated_name(&net_name(name, net))
+        };
+
+        // Pairs of the matched nets, and the unmatched ones.
+        let mut pairs = vec![];
+        let mut removed = vec![];
+        let mut added = vec![];
+
+        for (name, net) in sorted(&old.nets) {
+            match new.nets.get(name) {
+                Some(new_net) if !generated(name, net) => pairs.push((name, net, name, new_net)),
+                _ if generated(name, net) => {}
+                _ => removed.push((name, net)),
+            }
+        }
+        for (name, net) in sorted(&new.nets) {
+            if !old.nets.contains_key(name) && !generated(name, net) {
+                added.push((name, net));
+            }
+        }
+
+        // Match the nets with generated names by their pins: first the ones connecting the
+        // same pins, and then the ones sharing any pin.
+        let mut old_gen = sorted(&old.nets)
+            .into_iter()
+            .filter(|(name, net)| generated(name, net))
+            .collect::<Vec<_>>();
+        let mut new_gen = sorted(&new.nets)
+            .into_iter()
+            .filter(|(name, net)| generated(name, net))
+            .collect::<Vec<_>>();
+        let same = |a: &Net, b: &Net| a.ports == b.ports;
+        let overlap = |a: &Net, b: &Net| a.ports.intersection(&b.ports).next().is_some();
+        for matches in [&same as &dyn Fn(&Net, &Net) -> bool, &overlap] {
+            old_gen.retain(|&(name, net)| {
+                match new_gen
+                    .iter()
+                    .position(|(_, new_net)| matches(net, new_net))
+                {
+                    Some(i) => {
+                        let (new_name, new_net) = new_gen.remove(i);
+                        pairs.push((name, net, new_name, new_net));
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+        removed.extend(old_gen);
+        added.extend(new_gen);
+
+        pairs.sort_by_key(|&(_, _, name, _)| name);
+        for (_, old_net, name, new_net) in pairs {
+            let removed = sorted_pins(old_net)
+                .into_iter()
+                .filter(|p| !new_net.ports.contains(p))
+                .collect::<Vec<_>>();
+            let added = sorted_pins(new_net)
+                .into_iter()
+                .filter(|p| !old_net.ports.contains(p))
+                .collect::<Vec<_>>();
+            if !added.is_empty() || !removed.is_empty() {
+                self.changes.push(Change::NetChanged {
+                    net: path.child(name.clone()),
+                    added,
+                    removed,
+                    span: new_net.span,
+                });
+            }
+        }
+
+        removed.sort_by_key(|(name, _)| *name);
+        for (name, net) in removed {
+            self.changes.push(Change::NetRemoved {
+                net: old.path.child(name.clone()),
+                span: net.span,
+            });
+        }
+        added.sort_by_key(|(name, _)| *name);
+        for (name, net) in added {
+            self.changes.push(Change::NetAdded {
+                net: path.child(name.clone()),
+                span: net.span,
+            });
+        }
+    }
+
+    /// The changes as a JSON array of objects with a `"kind"` field, and the `"line"` and
+    /// `"column"` of their span.
+    pub fn to_json(&self) -> Value {
+        let pins = |pins: &[PortRef]| pins.iter().map(pin_string).collect::<Vec<_>>();
+        let dir = |d: Direction| format!("{:?}", d).to_lowercase();
+
+        let change = |c: &Change| match c {
+            Change::InstanceAdded { path, cell, .. } => {
+                json!({"kind": "instance_added", "path": path.to_string(), "cell": &**cell})
+            }
+            Change::InstanceRemoved { path, cell, .. } => {
+                json!({"kind": "instance_removed", "path": path.to_string(), "cell": &**cell})
+            }
+            Change::CellChanged { path, from, to, .. } => json!({
+                "kind": "cell_changed",
+                "path": path.to_string(),
+                "from": {"lib": &*from.0, "cell": &*from.1},
+                "to": {"lib": &*to.0, "cell": &*to.1},
+            }),
+            Change::PropertyChanged {
+                path,
+                name,
+                from,
+                to,
+                ..
+            } => json!({
+                "kind": "property_changed",
+                "path": path.to_string(),
+                "name": &**name,
+                "from": from,
+                "to": to,
+            }),
+            Change::NetAdded { net, .. } => json!({"kind": "net_added", "net": net.to_string()}),
+            Change::NetRemoved { net, .. } => {
+                json!({"kind": "net_removed", "net": net.to_string()})
+            }
+            Change::NetChanged {
+                net,
+                added,
+                removed,
+                ..
+            } => json!({
+                "kind": "net_changed",
+                "net": net.to_string(),
+                "added": pins(added),
+                "removed": pins(removed),
+            }),
+            Change::PortAdded { path, port, .. } => {
+                json!({"kind": "port_added", "path": path.to_string(), "port": &**port})
+            }
+            Change::PortRemoved { path, port, .. } => {
+                json!({"kind": "port_removed", "path": path.to_string(), "port": &**port})
+            }
+            Change::PortChanged {
+                path,
+                port,
+                from,
+                to,
+                ..
+            } => json!({
+                "kind": "port_changed",
+                "path": path.to_string(),
+                "port": &**port,
+                "from": {"direction": dir(from.0), "width": from.1},
+                "to": {"direction": dir(to.0), "width": to.1},
+            }),
+        };
+
+        self.changes
+            .iter()
+            .map(|c| {
+                let mut v = change(c);
+                v["line"] = c.span().line.into();
+                v["column"] = c.span().column.into();
+                v
+            })
+            .collect()
+    }
+}
+
+/// One change per line, marked with `+`, `-` or `~`.
+impl fmt::Display for NetlistDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let opt = |v: &Option<String>| v.clone().unwrap_or_else(|| "(none)".into());
+        for c in &self.changes {
+            match c {
+                Change::InstanceAdded { path, cell, .. } => {
+                    writeln!(f, "+ instance {} ({})", path, cell)?
+                }
+                Change::InstanceRemoved { path, cell, .. } => {
+                    writeln!(f, "- instance {} ({})", path, cell)?
+                }
+                Change::CellChanged { path, from, to, .. } => writeln!(
+                    f,
+                    "~ cell of {}: {}/{} -> {}/{}",
+                    path, from.0, from.1, to.0, to.1
+                )?,
+                Change::PropertyChanged {
+                    path,
+                    name,
+                    from,
+                    to,
+                    ..
+                } => writeln!(
+                    f,
+                    "~ property {} of {}: {} -> {}",
+                    name,
+                    path,
+                    opt(from),
+                    opt(to)
+                )?,
+                Change::NetAdded { net, .. } => writeln!(f, "+ net {}", net)?,
+                Change::NetRemoved { net, .. } => writeln!(f, "- net {}", net)?,
+                Change::NetChanged {
+                    net,
+                    added,
+                    removed,
+                    ..
+                } => {
+                    writeln!(f, "~ net {}", net)?;
+                    for p in added {
+                        writeln!(f, "    + {}", pin_string(p))?;
+                    }
+                    for p in removed {
+                        writeln!(f, "    - {}", pin_string(p))?;
+                    }
+                }
+                Change::PortAdded { path, port, .. } => writeln!(f, "+ port {} of {}", port, path)?,
+                Change::PortRemoved { path, port, .. } => {
+                    writeln!(f, "- port {} of {}", port, path)?
+                }
+                Change::PortChanged {
+                    path,
+                    port,
+                    from,
+                    to,
+                    ..
+                } => writeln!(
+                    f,
+                    "~ port {} of {}: {:?}[{}] -> {:?}[{}]",
+                    port, path, from.0, from.1, to.0, to.1
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+//! Graphviz DOT rendering of a [`Netlist`](crate::netlist::Netlist).
+//!
+//! [`DotMode::Hierarchy`] draws the instance tree as nested clusters, and
+//! [`DotMode::Connectivity`] draws a schematic where each instance is a record node with a
+//! slot for every port, connected by the nets between them.
+
+use crate::ast::Direction;
+use crate::atom::Atom;
+use crate::netlist::{Instance, Netlist, Path, PortRef};
+use fxhash::FxHashMap;
+use std::fmt::Write as _;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DotMode {
+    /// The instance tree, with hierarchical instances drawn as clusters.
+    #[default]
+    Hierarchy,
+    /// Instances as record-shaped nodes with port slots, connected by nets.
+    Connectivity,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DotOptions {
+    pub mode: DotMode,
+    /// The maximum depth of instances to draw, relative to the root instance. Hierarchical
+    /// instances at this depth are drawn as a single node.
+    pub max_depth: Option<usize>,
+    /// Draw only the leaf instances of these cell types. All cells are drawn if empty.
+    pub cells: Vec<Atom>,
+    /// Draw only the instance at this path, as in `main/inner`, and its descendants.
+    pub path_prefix: Option<String>,
+    /// Nets with more pins than this are drawn as a single hub node. Nets driven by constants
+    /// (`GND` and `VCC`) are always drawn as hubs.
+    pub hub_fanout: Option<usize>,
+}
+
+/// Renders `netlist` as a DOT graph.
+pub fn to_dot(netlist: &Netlist, options: &DotOptions) -> anyhow::Result<String> {
+    let root = match &options.path_prefix {
+        Some(path) => netlist
+            .top
+            .walk()
+            .find(|inst| inst.path.to_string() == *path)
+            .ok_or_else(|| anyhow::anyhow!("instance `{}` not found", path))?,
+        None => &*netlist.top,
+    };
+
+    let mut dot = Dot {
+        options,
+        root,
+        out: String::new(),
+        node_ids: FxHashMap::default(),
+    };
+
+    match options.mode {
+        DotMode::Hierarchy => dot.hierarchy(),
+        DotMode::Connectivity => dot.connectivity(netlist)?,
+    }
+
+    Ok(dot.out)
+}
+
+/// Writes `netlist` as a DOT graph.
+pub fn write_dot<W: io::Write>(
+    netlist: &Netlist,
+    options: &DotOptions,
+    mut w: W,
+) -> anyhow::Result<()> {
+    w.write_all(to_dot(netlist, options)?.as_bytes())?;
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if let '"' | '\\' = c {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escapes a field of a record label.
+fn escape_record(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if let '"' | '\\' | '{' | '}' | '|' | '<' | '>' | ' ' = c {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn sorted_children(inst: &Instance) -> Vec<&Instance> {
+    let mut children = inst.instances.values().collect::<Vec<_>>();
+    children.sort_by(|a, b| a.path.cmp(&b.path));
+    children
+}
+
+struct Dot<'a> {
+    options: &'a DotOptions,
+    root: &'a Instance,
+    out: String,
+    node_ids: FxHashMap<Path, usize>,
+}
+
+impl<'a> Dot<'a> {
+    fn depth(&self, inst: &Instance) -> usize {
+        inst.path.len() - self.root.path.len()
+    }
+
+    fn cell_matches(&self, inst: &Instance) -> bool {
+        self.options.cells.is_empty() || self.options.cells.contains(&inst.cell)
+    }
+
+    /// Whether `inst` is drawn as a single node rather than expanded.
+    fn is_node(&self, inst: &Instance) -> bool {
+        inst.is_leaf() || self.options.max_depth == Some(self.depth(inst))
+    }
+
+    fn hierarchy(&mut self) {
+        writeln!(
+            self.out,
+            "digraph \"{}\" {{",
+            escape(&self.root.path.to_string())
+        )
+        .unwrap();
+        writeln!(self.out, "  node [shape=box];").unwrap();
+        self.cluster(self.root, 1);
+        writeln!(self.out, "}}").unwrap();
+    }
+
+    /// Writes `inst` and its descendants, and returns whether anything was written.
+    fn cluster(&mut self, inst: &'a Instance, indent: usize) -> bool {
+        let pad = "  ".repeat(indent);
+
+        if self.depth(inst) > 0 && self.is_node(inst) {
+            if !self.cell_matches(inst) {
+                return false;
+            }
+            let id = self.node_id(inst);
+            writeln!(
+                self.out,
+                "{}n{} [label=\"{}\\n{}\"];",
+                pad,
+                id,
+                escape(&inst.path.name()),
+                escape(&inst.cell)
+            )
+            .unwrap();
+            return true;
+        }
+
+        let start = self.out.len();
+        let id = self.node_id(inst);
+        writeln!(self.out, "{}subgraph cluster_{} {{", pad, id).unwrap();
+        writeln!(
+            self.out,
+            "{}  label=\"{}\\n{}\";",
+            pad,
+            escape(&inst.path.name()),
+            escape(&inst.cell)
+        )
+        .unwrap();
+
+        let mut any = false;
+        for child in sorted_children(inst) {
+            any |= self.cluster(child, indent + 1);
+        }
+
+        if any || self.depth(inst) == 0 {
+            writeln!(self.out, "{}}}", pad).unwrap();
+            true
+        } else {
+            self.out.truncate(start);
+            false
+        }
+    }
+
+    fn node_id(&mut self, inst: &Instance) -> usize {
+        let len = self.node_ids.len();
+        *self.node_ids.entry(inst.path.clone()).or_insert(len)
+    }
+
+    fn connectivity(&mut self, netlist: &'a Netlist) -> anyhow::Result<()> {
+        writeln!(
+            self.out,
+            "digraph \"{}\" {{",
+            escape(&self.root.path.to_string())
+        )
+        .unwrap();
+        writeln!(self.out, "  rankdir=LR;").unwrap();
+        writeln!(self.out, "  node [shape=record];").unwrap();
+
+        // Ports of the root instance.
+        let mut ports = self.root.interface.values().collect::<Vec<_>>();
+        ports.sort_by(|a, b| a.name.name.cmp(&b.name.name));
+        for port in ports {
+            let shape = match port.dir {
+                Direction::Input => "rarrow",
+                Direction::Output => "larrow",
+                Direction::InOut => "diamond",
+            };
+            writeln!(
+                self.out,
+                "  \"port:{}\" [shape={}, label=\"{}\"];",
+                escape(&port.name.name),
+                shape,
+                escape(&port.name.name)
+            )
+            .unwrap();
+        }
+
+        let mut nodes = vec![];
+        let mut stack = vec![self.root];
+        while let Some(inst) = stack.pop() {
+            if self.depth(inst) > 0 && self.is_node(inst) {
+                if self.cell_matches(inst) {
+                    nodes.push(inst);
+                }
+            } else {
+                stack.extend(inst.instances.values());
+            }
+        }
+        nodes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut node_of = FxHashMap::default();
+        for inst in nodes {
+            let id = self.node_id(inst);
+            node_of.insert(inst.path.clone(), inst);
+
+            let mut ports = inst.interface.values().collect::<Vec<_>>();
+            ports.sort_by(|a, b| a.name.name.cmp(&b.name.name));
+            let slots = |dir: &dyn Fn(Direction) -> bool| {
+                ports
+                    .iter()
+                    .filter(|p| dir(p.dir))
+                    .map(|p| format!("<{0}>{0}", escape_record(&p.name.name)))
+                    .collect::<Vec<_>>()
+                    .join("|")
+            };
+            writeln!(
+                self.out,
+                "  n{} [label=\"{{{{{}}}|{}\\n{}|{{{}}}}}\"];",
+                id,
+                slots(&|d| d != Direction::Output),
+                escape_record(&inst.path.name()),
+                escape_record(&inst.cell),
+                slots(&|d| d == Direction::Output),
+            )
+            .unwrap();
+        }
+
+        for (i, net) in netlist.global_nets().into_iter().enumerate() {
+            let mut drivers = vec![];
+            let mut sinks = vec![];
+            let mut is_const = false;
+
+            for p in &net.pins {
+                let (end, dir) = if p.instance == self.root.path {
+                    // Signals come in through the input ports of the root.
+                    let dir = match self.root.pin_port(p)?.dir {
+                        Direction::Input => Direction::Output,
+                        Direction::Output => Direction::Input,
+                        Direction::InOut => Direction::InOut,
+                    };
+                    (format!("\"port:{}\"", escape(&p.port)), dir)
+                } else if let Some(inst) = node_of.get(&p.instance) {
+                    is_const |= inst.primitive_kind().is_some_and(|k| k.is_constant());
+                    (self.pin_end(inst, p), inst.pin_port(p)?.dir)
+                } else {
+                    continue;
+                };
+
+                if dir == Direction::Output {
+                    drivers.push(end);
+                } else {
+                    sinks.push(end);
+                }
+            }
+
+            let len = drivers.len() + sinks.len();
+            if len < 2 {
+                continue;
+            }
+
+            // Name the net after its highest segment drawn in the graph.
+            let segment = net
+                .segments
+                .iter()
+                .find(|s| s.starts_with(&self.root.path))
+                .unwrap_or(&net.segments[0]);
+            let label = escape(&segment.to_string());
+            let is_hub = is_const || self.options.hub_fanout.is_some_and(|f| len > f);
+
+            if drivers.len() == 1 && !is_hub {
+                for sink in sinks {
+                    writeln!(
+                        self.out,
+                        "  {} -> {} [tooltip=\"{}\"];",
+                        drivers[0], sink, label
+                    )
+                    .unwrap();
+                }
+                continue;
+            }
+
+            let shape = if is_hub {
+                format!("shape=diamond, label=\"{}\"", label)
+            } else {
+                format!("shape=point, tooltip=\"{}\"", label)
+            };
+            writeln!(self.out, "  net{} [{}];", i, shape).unwrap();
+            for driver in drivers {
+                writeln!(self.out, "  {} -> net{};", driver, i).unwrap();
+            }
+            for sink in sinks {
+                writeln!(self.out, "  net{} -> {};", i, sink).unwrap();
+            }
+        }
+
+        writeln!(self.out, "}}").unwrap();
+        Ok(())
+    }
+
+    fn pin_end(&self, inst: &Instance, p: &PortRef) -> String {
+        format!("n{}:\"{}\"", self.node_ids[&inst.path], escape(&p.port))
+    }
+}
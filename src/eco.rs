@@ -0,0 +1,599 @@
+//! Checked editing of [`Netlist`](crate::netlist::Netlist)s for ECOs.
+//!
+//! Edits are made through a [`Transaction`], which validates every operation against the
+//! interfaces of the cells involved and can undo all of its edits:
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! # let mut netlist = edif::netlist::from_str("")?;
+//! let top = netlist.top.path.clone();
+//! netlist.edit(|tx| {
+//!     let lut = tx.add_instance(&top, "fix_i", "hdi_primitives", "LUT1", vec![])?;
+//!     let net = tx.create_net(&top, "fix")?;
+//!     tx.connect(&tx.pin(&lut, "O"), &net)
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ast::{Direction, FxIndexMap, FxIndexSet, Port, PortKind, Property};
+use crate::atom::Atom;
+use crate::netlist::{Instance, Net, Netlist, Path, PortRef};
+use crate::primitives;
+use anyhow::{anyhow, bail, ensure, Result};
+
+enum Undo {
+    AddInstance(Path),
+    /// The instance and its pins, with their positions in the scope and the nets.
+    RemoveInstance(Box<Instance>, usize, Vec<(Atom, usize, PortRef)>),
+    CreateNet(Path),
+    Connect(Path, PortRef),
+    Disconnect(Path, usize, PortRef),
+    Rename(Path, Path),
+    SetProperty(Path, Atom, Option<(usize, Property)>),
+    AddPort(Vec<Path>, Atom),
+}
+
+/// A batch of edits of a netlist. Edits that are not [committed](Transaction::commit) are
+/// undone when the transaction is dropped.
+pub struct Transaction<'a> {
+    netlist: &'a mut Netlist,
+    log: Vec<Undo>,
+}
+
+impl Netlist {
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction {
+            netlist: self,
+            log: vec![],
+        }
+    }
+
+    /// Runs `f` in a transaction, which is committed if `f` succeeds and rolled back
+    /// otherwise.
+    pub fn edit<T>(&mut self, f: impl FnOnce(&mut Transaction) -> Result<T>) -> Result<T> {
+        let mut tx = self.transaction();
+        let v = f(&mut tx)?;
+        tx.commit();
+        Ok(v)
+    }
+}
+
+/// `path` with the prefix `from` replaced by `to`.
+fn relocate(path: &Path, from: &Path, to: &Path) -> Path {
+    path.as_slice()[from.len()..]
+        .iter()
+        .fold(to.clone(), |p, name| p.child(name.clone()))
+}
+
+/// Moves `inst` and its descendants from `from` to `to`, rewriting the paths in their nets.
+fn relocate_instance(inst: &mut Instance, from: &Path, to: &Path) {
+    inst.path = relocate(&inst.path, from, to);
+    for net in inst.nets.values_mut() {
+        net.ports = net
+            .ports
+            .drain(..)
+            .map(|mut p| {
+                if p.instance.starts_with(from) {
+                    p.instance = relocate(&p.instance, from, to);
+                }
+                p
+            })
+            .collect();
+    }
+    for child in inst.instances.values_mut() {
+        relocate_instance(child, from, to);
+    }
+}
+
+fn clone_instance(inst: &Instance) -> Instance {
+    Instance {
+        path: inst.path.clone(),
+        instances: inst
+            .instances
+            .iter()
+            .map(|(name, child)| (name.clone(), clone_instance(child)))
+            .collect(),
+        nets: inst
+            .nets
+            .iter()
+            .map(|(name, net)| {
+                let net = Net {
+                    ports: net.ports.clone(),
+                    rename_from: net.rename_from.clone(),
+                    span: net.span,
+                };
+                (name.clone(), net)
+            })
+            .collect(),
+        interface: inst.interface.clone(),
+        lib: inst.lib.clone(),
+        cell: inst.cell.clone(),
+        properties: inst.properties.clone(),
+        property_spans: inst.property_spans.clone(),
+        rename_from: inst.rename_from.clone(),
+        black_box: inst.black_box,
+        span: inst.span,
+    }
+}
+
+fn check_member(port: &Port, member: Option<i32>) -> bool {
+    match (port.kind, member) {
+        (PortKind::Single, None) => true,
+        (PortKind::Array(n), Some(m)) => 0 <= m && m < n,
+        _ => false,
+    }
+}
+
+impl<'a> Transaction<'a> {
+    /// Keeps the edits.
+    pub fn commit(mut self) {
+        self.log.clear();
+    }
+
+    /// Undoes the edits.
+    pub fn rollback(self) {}
+
+    /// The netlist with the edits so far.
+    pub fn netlist(&self) -> &Netlist {
+        self.netlist
+    }
+
+    /// A reference to the single-bit port `port` of the instance at `instance`.
+    pub fn pin(&self, instance: &Path, port: &str) -> PortRef {
+        PortRef {
+            instance: instance.clone(),
+            port: Atom::from(port),
+            member: None,
+        }
+    }
+
+    fn instance(&self, path: &Path) -> Result<&Instance> {
+        self.netlist
+            .instance(path)
+            .ok_or_else(|| anyhow!("instance `{}` not found", path))
+    }
+
+    fn instance_mut(&mut self, path: &Path) -> Result<&mut Instance> {
+        self.netlist
+            .instance_mut(path)
+            .ok_or_else(|| anyhow!("instance `{}` not found", path))
+    }
+
+    /// The instance that can contain child instances and nets.
+    fn scope_mut(&mut self, path: &Path) -> Result<&mut Instance> {
+        let inst = self.instance_mut(path)?;
+        ensure!(
+            inst.primitive().is_none(),
+            "`{}` is a primitive `{}`",
+            path,
+            inst.cell
+        );
+        Ok(inst)
+    }
+
+    /// Splits the path of a net into the instance containing it and its name.
+    fn split_net(&self, net: &Path) -> Result<(Path, Atom)> {
+        let scope = net
+            .parent()
+            .ok_or_else(|| anyhow!("`{}` is not a path of a net", net))?;
+        let inst = self.instance(&scope)?;
+        ensure!(
+            inst.nets.contains_key(&net.name()),
+            "net `{}` not found",
+            net
+        );
+        Ok((scope, net.name()))
+    }
+
+    /// Adds an instance of `lib`/`cell` named `name` in `parent`. The interface of the cell
+    /// is taken from the [primitive catalogue](crate::primitives), or copied with the
+    /// contents of the cell from an existing instance of it.
+    pub fn add_instance(
+        &mut self,
+        parent: &Path,
+        name: &str,
+        lib: &str,
+        cell: &str,
+        properties: Vec<(Atom, Property)>,
+    ) -> Result<Path> {
+        let path = parent.child(Atom::from(name));
+        let (lib, cell) = (Atom::from(lib), Atom::from(cell));
+
+        let mut inst = match primitives::lookup(&cell) {
+            Some(prim) => {
+                for (key, _) in &properties {
+                    ensure!(
+                        prim.property(key).is_some(),
+                        "`{}` does not have property `{}`",
+                        cell,
+                        key
+                    );
+                }
+                let interface = prim
+                    .pins
+                    .iter()
+                    .map(|pin| {
+                        let name = Atom::from(pin.name);
+                        let port = Port {
+                            kind: match pin.width {
+                                1 => PortKind::Single,
+                                n => PortKind::Array(n as i32),
+                            },
+                            dir: pin.dir,
+                            name: crate::ast::Name {
+                                name: name.clone(),
+                                rename_from: None,
+                            },
+                            span: Default::default(),
+                        };
+                        (name, port)
+                    })
+                    .collect();
+                Instance {
+                    path: path.clone(),
+                    instances: FxIndexMap::default(),
+                    nets: FxIndexMap::default(),
+                    interface,
+                    lib,
+                    cell,
+                    properties: FxIndexMap::default(),
+                    property_spans: FxIndexMap::default(),
+                    rename_from: None,
+                    black_box: false,
+                    span: Default::default(),
+                }
+            }
+            None => {
+                let template = self
+                    .netlist
+                    .top
+                    .walk()
+                    .find(|inst| inst.lib == lib && inst.cell == cell)
+                    .ok_or_else(|| anyhow!("cell `{}` of library `{}` not found", cell, lib))?;
+                let mut inst = clone_instance(template);
+                let from = inst.path.clone();
+                relocate_instance(&mut inst, &from, &path);
+                inst.properties.clear();
+                inst.property_spans.clear();
+                inst.rename_from = None;
+                inst
+            }
+        };
+        inst.properties.extend(properties);
+
+        let scope = self.scope_mut(parent)?;
+        ensure!(
+            !scope.instances.contains_key(&path.name()),
+            "instance `{}` already exists",
+            path
+        );
+        scope.instances.insert(path.name(), inst);
+        self.log.push(Undo::AddInstance(path.clone()));
+
+        Ok(path)
+    }
+
+    /// Removes the instance at `path` and disconnects it from the nets of its parent.
+    pub fn remove_instance(&mut self, path: &Path) -> Result<()> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow!("cannot remove the top-level instance"))?;
+        let scope = self.scope_mut(&parent)?;
+        let (i, _, inst) = scope
+            .instances
+            .shift_remove_full(&path.name())
+            .ok_or_else(|| anyhow!("instance `{}` not found", path))?;
+
+        let mut pins = vec![];
+        for (name, net) in &mut scope.nets {
+            pins.extend(
+                net.ports
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.instance == *path)
+                    .map(|(j, p)| (name.clone(), j, p.clone())),
+            );
+            net.ports.retain(|p| p.instance != *path);
+        }
+        self.log.push(Undo::RemoveInstance(Box::new(inst), i, pins));
+
+        Ok(())
+    }
+
+    /// Creates a net without pins named `name` in `parent`.
+    pub fn create_net(&mut self, parent: &Path, name: &str) -> Result<Path> {
+        let scope = self.scope_mut(parent)?;
+        let name = Atom::from(name);
+        ensure!(
+            !scope.nets.contains_key(&name),
+            "net `{}/{}` already exists",
+            parent,
+            name
+        );
+        scope.nets.insert(
+            name.clone(),
+            Net {
+                ports: FxIndexSet::default(),
+                rename_from: None,
+                span: Default::default(),
+            },
+        );
+
+        let path = parent.child(name);
+        self.log.push(Undo::CreateNet(path.clone()));
+        Ok(path)
+    }
+
+    /// Connects `pin` to `net`. The pin is either a port of a child instance of the
+    /// instance containing the net, or a port of that instance itself.
+    pub fn connect(&mut self, pin: &PortRef, net: &Path) -> Result<()> {
+        let (scope, name) = self.split_net(net)?;
+        ensure!(
+            pin.instance == scope || pin.instance.parent().as_ref() == Some(&scope),
+            "`{}` cannot be connected from `{}`",
+            pin.instance,
+            scope
+        );
+
+        let inst = self.instance(&pin.instance)?;
+        let port = inst
+            .interface
+            .get(&pin.port)
+            .ok_or_else(|| anyhow!("`{}` does not have port `{}`", pin.instance, pin.port))?;
+        ensure!(
+            check_member(port, pin.member),
+            "invalid member {:?} of port `{}` of `{}`",
+            pin.member,
+            pin.port,
+            pin.instance
+        );
+
+        let scope_inst = self.instance_mut(&scope)?;
+        if let Some((other, _)) = scope_inst.nets.iter().find(|(_, n)| n.ports.contains(pin)) {
+            bail!(
+                "`{}/{}` is already connected to `{}/{}`",
+                pin.instance,
+                pin.port,
+                scope,
+                other
+            );
+        }
+        scope_inst
+            .nets
+            .get_mut(&name)
+            .unwrap()
+            .ports
+            .insert(pin.clone());
+        self.log.push(Undo::Connect(net.clone(), pin.clone()));
+
+        Ok(())
+    }
+
+    /// Disconnects `pin` from `net`. As for [`connect`](Transaction::connect), a port of a
+    /// hierarchical instance is disconnected inside the instance or outside of it depending
+    /// on the net.
+    pub fn disconnect(&mut self, pin: &PortRef, net: &Path) -> Result<()> {
+        let (scope, name) = self.split_net(net)?;
+        let scope_inst = self.instance_mut(&scope)?;
+        let (i, _) = scope_inst.nets[&name]
+            .ports
+            .shift_remove_full(pin)
+            .ok_or_else(|| {
+                anyhow!(
+                    "`{}/{}` is not connected to `{}`",
+                    pin.instance,
+                    pin.port,
+                    net
+                )
+            })?;
+
+        self.log.push(Undo::Disconnect(net.clone(), i, pin.clone()));
+        Ok(())
+    }
+
+    fn rename_unlogged(&mut self, path: &Path, new_name: &Atom) -> Result<Path> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow!("cannot rename the top-level instance"))?;
+        let to = parent.child(new_name.clone());
+        let scope = self.instance_mut(&parent)?;
+        ensure!(
+            !scope.instances.contains_key(new_name) && !scope.nets.contains_key(new_name),
+            "`{}` already exists",
+            to
+        );
+        ensure!(
+            !(scope.instances.contains_key(&path.name()) && scope.nets.contains_key(&path.name())),
+            "`{}` names both an instance and a net",
+            path
+        );
+
+        // Renamed in place, keeping the order of the scope.
+        if let Some(i) = scope.nets.get_index_of(&path.name()) {
+            let _ = scope.nets.replace_index(i, new_name.clone());
+        } else {
+            let i = scope
+                .instances
+                .get_index_of(&path.name())
+                .ok_or_else(|| anyhow!("`{}` not found", path))?;
+            let _ = scope.instances.replace_index(i, new_name.clone());
+            relocate_instance(&mut scope.instances[i], path, &to);
+
+            for net in scope.nets.values_mut() {
+                net.ports = net
+                    .ports
+                    .drain(..)
+                    .map(|mut p| {
+                        if p.instance == *path {
+                            p.instance = to.clone();
+                        }
+                        p
+                    })
+                    .collect();
+            }
+        }
+
+        Ok(to)
+    }
+
+    /// Renames the instance or the net at `path`, which must not name both. Returns the new
+    /// path.
+    pub fn rename(&mut self, path: &Path, new_name: &str) -> Result<Path> {
+        let to = self.rename_unlogged(path, &Atom::from(new_name))?;
+        self.log.push(Undo::Rename(path.clone(), to.clone()));
+        Ok(to)
+    }
+
+    /// Sets or, with `None`, removes the property `name` of the instance at `path`. Returns
+    /// the previous value.
+    pub fn set_property(
+        &mut self,
+        path: &Path,
+        name: &str,
+        value: Option<Property>,
+    ) -> Result<Option<Property>> {
+        let name = Atom::from(name);
+        let inst = self.instance_mut(path)?;
+        if let Some(prim) = inst.primitive() {
+            ensure!(
+                prim.property(&name).is_some(),
+                "`{}` does not have property `{}`",
+                prim.name,
+                name
+            );
+        }
+
+        let old = match value {
+            Some(v) => {
+                let (i, old) = inst.properties.insert_full(name.clone(), v);
+                old.map(|v| (i, v))
+            }
+            None => inst
+                .properties
+                .shift_remove_full(&name)
+                .map(|(i, _, v)| (i, v)),
+        };
+        self.log
+            .push(Undo::SetProperty(path.clone(), name, old.clone()));
+        Ok(old.map(|(_, v)| v))
+    }
+
+    /// Adds a port to the cell of the hierarchical instance at `path`, and so to all the
+    /// instances of the cell.
+    pub fn add_port(
+        &mut self,
+        path: &Path,
+        name: &str,
+        dir: Direction,
+        kind: PortKind,
+    ) -> Result<()> {
+        let inst = self.instance(path)?;
+        ensure!(
+            inst.primitive().is_none(),
+            "`{}` is a primitive `{}`",
+            path,
+            inst.cell
+        );
+        let name = Atom::from(name);
+        ensure!(
+            !inst.interface.contains_key(&name),
+            "`{}` already has port `{}`",
+            inst.cell,
+            name
+        );
+
+        let (lib, cell) = (inst.lib.clone(), inst.cell.clone());
+        let paths = self
+            .netlist
+            .top
+            .walk()
+            .filter(|inst| inst.lib == lib && inst.cell == cell)
+            .map(|inst| inst.path.clone())
+            .collect::<Vec<_>>();
+
+        let port = Port {
+            kind,
+            dir,
+            name: crate::ast::Name {
+                name: name.clone(),
+                rename_from: None,
+            },
+            span: Default::default(),
+        };
+        for path in &paths {
+            self.instance_mut(path)?
+                .interface
+                .insert(name.clone(), port.clone());
+        }
+        self.log.push(Undo::AddPort(paths, name));
+
+        Ok(())
+    }
+
+    fn undo(&mut self, undo: Undo) {
+        let netlist = &mut *self.netlist;
+        match undo {
+            Undo::AddInstance(path) => {
+                let scope = netlist.instance_mut(&path.parent().unwrap()).unwrap();
+                scope.instances.shift_remove(&path.name());
+            }
+            Undo::RemoveInstance(inst, i, pins) => {
+                let scope = netlist.instance_mut(&inst.path.parent().unwrap()).unwrap();
+                for (net, j, pin) in pins {
+                    scope.nets.get_mut(&net).unwrap().ports.shift_insert(j, pin);
+                }
+                scope.instances.shift_insert(i, inst.path.name(), *inst);
+            }
+            Undo::CreateNet(path) => {
+                let scope = netlist.instance_mut(&path.parent().unwrap()).unwrap();
+                scope.nets.shift_remove(&path.name());
+            }
+            Undo::Connect(net, pin) => {
+                let scope = netlist.instance_mut(&net.parent().unwrap()).unwrap();
+                scope
+                    .nets
+                    .get_mut(&net.name())
+                    .unwrap()
+                    .ports
+                    .shift_remove(&pin);
+            }
+            Undo::Disconnect(net, i, pin) => {
+                let scope = netlist.instance_mut(&net.parent().unwrap()).unwrap();
+                scope
+                    .nets
+                    .get_mut(&net.name())
+                    .unwrap()
+                    .ports
+                    .shift_insert(i, pin);
+            }
+            Undo::Rename(from, to) => {
+                self.rename_unlogged(&to, &from.name()).unwrap();
+            }
+            Undo::SetProperty(path, name, old) => {
+                let inst = netlist.instance_mut(&path).unwrap();
+                match old {
+                    Some((i, v)) => inst.properties.shift_insert(i, name, v),
+                    None => inst.properties.shift_remove(&name),
+                };
+            }
+            Undo::AddPort(paths, name) => {
+                for path in paths {
+                    netlist
+                        .instance_mut(&path)
+                        .unwrap()
+                        .interface
+                        .shift_remove(&name);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        while let Some(undo) = self.log.pop() {
+            self.undo(undo);
+        }
+    }
+}
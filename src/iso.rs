@@ -0,0 +1,376 @@
+//! Comparison of two netlists ignoring the names of instances and nets.
+//!
+//! The flattened netlists are compared as bipartite graphs of leaf instances and nets. The
+//! instances are labelled by their cells and properties, the edges by their ports, and the
+//! top ports keep their names. Colours are refined by Weisfeiler-Lehman hashing, and ties
+//! between symmetric elements are broken by individualization with backtracking, so the
+//! result is exact up to hash collisions. The backtracking is bounded by
+//! [`IsoOptions::max_backtracks`], past which the result is undecided.
+
+use crate::diff::property_string;
+use crate::netlist::{Netlist, Path};
+use fxhash::{FxHashMap, FxHashSet, FxHasher};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, Default)]
+pub struct IsoReport {
+    /// The matching leaf instances, and the top instances, if the netlists are isomorphic.
+    pub instances: Vec<(Path, Path)>,
+    /// The matching nets, named by their highest segments.
+    pub nets: Vec<(Path, Path)>,
+    pub mismatch: Option<Mismatch>,
+    /// Whether the search gave up before telling the netlists apart or matching them.
+    pub undecided: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsoOptions {
+    /// The number of candidates of the backtracking search that may fail to match before it
+    /// gives up.
+    pub max_backtracks: usize,
+}
+
+impl Default for IsoOptions {
+    fn default() -> Self {
+        IsoOptions {
+            max_backtracks: 1000,
+        }
+    }
+}
+
+/// The elements whose neighbourhoods differ, at the smallest radius that tells the
+/// netlists apart. Structurally identical candidates are told apart by their names.
+#[derive(Debug, Clone, Default)]
+pub struct Mismatch {
+    /// The number of refinement rounds, each extending the neighbourhoods by one instance and
+    /// one net, before the mismatch was found.
+    pub radius: usize,
+    pub left_instances: Vec<Path>,
+    pub right_instances: Vec<Path>,
+    pub left_nets: Vec<Path>,
+    pub right_nets: Vec<Path>,
+}
+
+fn hash(v: impl Hash) -> u64 {
+    let mut h = FxHasher::default();
+    v.hash(&mut h);
+    h.finish()
+}
+
+struct Graph {
+    /// The top instance, whose pins are the top ports, and then the leaf instances.
+    insts: Vec<Path>,
+    nets: Vec<Path>,
+    /// The pins of each instance, as a port label and a net.
+    inst_pins: Vec<Vec<(u64, usize)>>,
+    /// The pins on each net, as a port label and an instance.
+    net_pins: Vec<Vec<(u64, usize)>>,
+}
+
+#[derive(Clone)]
+struct Colors {
+    insts: Vec<u64>,
+    nets: Vec<u64>,
+}
+
+impl Graph {
+    fn new(netlist: &Netlist) -> (Graph, Colors) {
+        let top = &netlist.top;
+        let mut leaves = top
+            .walk()
+            .filter(|i| i.is_leaf() && i.path != top.path)
+            .collect::<Vec<_>>();
+        leaves.sort_by(|a, b| a.path.cmp(&b.path));
+        leaves.insert(0, top);
+
+        let index = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, inst)| (&inst.path, i))
+            .collect::<FxHashMap<_, _>>();
+        let labels = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, inst)| {
+                if i == 0 {
+                    return hash("top");
+                }
+                let mut props = inst
+                    .properties
+                    .iter()
+                    .map(|(k, v)| (&**k, property_string(v)))
+                    .collect::<Vec<_>>();
+                props.sort();
+                hash((&*inst.lib, &*inst.cell, props))
+            })
+            .collect();
+
+        let mut global = netlist.global_nets();
+        global.sort_by(|a, b| a.segments.cmp(&b.segments));
+        let mut graph = Graph {
+            insts: leaves.iter().map(|i| i.path.clone()).collect(),
+            nets: vec![],
+            inst_pins: vec![vec![]; leaves.len()],
+            net_pins: vec![],
+        };
+        let mut net_labels = vec![];
+        for net in global {
+            let pins = net
+                .pins
+                .iter()
+                .filter_map(|p| Some((hash((&*p.port, p.member)), *index.get(&p.instance)?)))
+                .collect::<Vec<_>>();
+            if pins.is_empty() {
+                continue;
+            }
+            let n = graph.nets.len();
+            for &(label, i) in &pins {
+                graph.inst_pins[i].push((label, n));
+            }
+            let mut labels = pins.iter().map(|&(label, _)| label).collect::<Vec<_>>();
+            labels.sort_unstable();
+            net_labels.push(hash(("net", labels)));
+            graph.nets.push(net.segments[0].clone());
+            graph.net_pins.push(pins);
+        }
+
+        let colors = Colors {
+            insts: labels,
+            nets: net_labels,
+        };
+        (graph, colors)
+    }
+
+    fn refine(&self, c: &Colors) -> Colors {
+        let step = |own: &[u64], pins: &[Vec<(u64, usize)>], other: &[u64]| {
+            own.iter()
+                .zip(pins)
+                .map(|(&color, pins)| {
+                    let mut around = pins
+                        .iter()
+                        .map(|&(label, j)| hash((label, other[j])))
+                        .collect::<Vec<_>>();
+                    around.sort_unstable();
+                    hash((color, around))
+                })
+                .collect()
+        };
+        Colors {
+            insts: step(&c.insts, &self.inst_pins, &c.nets),
+            nets: step(&c.nets, &self.net_pins, &c.insts),
+        }
+    }
+}
+
+fn distinct(c: &Colors) -> usize {
+    c.insts
+        .iter()
+        .chain(&c.nets)
+        .collect::<FxHashSet<_>>()
+        .len()
+}
+
+/// The elements of the colour classes of different sizes, if any.
+fn mismatch(a: &Graph, b: &Graph, ca: &Colors, cb: &Colors, radius: usize) -> Option<Mismatch> {
+    let mut count = FxHashMap::<u64, isize>::default();
+    for &c in ca.insts.iter().chain(&ca.nets) {
+        *count.entry(c).or_default() += 1;
+    }
+    for &c in cb.insts.iter().chain(&cb.nets) {
+        *count.entry(c).or_default() -= 1;
+    }
+    if count.values().all(|&n| n == 0) {
+        return None;
+    }
+
+    // Group the elements of the differing classes, and drop the ones found on both sides.
+    let select = |paths: &[Path], colors: &[u64]| {
+        let mut classes = FxHashMap::<u64, FxHashSet<Path>>::default();
+        for (p, c) in paths.iter().zip(colors) {
+            if count[c] != 0 {
+                classes.entry(*c).or_default().insert(p.clone());
+            }
+        }
+        classes
+    };
+    let only = |left: &FxHashMap<u64, FxHashSet<Path>>, right: &FxHashMap<u64, FxHashSet<Path>>| {
+        let mut paths = left
+            .iter()
+            .flat_map(|(c, paths)| {
+                paths
+                    .iter()
+                    .filter(move |p| !right.get(c).is_some_and(|r| r.contains(p)))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    };
+    let (ia, ib) = (select(&a.insts, &ca.insts), select(&b.insts, &cb.insts));
+    let (na, nb) = (select(&a.nets, &ca.nets), select(&b.nets, &cb.nets));
+    Some(Mismatch {
+        radius,
+        left_instances: only(&ia, &ib),
+        right_instances: only(&ib, &ia),
+        left_nets: only(&na, &nb),
+        right_nets: only(&nb, &na),
+    })
+}
+
+/// Refines the colours of both graphs in lockstep until they are stable.
+fn stabilize(a: &Graph, b: &Graph, ca: &mut Colors, cb: &mut Colors) -> Result<(), Mismatch> {
+    let mut stable = false;
+    for radius in 0.. {
+        if let Some(m) = mismatch(a, b, ca, cb, radius) {
+            return Err(m);
+        }
+        if stable {
+            break;
+        }
+        let (na, nb) = (a.refine(ca), b.refine(cb));
+        stable = distinct(&na) == distinct(ca) && distinct(&nb) == distinct(cb);
+        *ca = na;
+        *cb = nb;
+    }
+    Ok(())
+}
+
+enum Failure {
+    Mismatch(Mismatch),
+    /// The backtracking ran out of its budget.
+    Undecided,
+}
+
+/// Finds colourings of both graphs giving every element a distinct colour, and the same
+/// colours to matching elements, with at most `budget` more failed candidates.
+fn search(
+    a: &Graph,
+    b: &Graph,
+    mut ca: Colors,
+    mut cb: Colors,
+    budget: &mut usize,
+) -> Result<(Colors, Colors), Failure> {
+    stabilize(a, b, &mut ca, &mut cb).map_err(Failure::Mismatch)?;
+
+    let mut count = FxHashMap::<u64, usize>::default();
+    for &c in ca.insts.iter().chain(&ca.nets) {
+        *count.entry(c).or_default() += 1;
+    }
+    let tied = |colors: &[u64]| colors.iter().position(|c| count[c] > 1);
+    let (net, i) = match (tied(&ca.insts), tied(&ca.nets)) {
+        (Some(i), _) => (false, i),
+        (None, Some(i)) => (true, i),
+        (None, None) => return Ok((ca, cb)),
+    };
+
+    // Individualize the first tied element against each candidate in turn.
+    let color = if net { ca.nets[i] } else { ca.insts[i] };
+    let fresh = hash((color, "individualized"));
+    let candidates = if net { &cb.nets } else { &cb.insts };
+    let mut first = None;
+    for (k, j) in (0..candidates.len())
+        .filter(|&j| candidates[j] == color)
+        .enumerate()
+    {
+        // Backtrack from the previous candidate.
+        if k > 0 {
+            if *budget == 0 {
+                return Err(Failure::Undecided);
+            }
+            *budget -= 1;
+        }
+        let (mut xa, mut xb) = (ca.clone(), cb.clone());
+        if net {
+            xa.nets[i] = fresh;
+            xb.nets[j] = fresh;
+        } else {
+            xa.insts[i] = fresh;
+            xb.insts[j] = fresh;
+        }
+        match search(a, b, xa, xb, budget) {
+            Ok(found) => return Ok(found),
+            Err(Failure::Mismatch(m)) => {
+                first.get_or_insert(m);
+            }
+            Err(Failure::Undecided) => return Err(Failure::Undecided),
+        }
+    }
+    Err(Failure::Mismatch(first.unwrap()))
+}
+
+fn pairs(left: &[Path], ca: &[u64], right: &[Path], cb: &[u64]) -> Vec<(Path, Path)> {
+    let index = cb.iter().zip(right).collect::<FxHashMap<_, _>>();
+    ca.iter()
+        .zip(left)
+        .map(|(c, p)| (p.clone(), index[c].clone()))
+        .collect()
+}
+
+/// Checks whether two netlists are structurally identical, up to the names of their instances
+/// and nets, and matches their elements.
+pub fn compare(left: &Netlist, right: &Netlist) -> IsoReport {
+    compare_with(left, right, &IsoOptions::default())
+}
+
+/// Like [`compare`], with the limits of `options`.
+pub fn compare_with(left: &Netlist, right: &Netlist, options: &IsoOptions) -> IsoReport {
+    let (a, ca) = Graph::new(left);
+    let (b, cb) = Graph::new(right);
+    let mut budget = options.max_backtracks;
+    match search(&a, &b, ca, cb, &mut budget) {
+        Ok((ca, cb)) => IsoReport {
+            instances: pairs(&a.insts, &ca.insts, &b.insts, &cb.insts),
+            nets: pairs(&a.nets, &ca.nets, &b.nets, &cb.nets),
+            ..IsoReport::default()
+        },
+        Err(Failure::Mismatch(m)) => IsoReport {
+            mismatch: Some(m),
+            ..IsoReport::default()
+        },
+        Err(Failure::Undecided) => IsoReport {
+            undecided: true,
+            ..IsoReport::default()
+        },
+    }
+}
+
+impl IsoReport {
+    pub fn is_isomorphic(&self) -> bool {
+        self.mismatch.is_none() && !self.undecided
+    }
+}
+
+impl fmt::Display for IsoReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.mismatch {
+            None if self.undecided => writeln!(f, "undecided: the search gave up"),
+            None => writeln!(
+                f,
+                "isomorphic: {} instances and {} nets matched",
+                self.instances.len(),
+                self.nets.len()
+            ),
+            Some(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "not isomorphic, differing at radius {}:", self.radius)?;
+        for p in &self.left_instances {
+            writeln!(f, "- instance {}", p)?;
+        }
+        for p in &self.right_instances {
+            writeln!(f, "+ instance {}", p)?;
+        }
+        for p in &self.left_nets {
+            writeln!(f, "- net {}", p)?;
+        }
+        for p in &self.right_nets {
+            writeln!(f, "+ net {}", p)?;
+        }
+        Ok(())
+    }
+}
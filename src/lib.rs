@@ -7,6 +7,28 @@ mod atom {
 pub use crate::atom::Atom;
 
 pub mod ast;
+pub mod blif;
+pub mod builder;
+pub mod cache;
+pub mod diff;
+pub mod dot;
+pub mod eco;
+pub mod iso;
+pub mod link;
+pub mod lint;
+pub mod literal;
+pub mod lut;
+pub mod mangle;
 pub mod netlist;
+pub mod opt;
 pub mod parser;
-mod sexpr;
+pub mod primitives;
+#[cfg(feature = "serde")]
+mod serde_util;
+pub mod sexpr;
+pub mod sim;
+pub mod vcd;
+pub mod verilog;
+pub mod visit;
+pub mod writer;
+pub mod yosys;
@@ -0,0 +1,171 @@
+//! Linking of designs split over several EDIF files.
+//!
+//! Vivado's out-of-context flow writes each IP to its own file, and the files instantiating it
+//! keep a black-box stub: a cell with an interface but no contents. The linker merges the
+//! libraries of all the files, renaming the libraries whose cells would clash, and resolves
+//! every stub to the definition of the cell of the same name, wherever it is defined. The
+//! cells defined nowhere are left as black boxes.
+
+use crate::ast::{Cell, Content, Edif, FxIndexMap, Instance, Interface, Library};
+use crate::atom::Atom;
+use crate::netlist::Netlist;
+use anyhow::{anyhow, bail, Context, Result};
+use fxhash::FxHashMap;
+
+fn is_stub(cell: &Cell) -> bool {
+    cell.view.contents.is_empty()
+}
+
+/// Checks that the interfaces of a stub and of the definition replacing it match.
+fn check_interface(cell: &Atom, stub: &Interface, def: &Interface) -> Result<()> {
+    let ports = |i: &Interface| {
+        let mut ports = i
+            .ports
+            .iter()
+            .map(|p| (p.name.name.clone(), p.dir, p.kind.width()))
+            .collect::<Vec<_>>();
+        ports.sort_by(|a, b| a.0.cmp(&b.0));
+        ports
+    };
+    let (stub, def) = (ports(stub), ports(def));
+    if stub == def {
+        return Ok(());
+    }
+    if let Some(p) = stub.iter().find(|p| !def.contains(p)) {
+        bail!(
+            "port `{}` of the stub of cell `{}` is not in its definition",
+            p.0,
+            cell
+        );
+    }
+    let p = def.iter().find(|p| !stub.contains(p)).unwrap();
+    bail!("port `{}` of cell `{}` is not in its stub", p.0, cell)
+}
+
+fn instances_mut(libs: &mut FxIndexMap<Atom, Library>) -> impl Iterator<Item = &mut Instance> {
+    libs.values_mut()
+        .flat_map(|lib| lib.cells.values_mut())
+        .flat_map(|cell| cell.view.contents.iter_mut())
+        .filter_map(|c| match c {
+            Content::Instance(inst) => Some(inst),
+            Content::Net(_) => None,
+        })
+}
+
+/// Merges `edifs` into one document, whose design is the one of the first.
+pub fn link(edifs: impl IntoIterator<Item = Edif>) -> Result<Edif> {
+    let mut edifs = edifs.into_iter();
+    let first = edifs.next().ok_or_else(|| anyhow!("nothing to link"))?;
+    let mut design = first.design;
+    let mut libs = first.libs;
+
+    for mut edif in edifs {
+        // Rename the libraries defining cells already defined in the library of the same name.
+        let names = edif.libs.keys().cloned().collect::<Vec<_>>();
+        let mut renames = FxHashMap::default();
+        for name in names {
+            let clash = libs.get(&name).is_some_and(|lib| {
+                edif.libs[&name].cells.iter().any(|(c, cell)| {
+                    !is_stub(cell) && lib.cells.get(c).is_some_and(|c| !is_stub(c))
+                })
+            });
+            if clash {
+                let fresh = (1..)
+                    .map(|i| Atom::from(format!("{}_{}", name, i)))
+                    .find(|n| !libs.contains_key(n) && !edif.libs.contains_key(n))
+                    .unwrap();
+                let i = edif.libs.get_index_of(&name).unwrap();
+                let _ = edif.libs.replace_index(i, fresh.clone());
+                edif.libs[i].name = fresh.clone();
+                renames.insert(name, fresh);
+            }
+        }
+        for inst in instances_mut(&mut edif.libs) {
+            if let Some(new) = inst.libraryref.as_ref().and_then(|l| renames.get(l)) {
+                inst.libraryref = Some(new.clone());
+            }
+        }
+
+        // Merge the libraries, replacing the stubs by definitions.
+        for (name, lib) in edif.libs {
+            let target = match libs.get_mut(&name) {
+                Some(target) => target,
+                None => {
+                    libs.insert(name, lib);
+                    continue;
+                }
+            };
+            for (cell_name, cell) in lib.cells {
+                match target.cells.get(&cell_name) {
+                    None => {}
+                    Some(old) => {
+                        let (stub, def) = if is_stub(old) {
+                            (old, &cell)
+                        } else {
+                            (&cell, old)
+                        };
+                        check_interface(&cell_name, &stub.view.interface, &def.view.interface)
+                            .with_context(|| format!("in library `{}`", name))?;
+                        if !is_stub(old) || is_stub(&cell) {
+                            continue;
+                        }
+                    }
+                }
+                target.cells.insert(cell_name, cell);
+            }
+        }
+    }
+
+    // Point the references to the remaining stubs at the definitions in other libraries.
+    let mut defs = FxHashMap::<Atom, Vec<Atom>>::default();
+    for lib in libs.values() {
+        for (name, cell) in &lib.cells {
+            if !is_stub(cell) {
+                defs.entry(name.clone()).or_default().push(lib.name.clone());
+            }
+        }
+    }
+    let mut redirects = FxHashMap::default();
+    for lib in libs.values() {
+        for (name, cell) in &lib.cells {
+            let found = match defs.get(name) {
+                Some(found) if is_stub(cell) => found,
+                _ => continue,
+            };
+            if found.len() > 1 {
+                let mut found = found.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+                found.sort();
+                bail!(
+                    "cell `{}` is defined in several libraries: {}",
+                    name,
+                    found.join(", ")
+                );
+            }
+            let def = &libs[&found[0]].cells[name];
+            check_interface(name, &cell.view.interface, &def.view.interface)
+                .with_context(|| format!("in library `{}`", lib.name))?;
+            redirects.insert((lib.name.clone(), name.clone()), found[0].clone());
+        }
+    }
+    for inst in instances_mut(&mut libs) {
+        if let Some(lib) = &inst.libraryref {
+            if let Some(def) = redirects.get(&(lib.clone(), inst.cellref.clone())) {
+                inst.libraryref = Some(def.clone());
+            }
+        }
+    }
+    if let Some(def) = redirects.get(&(design.libraryref.clone(), design.cellref.clone())) {
+        design.libraryref = def.clone();
+    }
+    for (lib, cell) in redirects.keys() {
+        libs.get_mut(lib).unwrap().cells.shift_remove(cell);
+    }
+    libs.retain(|_, lib| !lib.cells.is_empty());
+
+    Ok(Edif { libs, design })
+}
+
+/// Links `edifs` into a netlist of the design of the first.
+pub fn link_netlist(edifs: impl IntoIterator<Item = Edif>) -> Result<Netlist> {
+    Ok(Netlist::from_ast(&link(edifs)?))
+}
@@ -0,0 +1,194 @@
+//! Structural checks of a [`Netlist`](crate::netlist::Netlist).
+
+use crate::ast::{Direction, Span};
+use crate::atom::Atom;
+use crate::netlist::{pin_string, Instance, Netlist, Path, PortRef};
+use crate::primitives::PinRole;
+use fxhash::{FxHashMap, FxHashSet};
+use serde_json::{json, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    /// A short name of the check, as `multiple-drivers`.
+    pub code: &'static str,
+    /// The instance or net the issue is about.
+    pub path: Path,
+    /// The span of the instance or net in the source.
+    pub span: Span,
+    pub message: String,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            self.severity.as_str(),
+            self.code,
+            self.path,
+            self.message
+        )
+    }
+}
+
+impl Issue {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "severity": self.severity.as_str(),
+            "code": self.code,
+            "path": self.path.to_string(),
+            "line": self.span.line,
+            "column": self.span.column,
+            "message": self.message,
+        })
+    }
+}
+
+/// Checks `netlist`, and returns the issues found, the most severe first.
+///
+/// Nets with several drivers and dangling references are errors. Undriven nets, nets with
+/// fewer than two pins, unconnected inputs and unknown properties of primitives are warnings,
+/// and black boxes are reported as information.
+pub fn lint(netlist: &Netlist) -> Vec<Issue> {
+    let top = &*netlist.top;
+    let mut issues = vec![];
+    let mut issue = |severity, code, path: &Path, message: String| {
+        issues.push(Issue {
+            severity,
+            code,
+            path: path.clone(),
+            span: netlist.span(path).unwrap_or_default(),
+            message,
+        })
+    };
+
+    if let Err(e) = netlist.verify_references() {
+        issue(
+            Severity::Error,
+            "invalid-reference",
+            &top.path,
+            e.to_string(),
+        );
+    }
+
+    let leaves = top
+        .walk()
+        .filter(|i| i.is_leaf() && i.path != top.path)
+        .map(|i| (&i.path, i))
+        .collect::<FxHashMap<&Path, &Instance>>();
+    let dir = |p: &PortRef| {
+        let inst = if p.instance == top.path {
+            top
+        } else {
+            *leaves.get(&p.instance)?
+        };
+        let dir = inst.interface.get(&p.port)?.dir;
+        // The top-level ports drive the nets inside.
+        Some(match (p.instance == top.path, dir) {
+            (true, Direction::Input) => Direction::Output,
+            (true, Direction::Output) => Direction::Input,
+            (_, dir) => dir,
+        })
+    };
+
+    let nets = netlist.global_nets();
+    let mut connected = FxHashSet::default();
+    for net in &nets {
+        let path = &net.segments[0];
+        let pins = net
+            .pins
+            .iter()
+            .filter_map(|p| Some((p, dir(p)?)))
+            .collect::<Vec<_>>();
+        connected.extend(pins.iter().map(|(p, _)| *p));
+
+        let drivers = pins
+            .iter()
+            .filter(|(_, d)| *d == Direction::Output)
+            .map(|(p, _)| pin_string(p))
+            .collect::<Vec<_>>();
+        let has = |d| pins.iter().any(|(_, dir)| *dir == d);
+        if drivers.len() > 1 {
+            let mut drivers = drivers;
+            drivers.sort();
+            let message = format!("driven by {}", drivers.join(", "));
+            issue(Severity::Error, "multiple-drivers", path, message);
+        } else if drivers.is_empty() && has(Direction::Input) && !has(Direction::InOut) {
+            issue(Severity::Warning, "undriven", path, "has no driver".into());
+        }
+        if pins.len() < 2 {
+            let message = format!("has {} pin(s)", pins.len());
+            issue(Severity::Warning, "dangling", path, message);
+        }
+    }
+
+    let mut insts = leaves.values().collect::<Vec<_>>();
+    insts.sort_by(|a, b| a.path.cmp(&b.path));
+    for inst in insts {
+        if inst.black_box {
+            let message = format!(
+                "cell `{}` of library `{}` is a black box",
+                inst.cell, inst.lib
+            );
+            issue(Severity::Info, "black-box", &inst.path, message);
+        }
+        let prim = match inst.primitive() {
+            Some(prim) => prim,
+            None => continue,
+        };
+        let mut props = inst.properties.keys().collect::<Vec<_>>();
+        props.sort();
+        for name in props {
+            if prim.property(name).is_none() {
+                let message = format!("`{}` has no property `{}`", inst.cell, name);
+                issue(Severity::Warning, "unknown-property", &inst.path, message);
+            }
+        }
+        for pin in prim.pins {
+            if pin.dir != Direction::Input || pin.role == PinRole::Cascade {
+                continue;
+            }
+            let port = match inst.interface.get(&Atom::from(pin.name)) {
+                Some(port) => port,
+                None => continue,
+            };
+            let floating = (0..port.kind.width())
+                .map(|bit| PortRef {
+                    instance: inst.path.clone(),
+                    port: port.name.name.clone(),
+                    member: port.kind.member_of_bit(bit),
+                })
+                .any(|p| !connected.contains(&p));
+            if floating {
+                let message = format!("input `{}` is not connected", pin.name);
+                issue(Severity::Warning, "floating-input", &inst.path, message);
+            }
+        }
+    }
+
+    issues.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    issues
+}
@@ -0,0 +1,183 @@
+//! Verilog-style literals in EDIF property strings, such as `2'h1` or `64'hFFFF0000FFFF0000`.
+
+use crate::atom::Atom;
+use crate::netlist::Instance;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// A fixed-width vector of bits. Bit 0 is the least significant bit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    width: usize,
+    words: Vec<u64>,
+}
+
+impl BitVec {
+    pub fn zeros(width: usize) -> Self {
+        BitVec {
+            width,
+            words: vec![0; width.div_ceil(64)],
+        }
+    }
+
+    /// A bit vector holding the low `width` bits of `value`.
+    pub fn from_u64(width: usize, value: u64) -> Self {
+        let mut bv = BitVec::zeros(width);
+        if let Some(w) = bv.words.first_mut() {
+            *w = value;
+        }
+        bv.truncate();
+        bv
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        i < self.width && self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.width, "bit {} out of range", i);
+        if value {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    /// The value as an integer, if it fits in 64 bits.
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.words.iter().skip(1).any(|&w| w != 0) {
+            return None;
+        }
+        Some(self.words.first().copied().unwrap_or(0))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Iterates over the bits from the least significant one.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.width).map(move |i| self.get(i))
+    }
+
+    /// Clears the bits beyond the width.
+    fn truncate(&mut self) {
+        if !self.width.is_multiple_of(64) {
+            if let Some(w) = self.words.last_mut() {
+                *w &= (1 << (self.width % 64)) - 1;
+            }
+        }
+    }
+
+    /// `self = self * mul + add`, discarding the overflow.
+    fn mul_add(&mut self, mul: u64, add: u64) {
+        let mut carry = add as u128;
+        for w in &mut self.words {
+            let v = *w as u128 * mul as u128 + carry;
+            *w = v as u64;
+            carry = v >> 64;
+        }
+        self.truncate();
+    }
+}
+
+/// The widest literal that is parsed.
+pub const MAX_WIDTH: usize = 1 << 16;
+
+/// Parses a Verilog-style literal: `N'hXX`, `N'bXX`, `N'oXX` or `N'dXX`, optionally with
+/// the signedness marker `s` and `_` separators. A plain decimal number is taken as 32 bits
+/// wide, as in Verilog. Digits beyond the width are truncated, and widths beyond
+/// [`MAX_WIDTH`] are rejected.
+impl FromStr for BitVec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (width, radix, digits) = match s.find('\'') {
+            Some(i) => {
+                let width = if i == 0 {
+                    32
+                } else {
+                    s[..i]
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| anyhow!("invalid width in `{}`", s))?
+                };
+                let mut rest = &s[i + 1..];
+                if let Some(r) = rest.strip_prefix(|c| c == 's' || c == 'S') {
+                    rest = r;
+                }
+                let mut chars = rest.chars();
+                let radix = match chars.next() {
+                    Some('h') | Some('H') => 16,
+                    Some('b') | Some('B') => 2,
+                    Some('o') | Some('O') => 8,
+                    Some('d') | Some('D') => 10,
+                    _ => bail!("invalid base in `{}`", s),
+                };
+                (width, radix, chars.as_str().trim())
+            }
+            None => (32, 10, s),
+        };
+
+        ensure!(width > 0, "zero-width literal `{}`", s);
+        ensure!(
+            width <= MAX_WIDTH,
+            "literal `{}` is wider than {} bits",
+            s,
+            MAX_WIDTH
+        );
+
+        let mut bv = BitVec::zeros(width);
+        let mut any = false;
+        for c in digits.chars().filter(|&c| c != '_') {
+            let d = match c.to_digit(radix) {
+                Some(d) => d,
+                None if "xXzZ?".contains(c) => bail!("unknown bits are not supported: `{}`", s),
+                None => bail!("invalid digit `{}` in `{}`", c, s),
+            };
+            bv.mul_add(radix.into(), d.into());
+            any = true;
+        }
+        ensure!(any, "no digits in `{}`", s);
+
+        Ok(bv)
+    }
+}
+
+/// Formats as a hexadecimal literal, as in `64'h0000FFFF0000FFFF`.
+impl fmt::Display for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}'h", self.width)?;
+        for i in (0..self.width.div_ceil(4)).rev() {
+            let nibble = (0..4).fold(0, |n, b| n | (self.get(i * 4 + b) as u32) << b);
+            write!(f, "{:X}", nibble)?;
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    /// Reads the property `name` as a bit vector, or returns `None` if it is not set.
+    pub fn property_bits(&self, name: &str) -> Result<Option<BitVec>> {
+        self.properties
+            .get(&Atom::from(name))
+            .map(|p| {
+                p.as_bitvec()
+                    .with_context(|| format!("{} of `{}`", name, self.path))
+            })
+            .transpose()
+    }
+
+    /// Reads a one-bit property such as `INIT` of a flip-flop.
+    pub fn bit_property(&self, name: &str, default: bool) -> Result<bool> {
+        Ok(self
+            .property_bits(name)?
+            .map_or(default, |bits| bits.get(0)))
+    }
+}
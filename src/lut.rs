@@ -0,0 +1,391 @@
+//! Truth tables of LUT primitives.
+//!
+//! The function of a `LUTk` instance is given by its `INIT` property: the output for inputs
+//! `I0`..`Ik-1` is the bit of `INIT` at the index whose bit `i` is the value of `Ii`.
+
+use crate::literal::BitVec;
+use crate::netlist::Instance;
+use crate::primitives::PrimitiveKind;
+use anyhow::{ensure, Context, Result};
+use fxhash::FxHashMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The maximum number of inputs of a LUT.
+pub const MAX_INPUTS: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TruthTable {
+    inputs: u8,
+    bits: u64,
+}
+
+/// A classification of simple LUT functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LutFunction {
+    Constant(bool),
+    /// The output equals the input of the index.
+    Buffer(u8),
+    /// The output is the negation of the input of the index.
+    Inverter(u8),
+    Other,
+}
+
+fn mask(inputs: u8) -> u64 {
+    if inputs >= 6 {
+        !0
+    } else {
+        (1 << (1 << inputs)) - 1
+    }
+}
+
+impl TruthTable {
+    /// Creates a truth table of `inputs` inputs. Bits of `bits` beyond `2^inputs` are ignored.
+    pub fn new(inputs: u8, bits: u64) -> Self {
+        assert!(inputs <= MAX_INPUTS, "too many inputs: {}", inputs);
+        TruthTable {
+            inputs,
+            bits: bits & mask(inputs),
+        }
+    }
+
+    /// Creates a truth table from an `INIT` value.
+    pub fn from_init(inputs: u8, init: &BitVec) -> Result<Self> {
+        ensure!(inputs <= MAX_INPUTS, "too many inputs: {}", inputs);
+        let bits = init
+            .to_u64()
+            .filter(|&b| b & !mask(inputs) == 0)
+            .with_context(|| format!("INIT `{}` is too wide for {} inputs", init, inputs))?;
+        Ok(TruthTable::new(inputs, bits))
+    }
+
+    pub fn inputs(&self) -> u8 {
+        self.inputs
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    fn size(&self) -> u32 {
+        1 << self.inputs
+    }
+
+    /// The `INIT` value of the function.
+    pub fn to_init(&self) -> BitVec {
+        BitVec::from_u64(self.size() as usize, self.bits)
+    }
+
+    /// Evaluates the function for the input values with `Ii` at `index` bit `i`.
+    pub fn eval_index(&self, index: u32) -> bool {
+        self.bits >> index & 1 == 1
+    }
+
+    /// Evaluates the function, where `inputs[i]` is the value of `Ii`.
+    pub fn eval(&self, inputs: &[bool]) -> bool {
+        assert_eq!(inputs.len(), self.inputs as usize);
+        let index = inputs
+            .iter()
+            .enumerate()
+            .fold(0, |idx, (i, &v)| idx | (v as u32) << i);
+        self.eval_index(index)
+    }
+
+    fn map_indices(&self, f: impl Fn(u32) -> u32) -> TruthTable {
+        let bits = (0..self.size()).fold(0, |bits, m| bits | (self.eval_index(f(m)) as u64) << m);
+        TruthTable::new(self.inputs, bits)
+    }
+
+    /// The function with `input` fixed to `value`, which no longer depends on `input`.
+    pub fn cofactor(&self, input: u8, value: bool) -> TruthTable {
+        assert!(input < self.inputs);
+        self.map_indices(|m| {
+            if value {
+                m | 1 << input
+            } else {
+                m & !(1 << input)
+            }
+        })
+    }
+
+    /// The function with `input` negated.
+    pub fn invert_input(&self, input: u8) -> TruthTable {
+        assert!(input < self.inputs);
+        self.map_indices(|m| m ^ 1 << input)
+    }
+
+    /// The negation of the function.
+    pub fn invert(&self) -> TruthTable {
+        TruthTable::new(self.inputs, !self.bits)
+    }
+
+    pub fn depends_on(&self, input: u8) -> bool {
+        self.cofactor(input, false) != self.cofactor(input, true)
+    }
+
+    /// Inputs the function depends on.
+    pub fn support(&self) -> Vec<u8> {
+        (0..self.inputs).filter(|&i| self.depends_on(i)).collect()
+    }
+
+    pub fn is_constant(&self) -> Option<bool> {
+        if self.bits == 0 {
+            Some(false)
+        } else if self.bits == mask(self.inputs) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    pub fn classify(&self) -> LutFunction {
+        if let Some(c) = self.is_constant() {
+            return LutFunction::Constant(c);
+        }
+
+        match *self.support() {
+            [i] => {
+                if self.cofactor(i, true).is_constant() == Some(true) {
+                    LutFunction::Buffer(i)
+                } else {
+                    LutFunction::Inverter(i)
+                }
+            }
+            _ => LutFunction::Other,
+        }
+    }
+
+    /// A minimal sum-of-products cover of the function, computed with the Quine-McCluskey
+    /// method.
+    pub fn to_sop(&self) -> Sop {
+        let all = (1u8 << self.inputs).wrapping_sub(1);
+
+        let mut implicants = (0..self.size())
+            .filter(|&m| self.eval_index(m))
+            .map(|m| Cube {
+                care: all,
+                value: m as u8,
+            })
+            .collect::<BTreeSet<_>>();
+        let mut primes = BTreeSet::new();
+
+        while !implicants.is_empty() {
+            let mut merged = BTreeSet::new();
+            let mut used = BTreeSet::new();
+            for a in &implicants {
+                for b in implicants.range(a..).skip(1) {
+                    let diff = a.value ^ b.value;
+                    if a.care == b.care && diff.count_ones() == 1 {
+                        let care = a.care & !diff;
+                        merged.insert(Cube {
+                            care,
+                            value: a.value & care,
+                        });
+                        used.insert(*a);
+                        used.insert(*b);
+                    }
+                }
+            }
+            primes.extend(implicants.difference(&used).copied());
+            implicants = merged;
+        }
+
+        // Pick essential prime implicants first, and then the ones covering the most of the
+        // remaining minterms.
+        let mut uncovered = (0..self.size())
+            .filter(|&m| self.eval_index(m))
+            .collect::<BTreeSet<_>>();
+        let mut cubes = vec![];
+
+        for &m in &uncovered.clone() {
+            let mut covering = primes.iter().filter(|c| c.contains(m));
+            if let (Some(&c), None) = (covering.next(), covering.next()) {
+                if !cubes.contains(&c) {
+                    cubes.push(c);
+                }
+            }
+        }
+        uncovered.retain(|&m| !cubes.iter().any(|c| c.contains(m)));
+
+        while !uncovered.is_empty() {
+            let best = *primes
+                .iter()
+                .max_by_key(|c| {
+                    let n = uncovered.iter().filter(|&&m| c.contains(m)).count();
+                    (n, std::cmp::Reverse(c.care.count_ones()))
+                })
+                .unwrap();
+            uncovered.retain(|&m| !best.contains(m));
+            cubes.push(best);
+        }
+
+        cubes.sort();
+        Sop {
+            inputs: self.inputs,
+            cubes,
+        }
+    }
+
+    /// A reduced ordered BDD of the function, testing the inputs from the highest index.
+    pub fn to_bdd(&self) -> Bdd {
+        let mut bdd = Bdd {
+            nodes: vec![],
+            unique: FxHashMap::default(),
+            root: 0,
+        };
+        bdd.root = bdd.build(self.bits, self.inputs);
+        bdd
+    }
+}
+
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_init())
+    }
+}
+
+/// A product term. Input `i` appears in the term if bit `i` of `care` is set, negated if bit
+/// `i` of `value` is clear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cube {
+    pub care: u8,
+    pub value: u8,
+}
+
+impl Cube {
+    /// Whether the term is true for the input values at `index`.
+    pub fn contains(&self, index: u32) -> bool {
+        index as u8 & self.care == self.value
+    }
+}
+
+/// A function in the sum-of-products form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sop {
+    pub inputs: u8,
+    pub cubes: Vec<Cube>,
+}
+
+impl Sop {
+    pub fn eval(&self, inputs: &[bool]) -> bool {
+        let index = inputs
+            .iter()
+            .enumerate()
+            .fold(0, |idx, (i, &v)| idx | (v as u32) << i);
+        self.cubes.iter().any(|c| c.contains(index))
+    }
+}
+
+/// Formats as in `I0 & !I1 | I2`.
+impl fmt::Display for Sop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.cubes.is_empty() {
+            return write!(f, "0");
+        }
+
+        for (i, c) in self.cubes.iter().enumerate() {
+            if i != 0 {
+                write!(f, " | ")?;
+            }
+            if c.care == 0 {
+                write!(f, "1")?;
+                continue;
+            }
+            let mut first = true;
+            for b in (0..self.inputs).filter(|b| c.care >> b & 1 == 1) {
+                if !first {
+                    write!(f, " & ")?;
+                }
+                first = false;
+                let neg = if c.value >> b & 1 == 1 { "" } else { "!" };
+                write!(f, "{}I{}", neg, b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A node of a [`Bdd`](Bdd), which selects `high` if input `var` is true and `low` otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BddNode {
+    pub var: u8,
+    pub low: usize,
+    pub high: usize,
+}
+
+/// A reduced ordered binary decision diagram. Node references `0` and `1` are the constant
+/// terminals, and `n + 2` refers to `nodes()[n]`.
+#[derive(Debug, Clone)]
+pub struct Bdd {
+    nodes: Vec<BddNode>,
+    unique: FxHashMap<BddNode, usize>,
+    root: usize,
+}
+
+impl Bdd {
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    pub fn nodes(&self) -> &[BddNode] {
+        &self.nodes
+    }
+
+    pub fn eval(&self, inputs: &[bool]) -> bool {
+        let mut r = self.root;
+        while r >= 2 {
+            let n = &self.nodes[r - 2];
+            r = if inputs[n.var as usize] {
+                n.high
+            } else {
+                n.low
+            };
+        }
+        r == 1
+    }
+
+    /// Builds a node for the function of the low `2^vars` bits of `bits`.
+    fn build(&mut self, bits: u64, vars: u8) -> usize {
+        if vars == 0 {
+            return (bits & 1) as usize;
+        }
+
+        let half = 1u32 << (vars - 1);
+        let low = self.build(bits & mask(vars - 1), vars - 1);
+        let high = self.build(bits >> half, vars - 1);
+        if low == high {
+            return low;
+        }
+
+        let node = BddNode {
+            var: vars - 1,
+            low,
+            high,
+        };
+        let len = self.nodes.len();
+        let r = *self.unique.entry(node).or_insert(len + 2);
+        if r == len + 2 {
+            self.nodes.push(node);
+        }
+        r
+    }
+}
+
+impl Instance {
+    /// The truth table of a `LUT1`..`LUT6` instance, or `None` for other instances.
+    pub fn truth_table(&self) -> Result<Option<TruthTable>> {
+        let k = match self.primitive_kind() {
+            Some(PrimitiveKind::Lut(k)) => k,
+            _ => return Ok(None),
+        };
+
+        match self.property_bits("INIT")? {
+            Some(init) => {
+                let table = TruthTable::from_init(k, &init)
+                    .with_context(|| format!("INIT of `{}`", self.path))?;
+                Ok(Some(table))
+            }
+            None => Ok(Some(TruthTable::new(k, 0))),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Legalization of names into EDIF identifiers, the inverse of [`Name`].
+//!
+//! An identifier starts with a letter or `&`, followed by letters, digits and `_`. Like Vivado,
+//! every other character, such as the `[`, `]`, `.` and `/` of HDL names, becomes `_`, and a
+//! name not starting with a letter is prefixed with `&`: `x_reg[0]` is written
+//! `(rename x_reg_0_ "x_reg[0]")`. A [`Scope`] also makes the identifiers unique, with a `_N`
+//! suffix. Identifiers are case-insensitive, so `A[0]` and `a[0]` get `A_0_` and `a_0__1`.
+
+use crate::ast::{FxIndexMap, Name};
+use crate::atom::Atom;
+use fxhash::FxHashMap;
+
+/// Whether `name` is an EDIF identifier.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '&')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The EDIF identifier of `name`, which may not be unique.
+pub fn legalize(name: &str) -> String {
+    let mut legal = String::with_capacity(name.len() + 1);
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '&') {
+        legal.push('&');
+    }
+    for (i, c) in name.chars().enumerate() {
+        legal.push(match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => c,
+            '&' if i == 0 => c,
+            _ => '_',
+        });
+    }
+    legal
+}
+
+/// Unique identifiers for the names of a scope, and the names they stand for.
+#[derive(Clone, Debug, Default)]
+pub struct Scope {
+    idents: FxIndexMap<Atom, Atom>,
+    /// The names by the lower-cased identifier.
+    names: FxHashMap<String, Atom>,
+}
+
+impl Scope {
+    /// The identifier of `name`, legalized and made unique the first time.
+    pub fn ident(&mut self, name: &Atom) -> &Atom {
+        if !self.idents.contains_key(name) {
+            let legal = legalize(name);
+            let mut ident = legal.clone();
+            for i in 1.. {
+                if !self.names.contains_key(&ident.to_ascii_lowercase()) {
+                    break;
+                }
+                ident = format!("{}_{}", legal, i);
+            }
+            self.names.insert(ident.to_ascii_lowercase(), name.clone());
+            self.idents.insert(name.clone(), Atom::from(ident));
+        }
+        &self.idents[name]
+    }
+
+    /// The [`Name`] of `name`, renamed from it if it is not its identifier.
+    pub fn name(&mut self, name: &Atom) -> Name {
+        let ident = self.ident(name).clone();
+        Name {
+            rename_from: (ident != *name).then(|| name.to_string()),
+            name: ident,
+        }
+    }
+
+    /// The identifier of `name`, if it has one.
+    pub fn get(&self, name: &Atom) -> Option<&Atom> {
+        self.idents.get(name)
+    }
+
+    /// The name of the identifier `ident`, in any case.
+    pub fn original(&self, ident: &str) -> Option<&Atom> {
+        self.names.get(&ident.to_ascii_lowercase())
+    }
+
+    /// The names and their identifiers, in the order of first use.
+    pub fn iter(&self) -> impl Iterator<Item = (&Atom, &Atom)> {
+        self.idents.iter()
+    }
+}
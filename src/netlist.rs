@@ -1,8 +1,10 @@
-use crate::ast;
+use crate::ast::{self, FxIndexMap, FxIndexSet, Span};
 use crate::atom::Atom;
+use crate::visit::netlist::{self as visit, Visit, VisitMut};
 use fxhash::{FxHashMap, FxHashSet};
 use std::fmt::{self, Debug};
 use std::mem;
+use std::ops::ControlFlow;
 
 /// Create a [`Netlist`](Netlist) from a string of an EDIF netlist.
 pub fn from_str(s: &str) -> anyhow::Result<Netlist> {
@@ -10,16 +12,35 @@ pub fn from_str(s: &str) -> anyhow::Result<Netlist> {
     Ok(Netlist::from_ast(&ast))
 }
 
+/// Create a [`Netlist`](Netlist) from a string of an EDIF netlist, parsing and elaborating it
+/// on several threads.
+#[cfg(feature = "rayon")]
+pub fn from_str_parallel(s: &str) -> anyhow::Result<Netlist> {
+    let ast = crate::parser::EdifParser::parse_from_str_parallel(s)?;
+    Ok(Netlist::from_ast_parallel(&ast))
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Path(Vec<Atom>);
 
 impl Path {
+    /// A path of the top-level instance.
+    pub fn root(name: Atom) -> Self {
+        Path(vec![name])
+    }
+
     fn from_path_and_name(path: &[Atom], name: Atom) -> Self {
         let mut v = path.to_vec();
         v.push(name);
         Path(v)
     }
 
+    /// A path of the child instance named `name`.
+    pub fn child(&self, name: Atom) -> Self {
+        Path::from_path_and_name(&self.0, name)
+    }
+
     pub fn name(&self) -> Atom {
         self.0.last().unwrap().clone()
     }
@@ -28,14 +49,32 @@ impl Path {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     fn push(&mut self, component: Atom) {
         self.0.push(component);
     }
 
-    fn as_slice(&self) -> &[Atom] {
+    pub fn as_slice(&self) -> &[Atom] {
         self.0.as_slice()
     }
 
+    /// The path of the parent instance, or `None` for the top-level instance.
+    pub fn parent(&self) -> Option<Path> {
+        if self.0.len() > 1 {
+            Some(Path(self.0[..self.0.len() - 1].to_vec()))
+        } else {
+            None
+        }
+    }
+
+    /// Whether `self` is `prefix` or one of its descendants.
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
     pub fn to_flattened_path(&self) -> Path {
         let len = self.0.len();
         if len == 1 {
@@ -51,6 +90,157 @@ impl Path {
     }
 }
 
+/// Matches a `/`-separated path such as `main/inner/x_reg_0_` against a glob. `*` matches
+/// within a path component, `**` across components, and `?` a character.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[u8], s: &[u8]) -> bool {
+        match pattern {
+            [] => s.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=s.len()).any(|i| matches(rest, &s[i..])),
+            [b'*', rest @ ..] => (0..=s.len())
+                .take_while(|&i| i == 0 || s[i - 1] != b'/')
+                .any(|i| matches(rest, &s[i..])),
+            [b'?', rest @ ..] => {
+                matches!(s, [c, tail @ ..] if *c != b'/' && matches(rest, tail))
+            }
+            [c, rest @ ..] => matches!(s, [d, tail @ ..] if c == d && matches(rest, tail)),
+        }
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+/// The name of a port, without the range of an array port such as `ret[1:0]`.
+pub(crate) fn port_name(port: &ast::Port) -> String {
+    match (&port.name.rename_from, port.kind) {
+        (Some(s), ast::PortKind::Array(_)) => match s.find('[') {
+            Some(i) => s[..i].to_string(),
+            None => s.clone(),
+        },
+        (Some(s), ast::PortKind::Single) => s.clone(),
+        (None, _) => port.name.name.to_string(),
+    }
+}
+
+/// Formats a pin as in `main/inner/x_reg_0_/D` or `main/ret[1]`.
+pub(crate) fn pin_string(p: &PortRef) -> String {
+    match p.member {
+        Some(m) => format!("{}/{}[{}]", p.instance, p.port, m),
+        None => format!("{}/{}", p.instance, p.port),
+    }
+}
+
+/// The definitions of the cells under an instance, as the writers write them.
+///
+/// Editing one instance of a hierarchical cell, as [`eco`](crate::eco) and [`opt`](crate::opt)
+/// do, makes it differ from the other instances of the cell. Each distinct definition is then
+/// a cell of its own, whose name gets a `_1`, `_2`... suffix when it is not the first one of
+/// its cell, or when a cell of another library has the name. Leaf cells keep their names and
+/// the interface of their first instance.
+pub(crate) struct Definitions<'a> {
+    /// The definitions, children first, and their names.
+    pub cells: Vec<(&'a Instance, Atom)>,
+    /// The definitions of each cell.
+    of_cell: FxHashMap<(&'a Atom, &'a Atom), Vec<usize>>,
+    /// The definition of each instance.
+    index: FxHashMap<&'a Path, usize>,
+    names: FxHashSet<Atom>,
+}
+
+/// The pins of `net` of `scope`, by the name of their instance, or `None` for `scope`.
+fn relative_pins<'a>(
+    scope: &Instance,
+    net: &'a Net,
+) -> FxHashSet<(Option<&'a Atom>, &'a Atom, Option<i32>)> {
+    net.ports
+        .iter()
+        .map(|p| {
+            let inst = (p.instance != scope.path).then(|| p.instance.as_slice().last());
+            (inst.flatten(), &p.port, p.member)
+        })
+        .collect()
+}
+
+impl<'a> Definitions<'a> {
+    pub fn new(top: &'a Instance) -> Self {
+        let mut defs = Definitions {
+            cells: vec![],
+            of_cell: FxHashMap::default(),
+            index: FxHashMap::default(),
+            names: top
+                .walk()
+                .filter(|inst| inst.is_leaf())
+                .map(|inst| inst.cell.clone())
+                .collect(),
+        };
+        defs.add(top);
+        defs
+    }
+
+    /// The name of the definition of `inst`, which is `top` or one of its descendants.
+    pub fn name(&self, inst: &Instance) -> &Atom {
+        &self.cells[self.index[&inst.path]].1
+    }
+
+    fn add(&mut self, inst: &'a Instance) {
+        for child in inst.instances.values() {
+            self.add(child);
+        }
+        let defs = self.of_cell.entry((&inst.lib, &inst.cell)).or_default();
+        let cells = &self.cells;
+        let index = &self.index;
+        let found = defs.iter().copied().find(|&i| {
+            let def = cells[i].0;
+            match (def.is_leaf(), inst.is_leaf()) {
+                (true, true) => true,
+                (false, false) => same_definition(index, def, inst),
+                _ => false,
+            }
+        });
+        let i = match found {
+            Some(i) => i,
+            None => {
+                let mut name = inst.cell.clone();
+                if !defs.is_empty() || !(inst.is_leaf() || self.names.insert(name.clone())) {
+                    for k in 1.. {
+                        name = Atom::from(format!("{}_{}", inst.cell, k));
+                        if self.names.insert(name.clone()) {
+                            break;
+                        }
+                    }
+                }
+                defs.push(self.cells.len());
+                self.cells.push((inst, name));
+                self.cells.len() - 1
+            }
+        };
+        self.index.insert(&inst.path, i);
+    }
+}
+
+/// Whether the hierarchical instances `a` and `b` have the same contents, their children
+/// having the definitions in `index`.
+fn same_definition(index: &FxHashMap<&Path, usize>, a: &Instance, b: &Instance) -> bool {
+    a.interface.len() == b.interface.len()
+        && a.interface
+            .values()
+            .zip(b.interface.values())
+            .all(|(x, y)| (&x.name, x.kind, x.dir) == (&y.name, y.kind, y.dir))
+        && a.instances.len() == b.instances.len()
+        && a.instances.iter().all(|(name, x)| {
+            b.instances.get(name).is_some_and(|y| {
+                x.rename_from == y.rename_from
+                    && x.properties == y.properties
+                    && index[&x.path] == index[&y.path]
+            })
+        })
+        && a.nets.len() == b.nets.len()
+        && a.nets.iter().all(|(name, x)| {
+            b.nets.get(name).is_some_and(|y| {
+                x.rename_from == y.rename_from && relative_pins(a, x) == relative_pins(b, y)
+            })
+        })
+}
+
 impl fmt::Display for Path {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for (i, c) in self.0.iter().enumerate() {
@@ -64,49 +254,194 @@ impl fmt::Display for Path {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Instance {
     pub path: Path,
-    pub instances: FxHashMap<Atom, Instance>,
-    pub nets: FxHashMap<Atom, Net>,
-    pub interface: FxHashMap<Atom, ast::Port>,
+    pub instances: FxIndexMap<Atom, Instance>,
+    pub nets: FxIndexMap<Atom, Net>,
+    pub interface: FxIndexMap<Atom, ast::Port>,
     pub lib: Atom,
     pub cell: Atom,
-    pub properties: FxHashMap<Atom, ast::Property>,
+    pub properties: FxIndexMap<Atom, ast::Property>,
+    /// The span of each `property` read from the source, by name.
+    pub property_spans: FxIndexMap<Atom, Span>,
+    /// The original name given by `rename`, as in `x_reg[3]`.
+    pub rename_from: Option<String>,
+    /// Whether the cell is not defined in the netlist: it is in an `external` library, has no
+    /// contents without being a primitive, as the stubs of out-of-context modules, or was not
+    /// found, in which case its interface is inferred from the connections to it.
+    pub black_box: bool,
+    /// The span of the `instance`, or of the `design` for the top-level instance.
+    pub span: Span,
+}
+
+/// Whether a cell has no contents and is neither in the [primitive catalogue](crate::primitives)
+/// nor in the primitive library of Vivado.
+fn is_stub(lib: &Atom, cell: &Atom, view: &ast::View) -> bool {
+    view.contents.is_empty()
+        && &**lib != "hdi_primitives"
+        && crate::primitives::lookup(cell).is_none()
 }
 
 impl Instance {
+    /// Whether the instance is a primitive, i.e. has neither child instances nor nets.
+    pub fn is_leaf(&self) -> bool {
+        self.instances.is_empty() && self.nets.is_empty()
+    }
+
+    /// Looks up the instance at `path`, which is `self` or one of its descendants.
+    pub fn get(&self, path: &Path) -> Option<&Instance> {
+        let rest = path.0.strip_prefix(self.path.as_slice())?;
+        rest.iter()
+            .try_fold(self, |inst, name| inst.instances.get(name))
+    }
+
+    /// Looks up the instance at `path` mutably, which is `self` or one of its descendants.
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Instance> {
+        let len = self.path.len();
+        if !path.0.starts_with(self.path.as_slice()) {
+            return None;
+        }
+        path.0[len..]
+            .iter()
+            .try_fold(self, |inst, name| inst.instances.get_mut(name))
+    }
+
+    /// Iterates over `self` and all of its descendants in pre-order.
+    pub fn walk(&self) -> impl Iterator<Item = &Instance> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let inst = stack.pop()?;
+            stack.extend(inst.instances.values());
+            Some(inst)
+        })
+    }
+
+    /// The port of the pin `p` of a net of `self`, which is a port of `self` or of one of its
+    /// children, or an error if `p` dangles as [`Netlist::verify_references`] reports.
+    pub fn pin_port(&self, p: &PortRef) -> anyhow::Result<&ast::Port> {
+        let inst = if p.instance == self.path {
+            Some(self)
+        } else {
+            p.instance
+                .as_slice()
+                .last()
+                .and_then(|name| self.instances.get(name))
+                .filter(|child| child.path == p.instance)
+        };
+        inst.and_then(|inst| inst.interface.get(&p.port))
+            .filter(|port| match (port.kind, p.member) {
+                (ast::PortKind::Single, None) => true,
+                (ast::PortKind::Array(n), Some(m)) => 0 <= m && m < n,
+                _ => false,
+            })
+            .ok_or_else(|| anyhow::anyhow!("pin `{}` does not exist", pin_string(p)))
+    }
+
     fn from_ast(
-        ast: &crate::ast::Edif,
+        cx: &Elaboration,
         parent_path: &[Atom],
         inst_name: &Atom,
-        properties: &FxHashMap<ast::Name, ast::Property>,
+        rename_from: Option<&String>,
+        properties: &FxIndexMap<ast::Name, ast::Property>,
         lib: &Atom,
         cell: &Atom,
     ) -> Self {
-        let view = &ast.libs[lib].cells[cell].view;
-
         let path = Path::from_path_and_name(parent_path, inst_name.clone());
+        let properties = properties
+            .iter()
+            .map(|(k, v)| (k.name.clone(), v.clone()))
+            .collect();
 
-        let mut instances = FxHashMap::default();
-        let mut nets = FxHashMap::default();
+        let library = cx.ast.libs.get(lib);
+        let view = match library.and_then(|l| l.cells.get(cell)) {
+            Some(c) => &c.view,
+            None => {
+                return Instance {
+                    path,
+                    instances: FxIndexMap::default(),
+                    nets: FxIndexMap::default(),
+                    interface: FxIndexMap::default(),
+                    properties,
+                    property_spans: FxIndexMap::default(),
+                    cell: cell.clone(),
+                    lib: lib.clone(),
+                    rename_from: rename_from.cloned(),
+                    black_box: true,
+                    span: Span::default(),
+                }
+            }
+        };
 
+        let children = view
+            .contents
+            .iter()
+            .filter_map(|c| match c {
+                ast::Content::Instance(inst) => Some(inst),
+                ast::Content::Net(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let elaborate = |inst: &&ast::Instance| {
+            let mut child = Instance::from_ast(
+                cx,
+                path.as_slice(),
+                &inst.name.name,
+                inst.name.rename_from.as_ref(),
+                &inst.properties,
+                inst.libraryref.as_ref().unwrap_or(lib),
+                &inst.cellref,
+            );
+            child.span = inst.span;
+            child.property_spans = inst.property_spans.clone();
+            child
+        };
+        #[cfg(feature = "rayon")]
+        let children = if cx.parallel {
+            use rayon::prelude::*;
+            children.par_iter().map(elaborate).collect::<Vec<_>>()
+        } else {
+            children.iter().map(elaborate).collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let children = children.iter().map(elaborate).collect::<Vec<_>>();
+
+        let mut instances = FxIndexMap::default();
+        for inst in children {
+            instances.insert(inst.path.name(), inst);
+        }
+        let mut nets = FxIndexMap::default();
         for c in &view.contents {
-            match c {
-                ast::Content::Instance(inst) => {
-                    let name = inst.name.name.clone();
-                    let inst = Instance::from_ast(
-                        ast,
-                        path.as_slice(),
-                        &name,
-                        &inst.properties,
-                        inst.libraryref.as_ref().unwrap(),
-                        &inst.cellref,
-                    );
-                    instances.insert(name, inst);
-                }
-                ast::Content::Net(net) => {
-                    nets.insert(Atom::from(&net.name.name), Net::from_ast(&net, &path));
-                }
+            if let ast::Content::Net(net) = c {
+                nets.insert(Atom::from(&net.name.name), Net::from_ast(net, &path));
+            }
+        }
+
+        // Infer the ports of the unresolved cells, whose directions are unknown.
+        let unresolved = instances
+            .iter()
+            .filter(|(_, inst)| inst.black_box && inst.interface.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect::<FxHashSet<_>>();
+        for p in nets.values().flat_map(|n: &Net| &n.ports) {
+            let name = p.instance.name();
+            if p.instance == path || !unresolved.contains(&name) {
+                continue;
+            }
+            let inst = instances.get_mut(&name).unwrap();
+            let port = inst
+                .interface
+                .entry(p.port.clone())
+                .or_insert_with(|| ast::Port {
+                    kind: ast::PortKind::Single,
+                    dir: ast::Direction::InOut,
+                    name: ast::Name {
+                        name: p.port.clone(),
+                        rename_from: None,
+                    },
+                    span: Span::default(),
+                });
+            if let Some(m) = p.member {
+                port.kind = ast::PortKind::Array(port.kind.width().max(m + 1));
             }
         }
 
@@ -123,28 +458,28 @@ impl Instance {
             instances,
             nets,
             interface,
-            properties: properties
-                .iter()
-                .map(|(k, v)| (k.name.clone(), v.clone()))
-                .collect(),
+            properties,
+            property_spans: FxIndexMap::default(),
             cell: cell.clone(),
             lib: lib.clone(),
+            rename_from: rename_from.cloned(),
+            black_box: library.is_some_and(|l| l.external) || is_stub(lib, cell, view),
+            span: Span::default(),
         }
     }
 
-    fn flatten(&mut self) {
-        let mut if_ports = FxHashSet::default();
-
-        for net in self.nets.values_mut() {
-            net.flatten();
-        }
-
-        self.path = self.path.to_flattened_path();
+    /// Hoists the contents of the children, which are flat already, into `self`.
+    fn hoist(&mut self) {
+        let mut if_ports = FxIndexSet::default();
 
         for (_, mut inst) in mem::take(&mut self.instances) {
-            inst.flatten();
+            // Keep the original names unique by prefixing them with the one of `inst`.
+            let prefix = inst
+                .rename_from
+                .clone()
+                .unwrap_or_else(|| inst.path.name().to_string());
 
-            if inst.instances.is_empty() && inst.nets.is_empty() {
+            if inst.is_leaf() {
                 self.instances.insert(inst.path.name(), inst);
                 continue;
             } else {
@@ -152,7 +487,13 @@ impl Instance {
                     .instances
                     .values()
                     .all(|inst| inst.instances.is_empty()));
-                self.instances.extend(inst.instances);
+                self.instances
+                    .extend(inst.instances.drain(..).map(|(name, mut child)| {
+                        if let Some(r) = &mut child.rename_from {
+                            *r = format!("{}/{}", prefix, r);
+                        }
+                        (name, child)
+                    }));
             }
 
             let inst_path = inst.path;
@@ -173,7 +514,7 @@ impl Instance {
 
             let self_path = &self.path;
             for (name, net) in &mut self.nets {
-                if net.ports.intersection(&if_ports).next().is_some() {
+                if net.ports.iter().any(|p| if_ports.contains(p)) {
                     assert!(merger.merge(|| format!("{}/{}", self_path, name).into(), net));
                 }
             }
@@ -183,7 +524,9 @@ impl Instance {
             for (name, mut net) in inst.nets {
                 if !merger.merge(|| name.clone(), &mut net) {
                     // An internal connection within `inst`.
-                    net.flatten();
+                    if let Some(r) = &mut net.rename_from {
+                        *r = format!("{}/{}", prefix, r);
+                    }
                     assert!(self
                         .nets
                         .insert(format!("{}/{}", inst_path, name).into(), net)
@@ -191,69 +534,27 @@ impl Instance {
                 }
             }
 
-            for (name, mut net) in merger.build() {
-                net.flatten();
+            for (name, net) in merger.build() {
                 assert!(self.nets.insert(name, net).is_none());
             }
         }
-
-        self.path = self.path.to_flattened_path();
-    }
-
-    fn verify_references_inner(
-        &self,
-        port_refs: &mut FxHashMap<Path, Vec<PortRef>>,
-    ) -> anyhow::Result<()> {
-        use std::collections::hash_map::Entry;
-        if let Entry::Occupied(occupied) = port_refs.entry(self.path.clone()) {
-            fn check_array(member: Option<i32>, kind: ast::PortKind) -> bool {
-                use ast::PortKind::*;
-                match (member, kind) {
-                    (None, Single) => true,
-                    (Some(x), Array(y)) => x < y,
-                    _ => false,
-                }
-            }
-            if let Some(rp) = occupied.get().iter().find(|rp| {
-                rp.instance != self.path || !check_array(rp.member, self.interface[&rp.port].kind)
-            }) {
-                anyhow::bail!("Invalid reference {:?}", rp);
-            }
-            occupied.remove_entry();
-        }
-
-        for n in self.nets.values() {
-            for p in &n.ports {
-                if p.instance == self.path {
-                    if !self.interface.contains_key(&p.port) {
-                        anyhow::bail!("Instance '{}' does not have port '{}'.", self.path, p.port);
-                    }
-                    continue;
-                }
-
-                port_refs
-                    .entry(p.instance.clone())
-                    .or_default()
-                    .push(p.clone());
-            }
-        }
-
-        for inst in self.instances.values() {
-            inst.verify_references_inner(port_refs)?;
-        }
-
-        Ok(())
     }
 
     pub fn verify_references(&self) -> anyhow::Result<()> {
-        let mut refs = FxHashMap::default();
-        self.verify_references_inner(&mut refs)?;
+        let mut refs = References {
+            pending: FxHashMap::default(),
+            scope: None,
+        };
+        if let ControlFlow::Break(e) = refs.visit_instance(self) {
+            return Err(e);
+        }
+        let refs = refs.pending;
         if refs.values().all(|v| v.is_empty()) {
             Ok(())
         } else {
             let missing_ports = refs
-                .into_iter()
-                .flat_map(|(_, v)| v)
+                .into_values()
+                .flatten()
                 .map(|p| {
                     let mut inst = p.instance;
                     inst.push(p.port);
@@ -268,7 +569,8 @@ impl Instance {
 
 struct NetMerger {
     idx: FxHashMap<PortRef, usize>,
-    nets: Vec<Option<(Atom, FxHashSet<PortRef>)>>,
+    /// The name, the span of the net the name comes from, and the pins of each merged net.
+    nets: Vec<Option<(Atom, Span, FxIndexSet<PortRef>)>>,
     instance: Path,
 }
 
@@ -304,16 +606,17 @@ impl NetMerger {
                 continue;
             }
 
-            if let Some((name_j, nets_j)) = self.nets[j].take() {
+            if let Some((name_j, span_j, nets_j)) = self.nets[j].take() {
                 match &mut self.nets[i] {
-                    Some((name_i, nets_i)) => {
+                    Some((name_i, span_i, nets_i)) => {
                         if name_j < *name_i {
                             *name_i = name_j;
+                            *span_i = span_j;
                         }
                         nets_i.extend(nets_j);
                     }
                     None => {
-                        self.nets[i] = Some((name_j, nets_j));
+                        self.nets[i] = Some((name_j, span_j, nets_j));
                     }
                 }
             }
@@ -322,9 +625,9 @@ impl NetMerger {
         }
 
         self.nets[i]
-            .get_or_insert_with(|| (net_name(), FxHashSet::default()))
-            .1
-            .extend(net.ports.drain());
+            .get_or_insert_with(|| (net_name(), net.span, FxIndexSet::default()))
+            .2
+            .extend(net.ports.drain(..));
 
         true
     }
@@ -334,25 +637,51 @@ impl NetMerger {
         self.nets
             .into_iter()
             .flatten()
-            .map(move |(name, mut ports)| {
+            .map(move |(name, span, mut ports)| {
                 ports.retain(|p| p.instance != instance);
-                (name, Net { ports })
+                (
+                    name,
+                    Net {
+                        ports,
+                        rename_from: None,
+                        span,
+                    },
+                )
             })
     }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Net {
-    pub ports: FxHashSet<PortRef>,
+    pub ports: FxIndexSet<PortRef>,
+    /// The original name given by `rename`, as in `p_9_out[10]`.
+    pub rename_from: Option<String>,
+    /// The span of the `net`. A net merged by flattening has the span of the one whose name
+    /// it keeps.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PortRef {
     pub instance: Path,
     pub port: Atom,
     pub member: Option<i32>,
 }
 
+/// A net spanning the instance hierarchy, made of the nets connected through the ports of
+/// hierarchical instances.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalNet {
+    /// Paths of the nets joined into this net, starting from the highest level in the
+    /// hierarchy. The last component of each path is the net name.
+    pub segments: Vec<Path>,
+    /// All the pins on the net, including ports of hierarchical instances.
+    pub pins: Vec<PortRef>,
+}
+
 impl Net {
     fn from_ast(ast: &ast::Net, parent_path: &Path) -> Net {
         Net {
@@ -375,42 +704,243 @@ impl Net {
                     }
                 })
                 .collect(),
+            rename_from: ast.name.rename_from.clone(),
+            span: ast.span,
         }
     }
+}
 
-    fn flatten(&mut self) {
-        self.ports = mem::take(&mut self.ports)
-            .into_iter()
-            .map(|mut p| {
-                p.instance = p.instance.to_flattened_path();
-                p
-            })
-            .collect();
+/// Checks the pins referring to each instance as the instance is reached, which is after the
+/// nets of its parent.
+struct References<'a> {
+    /// The pins of the instances not reached yet.
+    pending: FxHashMap<Path, Vec<PortRef>>,
+    scope: Option<&'a Instance>,
+}
+
+impl<'a> Visit<'a, anyhow::Error> for References<'a> {
+    fn visit_instance(&mut self, inst: &'a Instance) -> ControlFlow<anyhow::Error> {
+        fn check_array(member: Option<i32>, kind: ast::PortKind) -> bool {
+            use ast::PortKind::*;
+            match (member, kind) {
+                (None, Single) => true,
+                (Some(x), Array(y)) => x < y,
+                _ => false,
+            }
+        }
+        if let Some(refs) = self.pending.remove(&inst.path) {
+            if let Some(rp) = refs.iter().find(|rp| {
+                !inst
+                    .interface
+                    .get(&rp.port)
+                    .is_some_and(|p| check_array(rp.member, p.kind))
+            }) {
+                return ControlFlow::Break(anyhow::anyhow!("Invalid reference {:?}", rp));
+            }
+        }
+
+        let parent = self.scope.replace(inst);
+        let flow = visit::visit_instance(self, inst);
+        self.scope = parent;
+        flow
+    }
+
+    fn visit_portref(&mut self, _net: &Path, p: &'a PortRef) -> ControlFlow<anyhow::Error> {
+        let scope = self.scope.unwrap();
+        if p.instance != scope.path {
+            self.pending
+                .entry(p.instance.clone())
+                .or_default()
+                .push(p.clone());
+        } else if !scope.interface.contains_key(&p.port) {
+            return ControlFlow::Break(anyhow::anyhow!(
+                "Instance '{}' does not have port '{}'.",
+                scope.path,
+                p.port
+            ));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Flattens the children of an instance, and then hoists their contents into it.
+struct Flatten;
+
+impl VisitMut for Flatten {
+    fn visit_instance_mut(&mut self, inst: &mut Instance) -> ControlFlow<()> {
+        visit::visit_instance_mut(self, inst)?;
+        inst.path = inst.path.to_flattened_path();
+        inst.hoist();
+        ControlFlow::Continue(())
+    }
+
+    fn visit_portref_mut(&mut self, _net: &Path, pin: &mut PortRef) -> ControlFlow<()> {
+        pin.instance = pin.instance.to_flattened_path();
+        ControlFlow::Continue(())
+    }
+}
+
+/// The settings of [`Instance::from_ast`].
+struct Elaboration<'a> {
+    ast: &'a crate::ast::Edif,
+    /// Whether the child instances are elaborated on several threads.
+    #[cfg(feature = "rayon")]
+    parallel: bool,
+}
+
+impl Elaboration<'_> {
+    fn netlist(&self) -> Netlist {
+        let design = &self.ast.design;
+        let mut top = Instance::from_ast(
+            self,
+            &[],
+            &design.inst_name,
+            None,
+            &Default::default(),
+            &design.libraryref,
+            &design.cellref,
+        );
+        top.span = design.span;
+        Netlist { top: Box::new(top) }
     }
 }
 
 /// Instantiated netlist.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Netlist {
     pub top: Box<Instance>,
 }
 
 impl Netlist {
     pub fn from_ast(ast: &crate::ast::Edif) -> Self {
-        let top = Instance::from_ast(
+        Elaboration {
             ast,
-            &[],
-            &ast.design.inst_name,
-            &Default::default(),
-            &ast.design.libraryref,
-            &ast.design.cellref,
-        );
-        Netlist { top: Box::new(top) }
+            #[cfg(feature = "rayon")]
+            parallel: false,
+        }
+        .netlist()
+    }
+
+    /// Elaborates `ast` like [`from_ast`](Self::from_ast), with the child instances on several
+    /// threads. The result does not depend on the number of threads.
+    #[cfg(feature = "rayon")]
+    pub fn from_ast_parallel(ast: &crate::ast::Edif) -> Self {
+        Elaboration {
+            ast,
+            parallel: true,
+        }
+        .netlist()
+    }
+
+    /// The span of the instance or net at `path`.
+    pub fn span(&self, path: &Path) -> Option<Span> {
+        if let Some(inst) = self.instance(path) {
+            return Some(inst.span);
+        }
+        let scope = self.instance(&path.parent()?)?;
+        scope.nets.get(&path.name()).map(|net| net.span)
+    }
+
+    /// The instances of cells not defined in the netlist, sorted by path.
+    pub fn black_boxes(&self) -> Vec<&Instance> {
+        let mut insts = self
+            .top
+            .walk()
+            .filter(|inst| inst.black_box)
+            .collect::<Vec<_>>();
+        insts.sort_by(|a, b| a.path.cmp(&b.path));
+        insts
+    }
+
+    /// Looks up the instance at `path`.
+    pub fn instance(&self, path: &Path) -> Option<&Instance> {
+        self.top.get(path)
+    }
+
+    /// Looks up the instance at `path` mutably.
+    pub fn instance_mut(&mut self, path: &Path) -> Option<&mut Instance> {
+        self.top.get_mut(path)
+    }
+
+    /// Joins the nets in all levels of the hierarchy that are connected through ports of
+    /// hierarchical instances.
+    ///
+    /// The result is sorted by the first segment of each net.
+    pub fn global_nets(&self) -> Vec<GlobalNet> {
+        let mut pin_idx = FxHashMap::<PortRef, usize>::default();
+        let mut parent = Vec::<usize>::new();
+        let mut segments = Vec::<(Path, Option<usize>)>::new();
+
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        for inst in self.top.walk() {
+            for (name, net) in &inst.nets {
+                let mut first = None;
+                for p in &net.ports {
+                    let i = *pin_idx.entry(p.clone()).or_insert_with(|| {
+                        parent.push(parent.len());
+                        parent.len() - 1
+                    });
+                    match first {
+                        None => first = Some(i),
+                        Some(j) => {
+                            let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                            parent[a] = b;
+                        }
+                    }
+                }
+                segments.push((inst.path.child(name.clone()), first));
+            }
+        }
+
+        let mut nets = FxHashMap::<usize, GlobalNet>::default();
+        let mut dangling = vec![];
+        for (path, first) in segments {
+            let net = match first {
+                Some(i) => nets
+                    .entry(find(&mut parent, i))
+                    .or_insert_with(|| GlobalNet {
+                        segments: vec![],
+                        pins: vec![],
+                    }),
+                None => {
+                    dangling.push(GlobalNet {
+                        segments: vec![],
+                        pins: vec![],
+                    });
+                    dangling.last_mut().unwrap()
+                }
+            };
+            net.segments.push(path);
+        }
+        for (p, i) in pin_idx {
+            let root = find(&mut parent, i);
+            nets.get_mut(&root).unwrap().pins.push(p);
+        }
+
+        let mut nets = nets.into_values().chain(dangling).collect::<Vec<_>>();
+        for net in &mut nets {
+            net.segments.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+            net.pins.sort();
+        }
+        nets.sort_by(|a, b| {
+            let a = &a.segments[0];
+            let b = &b.segments[0];
+            (a.len(), a).cmp(&(b.len(), b))
+        });
+        nets
     }
 
     /// Flatten the nested instance hierarchy.
     pub fn flatten(&mut self) {
-        self.top.flatten();
+        let _ = Flatten.visit_instance_mut(&mut self.top);
     }
 
     pub fn verify_references(&self) -> anyhow::Result<()> {
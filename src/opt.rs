@@ -0,0 +1,589 @@
+//! Optimization passes on [`Netlist`](crate::netlist::Netlist)s.
+
+use crate::ast::{Direction, Property};
+use crate::atom::Atom;
+use crate::lut::{LutFunction, TruthTable};
+use crate::netlist::{Instance, Netlist, Path, PortRef};
+use crate::primitives::PrimitiveKind;
+use anyhow::{anyhow, ensure, Result};
+use fxhash::{FxHashMap, FxHashSet};
+use std::fmt;
+
+/// What [`propagate_constants`](propagate_constants) changed.
+#[derive(Debug, Default)]
+pub struct ConstPropReport {
+    /// Nets found to be constant, by their highest segment.
+    pub constant_nets: Vec<(Path, bool)>,
+    /// LUTs whose `INIT` was rewritten for the inputs tied to constants.
+    pub rewritten_luts: Vec<Path>,
+    pub removed_instances: Vec<Path>,
+    pub removed_nets: Vec<Path>,
+}
+
+impl fmt::Display for ConstPropReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (net, value) in &self.constant_nets {
+            writeln!(f, "constant net {} = {}", net, *value as u8)?;
+        }
+        for lut in &self.rewritten_luts {
+            writeln!(f, "rewrote INIT of {}", lut)?;
+        }
+        for inst in &self.removed_instances {
+            writeln!(f, "removed instance {}", inst)?;
+        }
+        for net in &self.removed_nets {
+            writeln!(f, "removed net {}", net)?;
+        }
+        Ok(())
+    }
+}
+
+/// A leaf instance and the global nets on its pins.
+struct Leaf<'a> {
+    inst: &'a Instance,
+    kind: Option<PrimitiveKind>,
+    inputs: Vec<(&'a PortRef, usize)>,
+    outputs: Vec<usize>,
+}
+
+impl Leaf<'_> {
+    fn input(&self, port: &str) -> Option<usize> {
+        self.inputs
+            .iter()
+            .find(|(p, _)| &*p.port == port)
+            .map(|&(_, net)| net)
+    }
+}
+
+/// The constant output of a flip-flop whose `D`, `CE` or set/reset are tied to constants.
+fn ff_constant(leaf: &Leaf, value: &[Option<bool>]) -> Result<Option<bool>> {
+    let (sr, set) = match leaf.kind {
+        Some(PrimitiveKind::Fdre) => ("R", false),
+        Some(PrimitiveKind::Fdse) => ("S", true),
+        Some(PrimitiveKind::Fdce) => ("CLR", false),
+        Some(PrimitiveKind::Fdpe) => ("PRE", true),
+        _ => return Ok(None),
+    };
+    let inst = leaf.inst;
+    let init = inst.bit_property("INIT", set)?;
+    let pin = |port: &str, inverted: bool| {
+        leaf.input(port)
+            .and_then(|net| value[net])
+            .map(|v| v ^ inverted)
+    };
+    let d = pin("D", inst.bit_property("IS_D_INVERTED", false)?);
+    let ce = pin("CE", false);
+    let sr = pin(
+        sr,
+        inst.bit_property(&format!("IS_{}_INVERTED", sr), false)?,
+    );
+
+    // The output stays at `INIT` if it can load nothing else.
+    let holds = (sr == Some(false) || init == set) && (ce == Some(false) || d == Some(init));
+    Ok(if sr == Some(true) && init == set {
+        Some(set)
+    } else if holds {
+        Some(init)
+    } else {
+        None
+    })
+}
+
+fn set_init(lut: &mut Instance, table: TruthTable) {
+    lut.properties.insert(
+        Atom::from("INIT"),
+        Property::String(table.to_init().to_string()),
+    );
+}
+
+/// Removes the pins matching `pred` from the nets of `parent`, and the nets left without pins.
+fn remove_pins(parent: &mut Instance, pred: impl Fn(&PortRef) -> bool, removed: &mut Vec<Path>) {
+    let path = parent.path.clone();
+    parent.nets.retain(|name, net| {
+        net.ports.retain(|p| !pred(p));
+        if net.ports.is_empty() {
+            removed.push(path.child(name.clone()));
+        }
+        !net.ports.is_empty()
+    });
+}
+
+/// Propagates the constants driven by `GND` and `VCC` through LUTs, by rewriting their `INIT`
+/// and disconnecting the inputs tied to constants, and through flip-flops whose output can
+/// never change. Then removes the instances whose outputs reach neither a top-level port nor
+/// a sequential element, along with the nets left unconnected.
+///
+/// Instances that are not in the [primitive catalogue](crate::primitives) are kept.
+pub fn propagate_constants(netlist: &mut Netlist) -> Result<ConstPropReport> {
+    let mut report = ConstPropReport::default();
+    let nets = netlist.global_nets();
+    let hierarchical = netlist
+        .top
+        .walk()
+        .filter(|inst| !inst.is_leaf() && inst.path != netlist.top.path)
+        .map(|inst| inst.path.clone())
+        .collect::<FxHashSet<_>>();
+
+    let mut leaves = netlist
+        .top
+        .walk()
+        .filter(|inst| inst.is_leaf() && inst.path != netlist.top.path)
+        .map(|inst| Leaf {
+            inst,
+            kind: inst.primitive_kind(),
+            inputs: vec![],
+            outputs: vec![],
+        })
+        .collect::<Vec<_>>();
+    leaves.sort_by(|a, b| a.inst.path.cmp(&b.inst.path));
+    let leaf_of = leaves
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (l.inst.path.clone(), i))
+        .collect::<FxHashMap<_, _>>();
+
+    let mut sinks = vec![vec![]; nets.len()];
+    let mut drivers = vec![vec![]; nets.len()];
+    let mut observed = vec![false; nets.len()];
+    for (n, net) in nets.iter().enumerate() {
+        for p in &net.pins {
+            if p.instance == netlist.top.path {
+                observed[n] |= netlist.top.pin_port(p)?.dir != Direction::Input;
+                continue;
+            }
+            let l = match leaf_of.get(&p.instance) {
+                Some(&l) => l,
+                None => continue,
+            };
+            let dir = leaves[l].inst.pin_port(p)?.dir;
+            if dir != Direction::Output {
+                leaves[l].inputs.push((p, n));
+                sinks[n].push((l, p));
+            }
+            if dir != Direction::Input {
+                leaves[l].outputs.push(n);
+                drivers[n].push(l);
+            }
+        }
+    }
+
+    // Propagate the constants.
+    let mut value = vec![None; nets.len()];
+    let mut queue = vec![];
+    let mut tables = leaves
+        .iter()
+        .map(|l| l.inst.truth_table())
+        .collect::<Result<Vec<_>>>()?;
+    let mut disconnected = FxHashSet::default();
+
+    let set_const = |leaf: &Leaf, v: bool, value: &mut [Option<bool>], queue: &mut Vec<usize>| {
+        for &n in &leaf.outputs {
+            if value[n].is_none() && drivers[n].len() == 1 {
+                value[n] = Some(v);
+                queue.push(n);
+            }
+        }
+    };
+
+    for (l, leaf) in leaves.iter().enumerate() {
+        let c = match leaf.kind {
+            Some(PrimitiveKind::Gnd) => Some(false),
+            Some(PrimitiveKind::Vcc) => Some(true),
+            _ => tables[l].and_then(|t| t.is_constant()),
+        };
+        if let Some(c) = c {
+            set_const(leaf, c, &mut value, &mut queue);
+        }
+    }
+
+    while let Some(n) = queue.pop() {
+        let v = value[n].unwrap();
+        for &(l, p) in &sinks[n] {
+            let leaf = &leaves[l];
+            if let Some(table) = &mut tables[l] {
+                let i = match p.port.strip_prefix('I').and_then(|i| i.parse().ok()) {
+                    Some(i) if i < table.inputs() => i,
+                    _ => continue,
+                };
+                *table = table.cofactor(i, v);
+                disconnected.insert(p);
+                if let Some(c) = table.is_constant() {
+                    set_const(leaf, c, &mut value, &mut queue);
+                }
+            } else if let Some(c) = ff_constant(leaf, &value)? {
+                set_const(leaf, c, &mut value, &mut queue);
+            }
+        }
+    }
+
+    for (n, v) in value.iter().enumerate() {
+        if let Some(v) = v {
+            report.constant_nets.push((nets[n].segments[0].clone(), *v));
+        }
+    }
+
+    // Sweep the dead logic until no more instances are removed, as removing a sequential
+    // element can make the logic driving it dead.
+    let mut removed = vec![false; leaves.len()];
+    loop {
+        let mut live_net = observed.clone();
+        let mut live = vec![false; leaves.len()];
+        let mut stack = (0..nets.len()).filter(|&n| observed[n]).collect::<Vec<_>>();
+        let seed = |l: usize, live: &mut Vec<bool>, stack: &mut Vec<usize>| {
+            live[l] = true;
+            for &(p, n) in &leaves[l].inputs {
+                if !disconnected.contains(p) {
+                    stack.push(n);
+                }
+            }
+        };
+
+        for (l, leaf) in leaves.iter().enumerate() {
+            if !removed[l] && leaf.kind.is_none_or(|k| k.is_sequential()) {
+                seed(l, &mut live, &mut stack);
+            }
+        }
+
+        while let Some(n) = stack.pop() {
+            live_net[n] = true;
+            for &l in &drivers[n] {
+                if !removed[l] && !live[l] {
+                    seed(l, &mut live, &mut stack);
+                }
+            }
+        }
+
+        let mut any = false;
+        for (l, leaf) in leaves.iter().enumerate() {
+            let reached = leaf.outputs.iter().any(|&n| live_net[n]);
+            if !removed[l] && leaf.kind.is_some() && !reached {
+                removed[l] = true;
+                any = true;
+            }
+        }
+        if !any {
+            break;
+        }
+    }
+
+    // Apply the changes.
+    let mut inits = vec![];
+    for (l, leaf) in leaves.iter().enumerate() {
+        if let Some(table) = tables[l] {
+            if !removed[l] && Some(table) != leaf.inst.truth_table()? {
+                inits.push((leaf.inst.path.clone(), table));
+            }
+        }
+    }
+    let disconnected = disconnected.into_iter().cloned().collect::<FxHashSet<_>>();
+    let removed = leaves
+        .iter()
+        .zip(&removed)
+        .filter(|(_, &r)| r)
+        .map(|(l, _)| l.inst.path.clone())
+        .collect::<FxHashSet<_>>();
+
+    for (path, table) in inits {
+        set_init(netlist.instance_mut(&path).unwrap(), table);
+        report.rewritten_luts.push(path);
+    }
+
+    let mut parents = disconnected
+        .iter()
+        .map(|p| &p.instance)
+        .chain(&removed)
+        .filter_map(|p| p.parent())
+        .collect::<FxHashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    parents.sort();
+
+    for parent in parents {
+        let inst = netlist.instance_mut(&parent).unwrap();
+        remove_pins(
+            inst,
+            |p| disconnected.contains(p) || removed.contains(&p.instance),
+            &mut report.removed_nets,
+        );
+        inst.instances
+            .retain(|_, child| !removed.contains(&child.path));
+    }
+    report.removed_instances.extend(removed);
+
+    // Remove the hierarchical instances left without contents.
+    loop {
+        let empty = netlist
+            .top
+            .walk()
+            .filter(|inst| {
+                hierarchical.contains(&inst.path)
+                    && inst.instances.is_empty()
+                    && inst.nets.values().all(|net| net.ports.len() <= 1)
+            })
+            .map(|inst| inst.path.clone())
+            .collect::<Vec<_>>();
+        if empty.is_empty() {
+            break;
+        }
+        for path in empty {
+            let parent = netlist.instance_mut(&path.parent().unwrap()).unwrap();
+            parent.instances.shift_remove(&path.name());
+            remove_pins(parent, |p| p.instance == path, &mut report.removed_nets);
+            report.removed_instances.push(path);
+        }
+    }
+
+    report.rewritten_luts.sort();
+    report.removed_instances.sort();
+    report.removed_nets.sort();
+
+    Ok(report)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AbsorbOptions {
+    /// Keep the I/O buffers such as `IBUF` and `OBUF`.
+    pub keep_io_buffers: bool,
+    /// Keep the global clock buffers such as `BUFG`.
+    pub keep_clock_buffers: bool,
+    /// Do not fold inverters into the LUTs they drive.
+    pub keep_inverters: bool,
+}
+
+/// What [`absorb_buffers`](absorb_buffers) changed.
+#[derive(Debug, Default)]
+pub struct AbsorbReport {
+    pub removed_instances: Vec<Path>,
+    /// LUTs whose `INIT` was rewritten to absorb an inverter.
+    pub rewritten_luts: Vec<Path>,
+    /// The removed nets and the nets they were merged into.
+    pub renames: FxHashMap<Path, Path>,
+}
+
+impl fmt::Display for AbsorbReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for inst in &self.removed_instances {
+            writeln!(f, "removed instance {}", inst)?;
+        }
+        for lut in &self.rewritten_luts {
+            writeln!(f, "rewrote INIT of {}", lut)?;
+        }
+        let mut renames = self.renames.iter().collect::<Vec<_>>();
+        renames.sort();
+        for (from, to) in renames {
+            writeln!(f, "merged net {} into {}", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+/// The input and output pins of `inst` if it is a buffer to remove.
+fn buffer_pins(
+    inst: &Instance,
+    options: &AbsorbOptions,
+) -> Result<Option<(&'static str, &'static str)>> {
+    use PrimitiveKind::*;
+    Ok(match inst.primitive_kind() {
+        Some(Lut(1)) => match inst.truth_table()?.map(|t| t.classify()) {
+            Some(LutFunction::Buffer(0)) => Some(("I0", "O")),
+            _ => None,
+        },
+        Some(Buf) => Some(("I", "O")),
+        Some(Ibuf) | Some(Ibufg) | Some(Obuf) if !options.keep_io_buffers => Some(("I", "O")),
+        Some(Bufg) if !options.keep_clock_buffers => Some(("I", "O")),
+        _ => None,
+    })
+}
+
+/// The input and output pins of `inst` if it is an inverter.
+fn inverter_pins(inst: &Instance) -> Result<Option<(&'static str, &'static str)>> {
+    Ok(match inst.primitive_kind() {
+        Some(PrimitiveKind::Lut(1)) => match inst.truth_table()?.map(|t| t.classify()) {
+            Some(LutFunction::Inverter(0)) => Some(("I0", "O")),
+            _ => None,
+        },
+        Some(PrimitiveKind::Inv) => Some(("I", "O")),
+        _ => None,
+    })
+}
+
+fn pin(instance: &Path, port: &str) -> PortRef {
+    PortRef {
+        instance: instance.clone(),
+        port: Atom::from(port),
+        member: None,
+    }
+}
+
+/// Removes the two-pin cell `leaf` and merges the nets on its `input` and `output` into the
+/// net on the input. Returns `false` without changing anything if either pin is unconnected.
+fn bypass(
+    netlist: &mut Netlist,
+    leaf: &Path,
+    (input, output): (&str, &str),
+    report: &mut AbsorbReport,
+) -> bool {
+    let scope = netlist.instance_mut(&leaf.parent().unwrap()).unwrap();
+    let (input, output) = (pin(leaf, input), pin(leaf, output));
+    let net_of = |p: &PortRef| {
+        scope
+            .nets
+            .iter()
+            .find(|(_, net)| net.ports.contains(p))
+            .map(|(name, _)| name.clone())
+    };
+    let (a, b) = match (net_of(&input), net_of(&output)) {
+        (Some(a), Some(b)) if a != b => (a, b),
+        _ => return false,
+    };
+
+    let removed = scope.nets.shift_remove(&b).unwrap();
+    let net = scope.nets.get_mut(&a).unwrap();
+    net.ports.extend(removed.ports);
+    net.ports.shift_remove(&input);
+    net.ports.shift_remove(&output);
+    scope.instances.shift_remove(&leaf.name());
+
+    report
+        .renames
+        .insert(scope.path.child(b), scope.path.child(a));
+    report.removed_instances.push(leaf.clone());
+    true
+}
+
+/// Removes buffers, including `LUT1`s that pass their input through, by merging the nets on
+/// either side, and folds inverters into the LUTs they drive by rewriting their `INIT`.
+///
+/// An inverter is folded only if it drives nothing but LUT inputs, possibly through ports
+/// of hierarchical instances. The nets on the input side survive the merges.
+pub fn absorb_buffers(netlist: &mut Netlist, options: &AbsorbOptions) -> Result<AbsorbReport> {
+    let mut report = AbsorbReport::default();
+
+    // Folding an inverter into an inverting `LUT1` makes it a buffer, so repeat until nothing
+    // changes.
+    loop {
+        let mut changed = false;
+
+        let mut buffers = vec![];
+        for inst in netlist.top.walk().filter(|inst| inst.is_leaf()) {
+            if let Some(pins) = buffer_pins(inst, options)? {
+                buffers.push((inst.path.clone(), pins));
+            }
+        }
+        buffers.sort();
+        for (path, pins) in buffers {
+            changed |= bypass(netlist, &path, pins, &mut report);
+        }
+
+        if !options.keep_inverters {
+            // A fold rewrites the LUTs it drives and removes the inverter, which may be in
+            // another fold of the pass, such as for chained inverters. Those wait for the next
+            // pass.
+            let mut touched = FxHashSet::default();
+            for (inv, pins, luts) in foldable_inverters(netlist)? {
+                if touched.contains(&inv) || luts.iter().any(|(lut, _)| touched.contains(lut)) {
+                    continue;
+                }
+                for (lut, i) in &luts {
+                    let inst = netlist
+                        .instance_mut(lut)
+                        .ok_or_else(|| anyhow!("LUT `{}` not found", lut))?;
+                    let table = inst
+                        .truth_table()?
+                        .ok_or_else(|| anyhow!("`{}` is not a LUT", lut))?
+                        .invert_input(*i);
+                    set_init(inst, table);
+                    report.rewritten_luts.push(lut.clone());
+                }
+                ensure!(
+                    bypass(netlist, &inv, pins, &mut report),
+                    "inverter `{}` is not connected",
+                    inv
+                );
+                touched.insert(inv);
+                touched.extend(luts.into_iter().map(|(lut, _)| lut));
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    // Point the renames at the surviving nets.
+    let renames = report.renames.clone();
+    for to in report.renames.values_mut() {
+        while let Some(next) = renames.get(to) {
+            *to = next.clone();
+        }
+    }
+
+    report.removed_instances.sort();
+    report.rewritten_luts.sort();
+    report.rewritten_luts.dedup();
+    let removed = &report.removed_instances;
+    report
+        .rewritten_luts
+        .retain(|lut| removed.binary_search(lut).is_err());
+
+    Ok(report)
+}
+
+type Fold = (Path, (&'static str, &'static str), Vec<(Path, u8)>);
+
+/// Inverters that drive only LUT inputs, with the LUT pins to invert.
+fn foldable_inverters(netlist: &Netlist) -> Result<Vec<Fold>> {
+    let nets = netlist.global_nets();
+    let net_of = nets
+        .iter()
+        .enumerate()
+        .flat_map(|(i, net)| net.pins.iter().map(move |p| (p, i)))
+        .collect::<FxHashMap<_, _>>();
+
+    let mut folds = vec![];
+    for inst in netlist.top.walk().filter(|inst| inst.is_leaf()) {
+        let pins = match inverter_pins(inst)? {
+            Some(pins) => pins,
+            None => continue,
+        };
+        let (input, output) = (pin(&inst.path, pins.0), pin(&inst.path, pins.1));
+        let n = match (net_of.get(&input), net_of.get(&output)) {
+            (Some(_), Some(&n)) => n,
+            _ => continue,
+        };
+
+        let mut luts = vec![];
+        let mut legal = true;
+        for p in nets[n].pins.iter().filter(|&p| *p != output) {
+            let sink = match netlist.instance(&p.instance) {
+                Some(sink) if p.instance != netlist.top.path => sink,
+                _ => {
+                    legal = false;
+                    break;
+                }
+            };
+            if !sink.is_leaf() {
+                continue;
+            }
+            let i = match (sink.truth_table()?, p.port.strip_prefix('I')) {
+                (Some(t), Some(i)) => i.parse::<u8>().ok().filter(|&i| i < t.inputs()),
+                _ => None,
+            };
+            match i {
+                Some(i) => luts.push((p.instance.clone(), i)),
+                None => {
+                    legal = false;
+                    break;
+                }
+            }
+        }
+
+        if legal && !luts.is_empty() {
+            folds.push((inst.path.clone(), pins, luts));
+        }
+    }
+
+    folds.sort();
+    Ok(folds)
+}
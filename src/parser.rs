@@ -2,10 +2,11 @@ use anyhow::{anyhow, bail, ensure, Context, Result};
 
 use crate::ast::*;
 use crate::atom::Atom;
-use crate::sexpr::{Expr, ExprKind};
-use fxhash::FxHashMap;
+use crate::sexpr::{Expr, ExprKind, Location};
+use std::convert::TryFrom;
 use std::sync::Arc;
 
+#[derive(Default)]
 pub struct EdifParser {}
 
 macro_rules! next_elem {
@@ -18,7 +19,7 @@ macro_rules! next_elem {
 macro_rules! ensure_exhausted {
     ($it:expr) => {
         if let Some(item) = $it.next() {
-            bail!("list not exhausted at {:?}", item.pos)
+            bail!("list not exhausted at {}", item.span)
         }
     };
 }
@@ -29,16 +30,44 @@ impl EdifParser {
     }
 
     pub fn parse_from_str(s: &str) -> Result<Edif> {
-        use combine::Parser;
-
-        let e = crate::sexpr::sexpr_parser()
-            .easy_parse(combine::stream::state::State::new(s))
-            .unwrap()
-            .0;
-
+        let e = parse_sexpr(s, Location::default())?;
         EdifParser::new().parse_expr(&e)
     }
 
+    /// Parses `s` like [`parse_from_str`](Self::parse_from_str), with the `cell`s of the
+    /// libraries parsed concurrently. The result does not depend on the number of threads.
+    #[cfg(feature = "rayon")]
+    pub fn parse_from_str_parallel(s: &str) -> Result<Edif> {
+        use rayon::prelude::*;
+
+        let (skeleton, spans) = split_cells(s)?;
+        let mut edif =
+            EdifParser::new().parse_expr(&parse_sexpr(&skeleton.text, Location::default())?)?;
+        for lib in edif.libs.values_mut() {
+            lib.span = skeleton.to_source(lib.span);
+        }
+        edif.design.span = skeleton.to_source(edif.design.span);
+
+        // The errors are collected too, so that the first one in the source is returned.
+        let cells = spans
+            .par_iter()
+            .map(|span| {
+                let e = parse_sexpr(&s[span.range.clone()], span.loc)?;
+                EdifParser::new().parse_cell(&e)
+            })
+            .collect::<Vec<Result<_>>>();
+
+        // Insert the cells in the order of the source, as `parse_library` does.
+        for (span, cell) in spans.iter().zip(cells) {
+            let cell = cell?;
+            let lib = edif.libs.get_mut(&span.lib).ok_or_else(|| {
+                anyhow!("library `{}` of cell `{}` not found", span.lib, cell.name)
+            })?;
+            lib.cells.insert(cell.name.clone(), cell);
+        }
+        Ok(edif)
+    }
+
     pub fn parse_expr(&self, e: &Expr) -> Result<Edif> {
         let mut it = self.expect_list(e)?.iter();
 
@@ -56,7 +85,7 @@ impl EdifParser {
         self.sym_match(&self.expect_list(next_elem!(it))?[0], atom!("keywordmap"))?;
         self.sym_match(&self.expect_list(next_elem!(it))?[0], atom!("status"))?;
 
-        let mut libs = FxHashMap::<Atom, Library>::default();
+        let mut libs = FxIndexMap::<Atom, Library>::default();
         let mut design = None;
         for e in it {
             let list = self.expect_list(e)?;
@@ -64,12 +93,12 @@ impl EdifParser {
 
             match sym {
                 atom!("comment") => continue,
-                atom!("Library") => {
-                    let lib = self.parse_library(&list)?;
+                atom!("Library") | atom!("external") => {
+                    let lib = self.parse_library(e, list)?;
                     libs.insert(lib.name.clone(), lib);
                 }
                 atom!("design") => {
-                    design = Some(self.parse_design(&list)?);
+                    design = Some(self.parse_design(e, list)?);
                 }
                 _ => bail!("unknown element `{}`", sym),
             }
@@ -81,7 +110,7 @@ impl EdifParser {
         })
     }
 
-    fn parse_design(&self, list: &[Expr]) -> Result<Design> {
+    fn parse_design(&self, e: &Expr, list: &[Expr]) -> Result<Design> {
         let mut it = list.iter();
 
         self.sym_match(next_elem!(it), atom!("design"))?;
@@ -99,13 +128,14 @@ impl EdifParser {
             inst_name,
             cellref,
             libraryref,
+            span: e.span,
         })
     }
 
-    fn parse_library(&self, list: &[Expr]) -> Result<Library> {
+    fn parse_library(&self, e: &Expr, list: &[Expr]) -> Result<Library> {
         let mut it = list.iter();
 
-        self.sym_match(next_elem!(it), atom!("Library"))?;
+        let external = self.expect_sym(next_elem!(it))? == atom!("external");
 
         let name = self.expect_sym(next_elem!(it))?;
 
@@ -117,9 +147,14 @@ impl EdifParser {
                 let c = self.parse_cell(e)?;
                 Ok((c.name.clone(), c))
             })
-            .collect::<Result<FxHashMap<Atom, Cell>>>()?;
+            .collect::<Result<FxIndexMap<Atom, Cell>>>()?;
 
-        Ok(Library { name, cells })
+        Ok(Library {
+            name,
+            external,
+            cells,
+            span: e.span,
+        })
     }
 
     fn parse_cell(&self, e: &Expr) -> Result<Cell> {
@@ -133,7 +168,11 @@ impl EdifParser {
 
         ensure_exhausted!(it);
 
-        Ok(Cell { name, view })
+        Ok(Cell {
+            name,
+            view,
+            span: e.span,
+        })
     }
 
     fn parse_view(&self, e: &Expr) -> Result<View> {
@@ -167,6 +206,7 @@ impl EdifParser {
             name,
             interface,
             contents,
+            span: e.span,
         })
     }
 
@@ -176,7 +216,10 @@ impl EdifParser {
         let ports = it
             .map(|p| self.parse_port(p))
             .collect::<Result<Vec<Port>>>()?;
-        Ok(Interface { ports })
+        Ok(Interface {
+            ports,
+            span: e.span,
+        })
     }
 
     fn parse_port(&self, e: &Expr) -> Result<Port> {
@@ -190,6 +233,7 @@ impl EdifParser {
                 kind: PortKind::Single,
                 dir,
                 name,
+                span: e.span,
             });
         }
 
@@ -197,13 +241,13 @@ impl EdifParser {
 
         ensure!(
             list.len() == 3,
-            "expected a list with 3-elements at {:?}",
-            name.pos,
+            "expected a list with 3-elements at {}",
+            name.span,
         );
 
         self.sym_match(&list[0], atom!("array"))?;
 
-        let size = list[2].num().ok_or_else(|| anyhow!("expected a number"))?;
+        let size = self.expect_num(&list[2])?;
 
         let name = self.parse_name(&list[1]).context("parsing name")?;
 
@@ -211,6 +255,7 @@ impl EdifParser {
             kind: PortKind::Array(size),
             dir,
             name,
+            span: e.span,
         })
     }
 
@@ -235,7 +280,7 @@ impl EdifParser {
                 rename_from: Some(from),
             })
         } else {
-            bail!("expected a symbol or '(rename ..)' at {:?}", e.pos);
+            bail!("expected a symbol or '(rename ..)' at {}", e.span);
         }
     }
 
@@ -276,12 +321,15 @@ impl EdifParser {
             let viewref = self.expect_sym(&viewref[1])?;
             let cellref = self.expect_sym(&cellref[1])?;
 
+            let (properties, property_spans) = self.parse_properties(&list[3..])?;
             Ok(Content::Instance(Instance {
                 name,
                 viewref,
                 cellref,
                 libraryref,
-                properties: Arc::new(self.parse_properties(&list[3..])?),
+                properties: Arc::new(properties),
+                property_spans,
+                span: e.span,
             }))
         } else if sym == atom!("net") {
             let joined = self.expect_list(&list[2])?;
@@ -290,43 +338,99 @@ impl EdifParser {
                 .iter()
                 .map(|e| self.parse_portref(e))
                 .collect::<Result<Vec<_>>>()?;
-            Ok(Content::Net(Net { name, portrefs }))
+            Ok(Content::Net(Net {
+                name,
+                portrefs,
+                span: e.span,
+            }))
         } else {
-            bail!("expected instance or net at {:?}", e.pos);
+            bail!("expected instance or net at {}", e.span);
         }
     }
 
-    fn parse_properties(&self, list: &[Expr]) -> Result<FxHashMap<Name, Property>> {
-        let mut props = FxHashMap::with_capacity_and_hasher(list.len(), Default::default());
+    /// Parses the properties and their spans.
+    fn parse_properties(
+        &self,
+        list: &[Expr],
+    ) -> Result<(FxIndexMap<Name, Property>, FxIndexMap<Atom, Span>)> {
+        let mut props = FxIndexMap::with_capacity_and_hasher(list.len(), Default::default());
+        let mut spans = FxIndexMap::with_capacity_and_hasher(list.len(), Default::default());
 
         for e in list {
             let p = self.expect_list(e)?;
             ensure!(p.len() == 3, "expected (property NAME value)");
             self.sym_match(&p[0], atom!("property"))?;
             let name = self.parse_name(&p[1])?;
-            let val = self.expect_list(&p[2])?;
-            let val = match self.expect_sym(&val[0])? {
-                atom!("string") => Property::String(self.expect_str(&val[1])?),
-                atom!("integer") => Property::Integer(self.expect_num(&val[1])?),
-                atom!("boolean") => {
-                    let val = self.expect_list(&val[1])?;
-                    ensure!(val.len() == 1, "expected (true) or (false)");
-                    let val = match self.expect_sym(&val[0])? {
-                        atom!("true") => true,
-                        atom!("false") => false,
-                        other => bail!("unknown boolean value: '{}'", other),
-                    };
-                    Property::Boolean(val)
-                }
-                kind => bail!("unknown property kind: '{}'", kind),
-            };
+            let val = self.parse_value(&p[2])?;
+            spans.insert(name.name.clone(), e.span);
             ensure!(
                 props.insert(name, val).is_none(),
                 "duplicated property name",
             );
         }
 
-        Ok(props)
+        Ok((props, spans))
+    }
+
+    /// Parses a typed value, which is a list if it has several elements, or `(array ...)` of
+    /// typed values.
+    fn parse_value(&self, e: &Expr) -> Result<Property> {
+        let val = self.expect_list(e)?;
+        ensure!(!val.is_empty(), "expected a property value at {}", e.span);
+        let kind = self.expect_sym(&val[0])?;
+        if kind == atom!("array") {
+            let elems = val[1..].iter().map(|e| self.parse_value(e));
+            return Ok(Property::List(elems.collect::<Result<_>>()?));
+        }
+        let mut elems = val[1..]
+            .iter()
+            .map(|e| self.parse_element(kind.clone(), e))
+            .collect::<Result<Vec<_>>>()?;
+        match elems.len() {
+            0 => bail!("expected a value at {}", e.span),
+            1 => Ok(elems.pop().unwrap()),
+            _ => Ok(Property::List(elems)),
+        }
+    }
+
+    fn parse_element(&self, kind: Atom, e: &Expr) -> Result<Property> {
+        Ok(match kind {
+            atom!("string") => Property::String(self.expect_str(e)?),
+            atom!("integer") => Property::Integer(self.expect_i64(e)?),
+            atom!("number") => Property::Number(self.parse_number(e)?),
+            atom!("boolean") => {
+                let val = self.expect_list(e)?;
+                ensure!(val.len() == 1, "expected (true) or (false)");
+                let val = match self.expect_sym(&val[0])? {
+                    atom!("true") => true,
+                    atom!("false") => false,
+                    other => bail!("unknown boolean value: '{}'", other),
+                };
+                Property::Boolean(val)
+            }
+            kind => bail!("unknown property kind: '{}'", kind),
+        })
+    }
+
+    /// Parses an integer or a scaled integer `(e mantissa exponent)`.
+    fn parse_number(&self, e: &Expr) -> Result<Number> {
+        if let Some(mantissa) = e.num() {
+            return Ok(Number {
+                mantissa,
+                exponent: 0,
+            });
+        }
+        let list = self.expect_list(e)?;
+        ensure!(
+            list.len() == 3,
+            "expected (e mantissa exponent) at {}",
+            e.span
+        );
+        self.sym_match(&list[0], atom!("e"))?;
+        Ok(Number {
+            mantissa: self.expect_i64(&list[1])?,
+            exponent: self.expect_num(&list[2])?,
+        })
     }
 
     pub fn parse_portref(&self, e: &Expr) -> Result<PortRef> {
@@ -342,7 +446,7 @@ impl EdifParser {
                 (self.expect_sym(&l[1])?, Some(self.expect_num(&l[2])?))
             }
             ExprKind::Symbol(s) => (s.clone(), None),
-            _ => bail!("expected a symbol or '(member ...)' at {:?}", e.pos),
+            _ => bail!("expected a symbol or '(member ...)' at {}", e.span),
         };
 
         let instance_ref = if let Some(iref) = it.next() {
@@ -358,12 +462,13 @@ impl EdifParser {
             port,
             member,
             instance_ref,
+            span: e.span,
         })
     }
 
     fn expect_list<'e>(&self, e: &'e Expr) -> Result<&'e [Expr]> {
         e.list()
-            .ok_or_else(|| anyhow!("expected list at {:?}", e.pos))
+            .ok_or_else(|| anyhow!("expected list at {}", e.span))
     }
 
     fn sym_match(&self, e: &Expr, s: Atom) -> Result<()> {
@@ -376,17 +481,165 @@ impl EdifParser {
 
     fn expect_sym(&self, e: &Expr) -> Result<Atom> {
         e.symbol()
-            .ok_or_else(|| anyhow!("expected a symbol at {:?}", e.pos))
+            .ok_or_else(|| anyhow!("expected a symbol at {}", e.span))
     }
 
     fn expect_str(&self, e: &Expr) -> Result<String> {
         e.str()
             .map(|s| s.to_string())
-            .ok_or_else(|| anyhow!("expected a string at {:?}", e.pos))
+            .ok_or_else(|| anyhow!("expected a string at {}", e.span))
     }
 
     fn expect_num(&self, e: &Expr) -> Result<i32> {
+        let n = self.expect_i64(e)?;
+        i32::try_from(n).map_err(|_| anyhow!("{} is out of range at {}", n, e.span))
+    }
+
+    fn expect_i64(&self, e: &Expr) -> Result<i64> {
         e.num()
-            .ok_or_else(|| anyhow!("expected a number at {:?}", e.pos))
+            .ok_or_else(|| anyhow!("expected a number at {}", e.span))
+    }
+}
+
+fn parse_sexpr(s: &str, loc: Location) -> Result<Expr> {
+    use combine::Parser;
+
+    crate::sexpr::sexpr_parser()
+        .easy_parse(combine::stream::state::State::with_positioner(s, loc))
+        .map(|(e, _)| e)
+        .map_err(|e| anyhow!("{}", e))
+}
+
+/// A `cell` form in the source.
+#[cfg(feature = "rayon")]
+struct CellSpan {
+    lib: Atom,
+    range: std::ops::Range<usize>,
+    loc: Location,
+}
+
+/// The source without the `cell` forms, which keeps its line numbers.
+#[cfg(feature = "rayon")]
+struct Skeleton {
+    text: String,
+    /// The locations right after each removed cell, in the skeleton and in the source.
+    gaps: Vec<(Location, Location)>,
+}
+
+#[cfg(feature = "rayon")]
+impl Skeleton {
+    /// Maps the span of a node outside the cells from the skeleton to the source.
+    fn to_source(&self, span: Span) -> Span {
+        let gap = |offset| {
+            let i = self.gaps.partition_point(|(skel, _)| skel.offset <= offset);
+            i.checked_sub(1).map(|i| self.gaps[i])
+        };
+        let offset = |offset| match gap(offset) {
+            Some((skel, src)) => src.offset + offset - skel.offset,
+            None => offset,
+        };
+        let column = match gap(span.start) {
+            Some((skel, src)) if span.line == skel.line => src.column + span.column - skel.column,
+            _ => span.column,
+        };
+        Span {
+            start: offset(span.start),
+            end: offset(span.end),
+            line: span.line,
+            column,
+        }
+    }
+}
+
+/// The symbol starting at `s[i..]`, after any whitespace.
+#[cfg(feature = "rayon")]
+fn symbol_at(s: &str, i: usize) -> (&str, usize) {
+    let rest = &s[i..];
+    let start = i + rest.len() - rest.trim_start().len();
+    let len = s[start..]
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '&' || c == '|'))
+        .unwrap_or(s.len() - start);
+    (&s[start..start + len], start + len)
+}
+
+/// Finds the `cell` forms of the libraries in `s`, and removes them from it.
+#[cfg(feature = "rayon")]
+fn split_cells(s: &str) -> Result<(Skeleton, Vec<CellSpan>)> {
+    let bytes = s.as_bytes();
+    let mut spans = vec![];
+    let mut skeleton = Skeleton {
+        text: String::new(),
+        gaps: vec![],
+    };
+    let mut copied = 0;
+    let (mut depth, mut in_string) = (0, false);
+    let mut lib = None;
+    let mut cell = None;
+    let mut loc = Location::default();
+
+    for (i, &b) in bytes.iter().enumerate() {
+        loc.offset = i;
+        if in_string {
+            in_string = b != b'"';
+        } else if b == b'"' {
+            in_string = true;
+        } else if b == b'(' {
+            depth += 1;
+            let (keyword, end) = symbol_at(s, i + 1);
+            if depth == 2 {
+                lib = match keyword {
+                    "Library" | "external" => Some(Atom::from(symbol_at(s, end).0)),
+                    _ => None,
+                };
+            } else if depth == 3 && lib.is_some() && keyword == "cell" {
+                cell = Some(loc);
+            }
+        } else if b == b')' {
+            ensure!(depth > 0, "unbalanced `)` at {}", loc);
+            if depth == 3 {
+                if let Some(start) = cell.take() {
+                    let text = &mut skeleton.text;
+                    text.push_str(&s[copied..start.offset]);
+                    let lines = s[start.offset..=i].matches('\n').count();
+                    text.extend(std::iter::repeat_n('\n', lines));
+                    copied = i + 1;
+                    // The columns after an earlier gap on the line are shifted in the skeleton.
+                    let column = match skeleton.gaps.last() {
+                        _ if lines > 0 => 1,
+                        Some((skel, src)) if src.line == start.line => {
+                            start.column - (src.column - skel.column)
+                        }
+                        _ => start.column,
+                    };
+                    let skel = Location {
+                        offset: text.len(),
+                        line: loc.line,
+                        column,
+                    };
+                    let src = Location {
+                        offset: i + 1,
+                        line: loc.line,
+                        column: loc.column + 1,
+                    };
+                    skeleton.gaps.push((skel, src));
+                    spans.push(CellSpan {
+                        lib: lib.clone().unwrap(),
+                        range: start.offset..i + 1,
+                        loc: start,
+                    });
+                }
+            }
+            depth -= 1;
+        }
+
+        if b == b'\n' {
+            loc.line += 1;
+            loc.column = 1;
+        } else if b & 0xc0 != 0x80 {
+            loc.column += 1;
+        }
     }
+    ensure!(depth == 0 && !in_string, "unexpected end of input");
+    skeleton.text.push_str(&s[copied..]);
+    Ok((skeleton, spans))
 }
@@ -0,0 +1,727 @@
+//! Catalogue of the Xilinx 7-series and UltraScale UNISIM primitives.
+//!
+//! The `hdi_primitives` library written by Vivado only declares the names and directions of
+//! the ports of primitive cells. This module describes what they mean: the role of each pin,
+//! and the properties each primitive recognizes along with their types and defaults.
+
+use crate::ast::Direction;
+use crate::netlist::Instance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimitiveKind {
+    /// `LUT1` to `LUT6`, with the number of inputs.
+    Lut(u8),
+    Lut6_2,
+    Fdre,
+    Fdse,
+    Fdce,
+    Fdpe,
+    Ldce,
+    Ldpe,
+    Carry4,
+    Carry8,
+    Muxf7,
+    Muxf8,
+    Muxf9,
+    Srl16e,
+    Srlc32e,
+    Ramb18e1,
+    Ramb36e1,
+    Ramb18e2,
+    Ramb36e2,
+    Dsp48e1,
+    Dsp48e2,
+    Ibuf,
+    Ibufg,
+    Ibufds,
+    Obuf,
+    Obuft,
+    Obufds,
+    Iobuf,
+    Bufg,
+    Bufgce,
+    Buf,
+    Inv,
+    Gnd,
+    Vcc,
+}
+
+impl PrimitiveKind {
+    /// Whether the primitive holds a state.
+    pub fn is_sequential(self) -> bool {
+        use PrimitiveKind::*;
+        matches!(
+            self,
+            Fdre | Fdse
+                | Fdce
+                | Fdpe
+                | Ldce
+                | Ldpe
+                | Srl16e
+                | Srlc32e
+                | Ramb18e1
+                | Ramb36e1
+                | Ramb18e2
+                | Ramb36e2
+                | Dsp48e1
+                | Dsp48e2
+        )
+    }
+
+    /// Whether the primitive is a flip-flop (`FDRE`, `FDSE`, `FDCE` or `FDPE`).
+    pub fn is_flip_flop(self) -> bool {
+        use PrimitiveKind::*;
+        matches!(self, Fdre | Fdse | Fdce | Fdpe)
+    }
+
+    /// Whether the set or reset of the primitive is asynchronous.
+    pub fn has_async_control(self) -> bool {
+        use PrimitiveKind::*;
+        matches!(self, Fdce | Fdpe | Ldce | Ldpe)
+    }
+
+    /// Whether the output of the primitive always equals its single input.
+    pub fn is_buffer(self) -> bool {
+        use PrimitiveKind::*;
+        matches!(self, Ibuf | Ibufg | Obuf | Bufg | Buf)
+    }
+
+    /// Whether the primitive drives a constant (`GND` or `VCC`).
+    pub fn is_constant(self) -> bool {
+        matches!(self, PrimitiveKind::Gnd | PrimitiveKind::Vcc)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PinRole {
+    Clock,
+    ClockEnable,
+    Set,
+    Reset,
+    Data,
+    Select,
+    Address,
+    WriteEnable,
+    CarryIn,
+    /// Dedicated cascade connections between adjacent primitives.
+    Cascade,
+    /// Other control inputs, such as the operation modes of a DSP.
+    Control,
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinInfo {
+    pub name: &'static str,
+    pub dir: Direction,
+    pub role: PinRole,
+    /// Number of bits. Pins wider than a bit are declared as arrays.
+    pub width: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+    /// A bit vector of the width, written as a Verilog literal such as `64'h0`.
+    Bits(u32),
+    Integer,
+    String,
+    Boolean,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertyInfo {
+    pub name: &'static str,
+    pub ty: PropertyType,
+    /// The default value as written in Verilog.
+    pub default: &'static str,
+    /// If non-zero, `name` is the prefix of a family of properties suffixed with two hex
+    /// digits from `00` up to this count, as `INIT_00` to `INIT_3F`.
+    pub indexed: u32,
+}
+
+impl PropertyInfo {
+    /// Whether this describes the property `name`.
+    pub fn matches(&self, name: &str) -> bool {
+        if self.indexed == 0 {
+            return self.name == name;
+        }
+
+        match name.strip_prefix(self.name) {
+            Some(suffix) if suffix.len() == 2 => {
+                u32::from_str_radix(suffix, 16).is_ok_and(|i| i < self.indexed)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Primitive {
+    pub name: &'static str,
+    pub kind: PrimitiveKind,
+    pub pins: &'static [PinInfo],
+    pub properties: &'static [PropertyInfo],
+}
+
+impl Primitive {
+    pub fn pin(&self, name: &str) -> Option<&'static PinInfo> {
+        self.pins.iter().find(|p| p.name == name)
+    }
+
+    /// Pins that have `role`.
+    pub fn pins_with_role(&self, role: PinRole) -> impl Iterator<Item = &'static PinInfo> {
+        self.pins.iter().filter(move |p| p.role == role)
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'static PropertyInfo> {
+        self.properties.iter().find(|p| p.matches(name))
+    }
+}
+
+/// Looks up the primitive named `cell`.
+pub fn lookup(cell: &str) -> Option<&'static Primitive> {
+    PRIMITIVES.iter().find(|p| p.name == cell)
+}
+
+/// All the primitives in the catalogue.
+pub fn all() -> &'static [Primitive] {
+    PRIMITIVES
+}
+
+impl Instance {
+    /// The primitive this instance is of, if it is a leaf instance of a known primitive cell.
+    pub fn primitive(&self) -> Option<&'static Primitive> {
+        if self.is_leaf() {
+            lookup(&self.cell)
+        } else {
+            None
+        }
+    }
+
+    pub fn primitive_kind(&self) -> Option<PrimitiveKind> {
+        self.primitive().map(|p| p.kind)
+    }
+}
+
+macro_rules! pins {
+    (@width) => { 1 };
+    (@width $w:literal) => { $w };
+    ($($name:literal: $dir:ident $role:ident $([$w:literal])?),* $(,)?) => {
+        &[$(PinInfo {
+            name: $name,
+            dir: Direction::$dir,
+            role: PinRole::$role,
+            width: pins!(@width $($w)?),
+        }),*]
+    };
+}
+
+macro_rules! props {
+    (@ty Bits $w:literal) => { PropertyType::Bits($w) };
+    (@ty $ty:ident) => { PropertyType::$ty };
+    (@indexed) => { 0 };
+    (@indexed $n:literal) => { $n };
+    ($($name:literal $([$n:literal])?: $ty:ident $(($w:literal))? = $default:literal),* $(,)?) => {
+        &[$(PropertyInfo {
+            name: $name,
+            ty: props!(@ty $ty $($w)?),
+            default: $default,
+            indexed: props!(@indexed $($n)?),
+        }),*]
+    };
+}
+
+macro_rules! primitive {
+    ($name:literal, $kind:expr, $pins:expr, $props:expr) => {
+        Primitive {
+            name: $name,
+            kind: $kind,
+            pins: $pins,
+            properties: $props,
+        }
+    };
+}
+
+const LUT_PINS: &[PinInfo] = pins![
+    "I0": Input Data, "I1": Input Data, "I2": Input Data,
+    "I3": Input Data, "I4": Input Data, "I5": Input Data,
+    "O": Output Output,
+];
+
+const BUF_PINS: &[PinInfo] = pins!["I": Input Data, "O": Output Output];
+const MUXF_PINS: &[PinInfo] = pins![
+    "I0": Input Data, "I1": Input Data, "S": Input Select, "O": Output Output,
+];
+
+const IBUF_PROPS: &[PropertyInfo] = props![
+    "IOSTANDARD": String = "DEFAULT",
+    "IBUF_LOW_PWR": String = "TRUE",
+];
+const OBUF_PROPS: &[PropertyInfo] = props![
+    "IOSTANDARD": String = "DEFAULT",
+    "DRIVE": Integer = "12",
+    "SLEW": String = "SLOW",
+];
+
+macro_rules! ramb_props {
+    ($($extra:tt)*) => {
+        props![
+            "RAM_MODE": String = "TDP",
+            "READ_WIDTH_A": Integer = "0",
+            "READ_WIDTH_B": Integer = "0",
+            "WRITE_WIDTH_A": Integer = "0",
+            "WRITE_WIDTH_B": Integer = "0",
+            "WRITE_MODE_A": String = "WRITE_FIRST",
+            "WRITE_MODE_B": String = "WRITE_FIRST",
+            "DOA_REG": Integer = "0",
+            "DOB_REG": Integer = "0",
+            "RSTREG_PRIORITY_A": String = "RSTREG",
+            "RSTREG_PRIORITY_B": String = "RSTREG",
+            "SIM_COLLISION_CHECK": String = "ALL",
+            "INIT_FILE": String = "NONE",
+            $($extra)*
+        ]
+    };
+}
+
+const RAMB18_PROPS: &[PropertyInfo] = ramb_props![
+    "INIT_A": Bits(18) = "18'h00000",
+    "INIT_B": Bits(18) = "18'h00000",
+    "SRVAL_A": Bits(18) = "18'h00000",
+    "SRVAL_B": Bits(18) = "18'h00000",
+    "INIT_" [0x40]: Bits(256) = "256'h0",
+    "INITP_" [0x08]: Bits(256) = "256'h0",
+];
+
+const RAMB36_PROPS: &[PropertyInfo] = ramb_props![
+    "INIT_A": Bits(36) = "36'h000000000",
+    "INIT_B": Bits(36) = "36'h000000000",
+    "SRVAL_A": Bits(36) = "36'h000000000",
+    "SRVAL_B": Bits(36) = "36'h000000000",
+    "RAM_EXTENSION_A": String = "NONE",
+    "RAM_EXTENSION_B": String = "NONE",
+    "EN_ECC_READ": String = "FALSE",
+    "EN_ECC_WRITE": String = "FALSE",
+    "INIT_" [0x80]: Bits(256) = "256'h0",
+    "INITP_" [0x10]: Bits(256) = "256'h0",
+];
+
+const DSP_REG_PROPS: &[PropertyInfo] = props![
+    "AREG": Integer = "1",
+    "BREG": Integer = "1",
+    "CREG": Integer = "1",
+    "DREG": Integer = "1",
+    "ADREG": Integer = "1",
+    "MREG": Integer = "1",
+    "PREG": Integer = "1",
+    "ACASCREG": Integer = "1",
+    "BCASCREG": Integer = "1",
+    "ALUMODEREG": Integer = "1",
+    "CARRYINREG": Integer = "1",
+    "CARRYINSELREG": Integer = "1",
+    "INMODEREG": Integer = "1",
+    "OPMODEREG": Integer = "1",
+    "A_INPUT": String = "DIRECT",
+    "B_INPUT": String = "DIRECT",
+    "USE_MULT": String = "MULTIPLY",
+    "USE_SIMD": String = "ONE48",
+    "USE_PATTERN_DETECT": String = "NO_PATDET",
+    "AUTORESET_PATDET": String = "NO_RESET",
+    "MASK": Bits(48) = "48'h3fffffffffff",
+    "PATTERN": Bits(48) = "48'h000000000000",
+    "SEL_MASK": String = "MASK",
+    "SEL_PATTERN": String = "PATTERN",
+];
+
+static PRIMITIVES: &[Primitive] = &[
+    primitive!(
+        "LUT1",
+        PrimitiveKind::Lut(1),
+        pins!["I0": Input Data, "O": Output Output],
+        props!["INIT": Bits(2) = "2'h0"]
+    ),
+    primitive!(
+        "LUT2",
+        PrimitiveKind::Lut(2),
+        pins![
+            "I0": Input Data, "I1": Input Data, "O": Output Output,
+        ],
+        props!["INIT": Bits(4) = "4'h0"]
+    ),
+    primitive!(
+        "LUT3",
+        PrimitiveKind::Lut(3),
+        pins![
+            "I0": Input Data, "I1": Input Data, "I2": Input Data, "O": Output Output,
+        ],
+        props!["INIT": Bits(8) = "8'h00"]
+    ),
+    primitive!(
+        "LUT4",
+        PrimitiveKind::Lut(4),
+        pins![
+            "I0": Input Data, "I1": Input Data, "I2": Input Data, "I3": Input Data,
+            "O": Output Output,
+        ],
+        props!["INIT": Bits(16) = "16'h0000"]
+    ),
+    primitive!(
+        "LUT5",
+        PrimitiveKind::Lut(5),
+        pins![
+            "I0": Input Data, "I1": Input Data, "I2": Input Data, "I3": Input Data,
+            "I4": Input Data, "O": Output Output,
+        ],
+        props!["INIT": Bits(32) = "32'h00000000"]
+    ),
+    primitive!(
+        "LUT6",
+        PrimitiveKind::Lut(6),
+        LUT_PINS,
+        props!["INIT": Bits(64) = "64'h0000000000000000"]
+    ),
+    primitive!(
+        "LUT6_2",
+        PrimitiveKind::Lut6_2,
+        pins![
+            "I0": Input Data, "I1": Input Data, "I2": Input Data,
+            "I3": Input Data, "I4": Input Data, "I5": Input Data,
+            "O5": Output Output, "O6": Output Output,
+        ],
+        props!["INIT": Bits(64) = "64'h0000000000000000"]
+    ),
+    primitive!(
+        "FDRE",
+        PrimitiveKind::Fdre,
+        pins![
+            "C": Input Clock, "CE": Input ClockEnable, "D": Input Data, "R": Input Reset,
+            "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(1) = "1'b0",
+            "IS_C_INVERTED": Bits(1) = "1'b0",
+            "IS_D_INVERTED": Bits(1) = "1'b0",
+            "IS_R_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "FDSE",
+        PrimitiveKind::Fdse,
+        pins![
+            "C": Input Clock, "CE": Input ClockEnable, "D": Input Data, "S": Input Set,
+            "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(1) = "1'b1",
+            "IS_C_INVERTED": Bits(1) = "1'b0",
+            "IS_D_INVERTED": Bits(1) = "1'b0",
+            "IS_S_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "FDCE",
+        PrimitiveKind::Fdce,
+        pins![
+            "C": Input Clock, "CE": Input ClockEnable, "CLR": Input Reset, "D": Input Data,
+            "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(1) = "1'b0",
+            "IS_C_INVERTED": Bits(1) = "1'b0",
+            "IS_CLR_INVERTED": Bits(1) = "1'b0",
+            "IS_D_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "FDPE",
+        PrimitiveKind::Fdpe,
+        pins![
+            "C": Input Clock, "CE": Input ClockEnable, "D": Input Data, "PRE": Input Set,
+            "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(1) = "1'b1",
+            "IS_C_INVERTED": Bits(1) = "1'b0",
+            "IS_D_INVERTED": Bits(1) = "1'b0",
+            "IS_PRE_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "LDCE",
+        PrimitiveKind::Ldce,
+        pins![
+            "CLR": Input Reset, "D": Input Data, "G": Input Clock, "GE": Input ClockEnable,
+            "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(1) = "1'b0",
+            "IS_CLR_INVERTED": Bits(1) = "1'b0",
+            "IS_G_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "LDPE",
+        PrimitiveKind::Ldpe,
+        pins![
+            "D": Input Data, "G": Input Clock, "GE": Input ClockEnable, "PRE": Input Set,
+            "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(1) = "1'b1",
+            "IS_G_INVERTED": Bits(1) = "1'b0",
+            "IS_PRE_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "CARRY4",
+        PrimitiveKind::Carry4,
+        pins![
+            "CI": Input CarryIn, "CYINIT": Input CarryIn, "DI": Input Data[4],
+            "S": Input Select[4], "CO": Output Output[4], "O": Output Output[4],
+        ],
+        &[]
+    ),
+    primitive!(
+        "CARRY8",
+        PrimitiveKind::Carry8,
+        pins![
+            "CI": Input CarryIn, "CI_TOP": Input CarryIn, "DI": Input Data[8],
+            "S": Input Select[8], "CO": Output Output[8], "O": Output Output[8],
+        ],
+        props!["CARRY_TYPE": String = "SINGLE_CY8"]
+    ),
+    primitive!("MUXF7", PrimitiveKind::Muxf7, MUXF_PINS, &[]),
+    primitive!("MUXF8", PrimitiveKind::Muxf8, MUXF_PINS, &[]),
+    primitive!("MUXF9", PrimitiveKind::Muxf9, MUXF_PINS, &[]),
+    primitive!(
+        "SRL16E",
+        PrimitiveKind::Srl16e,
+        pins![
+            "A0": Input Address, "A1": Input Address, "A2": Input Address,
+            "A3": Input Address, "CE": Input ClockEnable, "CLK": Input Clock,
+            "D": Input Data, "Q": Output Output,
+        ],
+        props![
+            "INIT": Bits(16) = "16'h0000",
+            "IS_CLK_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "SRLC32E",
+        PrimitiveKind::Srlc32e,
+        pins![
+            "A": Input Address[5], "CE": Input ClockEnable, "CLK": Input Clock,
+            "D": Input Data, "Q": Output Output, "Q31": Output Output,
+        ],
+        props![
+            "INIT": Bits(32) = "32'h00000000",
+            "IS_CLK_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!(
+        "RAMB18E1",
+        PrimitiveKind::Ramb18e1,
+        pins![
+            "CLKARDCLK": Input Clock, "CLKBWRCLK": Input Clock,
+            "ENARDEN": Input ClockEnable, "ENBWREN": Input ClockEnable,
+            "REGCEAREGCE": Input ClockEnable, "REGCEB": Input ClockEnable,
+            "RSTRAMARSTRAM": Input Reset, "RSTRAMB": Input Reset,
+            "RSTREGARSTREG": Input Reset, "RSTREGB": Input Reset,
+            "ADDRARDADDR": Input Address[14], "ADDRBWRADDR": Input Address[14],
+            "WEA": Input WriteEnable[2], "WEBWE": Input WriteEnable[4],
+            "DIADI": Input Data[16], "DIBDI": Input Data[16],
+            "DIPADIP": Input Data[2], "DIPBDIP": Input Data[2],
+            "DOADO": Output Output[16], "DOBDO": Output Output[16],
+            "DOPADOP": Output Output[2], "DOPBDOP": Output Output[2],
+        ],
+        RAMB18_PROPS
+    ),
+    primitive!(
+        "RAMB36E1",
+        PrimitiveKind::Ramb36e1,
+        pins![
+            "CLKARDCLK": Input Clock, "CLKBWRCLK": Input Clock,
+            "ENARDEN": Input ClockEnable, "ENBWREN": Input ClockEnable,
+            "REGCEAREGCE": Input ClockEnable, "REGCEB": Input ClockEnable,
+            "RSTRAMARSTRAM": Input Reset, "RSTRAMB": Input Reset,
+            "RSTREGARSTREG": Input Reset, "RSTREGB": Input Reset,
+            "ADDRARDADDR": Input Address[16], "ADDRBWRADDR": Input Address[16],
+            "WEA": Input WriteEnable[4], "WEBWE": Input WriteEnable[8],
+            "DIADI": Input Data[32], "DIBDI": Input Data[32],
+            "DIPADIP": Input Data[4], "DIPBDIP": Input Data[4],
+            "CASCADEINA": Input Cascade, "CASCADEINB": Input Cascade,
+            "INJECTDBITERR": Input Control, "INJECTSBITERR": Input Control,
+            "DOADO": Output Output[32], "DOBDO": Output Output[32],
+            "DOPADOP": Output Output[4], "DOPBDOP": Output Output[4],
+            "CASCADEOUTA": Output Cascade, "CASCADEOUTB": Output Cascade,
+            "DBITERR": Output Output, "SBITERR": Output Output,
+            "ECCPARITY": Output Output[8], "RDADDRECC": Output Output[9],
+        ],
+        RAMB36_PROPS
+    ),
+    primitive!(
+        "RAMB18E2",
+        PrimitiveKind::Ramb18e2,
+        pins![
+            "CLKARDCLK": Input Clock, "CLKBWRCLK": Input Clock,
+            "ENARDEN": Input ClockEnable, "ENBWREN": Input ClockEnable,
+            "REGCEAREGCE": Input ClockEnable, "REGCEB": Input ClockEnable,
+            "RSTRAMARSTRAM": Input Reset, "RSTRAMB": Input Reset,
+            "RSTREGARSTREG": Input Reset, "RSTREGB": Input Reset,
+            "ADDRARDADDR": Input Address[14], "ADDRBWRADDR": Input Address[14],
+            "ADDRENA": Input Control, "ADDRENB": Input Control,
+            "WEA": Input WriteEnable[2], "WEBWE": Input WriteEnable[4],
+            "DINADIN": Input Data[16], "DINBDIN": Input Data[16],
+            "DINPADINP": Input Data[2], "DINPBDINP": Input Data[2],
+            "DOUTADOUT": Output Output[16], "DOUTBDOUT": Output Output[16],
+            "DOUTPADOUTP": Output Output[2], "DOUTPBDOUTP": Output Output[2],
+        ],
+        RAMB18_PROPS
+    ),
+    primitive!(
+        "RAMB36E2",
+        PrimitiveKind::Ramb36e2,
+        pins![
+            "CLKARDCLK": Input Clock, "CLKBWRCLK": Input Clock,
+            "ENARDEN": Input ClockEnable, "ENBWREN": Input ClockEnable,
+            "REGCEAREGCE": Input ClockEnable, "REGCEB": Input ClockEnable,
+            "RSTRAMARSTRAM": Input Reset, "RSTRAMB": Input Reset,
+            "RSTREGARSTREG": Input Reset, "RSTREGB": Input Reset,
+            "ADDRARDADDR": Input Address[15], "ADDRBWRADDR": Input Address[15],
+            "ADDRENA": Input Control, "ADDRENB": Input Control,
+            "WEA": Input WriteEnable[4], "WEBWE": Input WriteEnable[8],
+            "DINADIN": Input Data[32], "DINBDIN": Input Data[32],
+            "DINPADINP": Input Data[4], "DINPBDINP": Input Data[4],
+            "INJECTDBITERR": Input Control, "INJECTSBITERR": Input Control,
+            "DOUTADOUT": Output Output[32], "DOUTBDOUT": Output Output[32],
+            "DOUTPADOUTP": Output Output[4], "DOUTPBDOUTP": Output Output[4],
+            "DBITERR": Output Output, "SBITERR": Output Output,
+            "ECCPARITY": Output Output[8], "RDADDRECC": Output Output[9],
+        ],
+        RAMB36_PROPS
+    ),
+    primitive!(
+        "DSP48E1",
+        PrimitiveKind::Dsp48e1,
+        pins![
+            "CLK": Input Clock,
+            "A": Input Data[30], "B": Input Data[18], "C": Input Data[48], "D": Input Data[25],
+            "CARRYIN": Input CarryIn,
+            "ACIN": Input Cascade[30], "BCIN": Input Cascade[18], "PCIN": Input Cascade[48],
+            "CARRYCASCIN": Input Cascade, "MULTSIGNIN": Input Cascade,
+            "ALUMODE": Input Control[4], "CARRYINSEL": Input Control[3],
+            "INMODE": Input Control[5], "OPMODE": Input Control[7],
+            "CEA1": Input ClockEnable, "CEA2": Input ClockEnable,
+            "CEB1": Input ClockEnable, "CEB2": Input ClockEnable,
+            "CEC": Input ClockEnable, "CED": Input ClockEnable, "CEAD": Input ClockEnable,
+            "CEM": Input ClockEnable, "CEP": Input ClockEnable,
+            "CEALUMODE": Input ClockEnable, "CECARRYIN": Input ClockEnable,
+            "CECTRL": Input ClockEnable, "CEINMODE": Input ClockEnable,
+            "RSTA": Input Reset, "RSTB": Input Reset, "RSTC": Input Reset,
+            "RSTD": Input Reset, "RSTM": Input Reset, "RSTP": Input Reset,
+            "RSTALLCARRYIN": Input Reset, "RSTALUMODE": Input Reset,
+            "RSTCTRL": Input Reset, "RSTINMODE": Input Reset,
+            "P": Output Output[48], "CARRYOUT": Output Output[4],
+            "ACOUT": Output Cascade[30], "BCOUT": Output Cascade[18],
+            "PCOUT": Output Cascade[48], "CARRYCASCOUT": Output Cascade,
+            "MULTSIGNOUT": Output Cascade,
+            "OVERFLOW": Output Output, "UNDERFLOW": Output Output,
+            "PATTERNDETECT": Output Output, "PATTERNBDETECT": Output Output,
+        ],
+        DSP_REG_PROPS
+    ),
+    primitive!(
+        "DSP48E2",
+        PrimitiveKind::Dsp48e2,
+        pins![
+            "CLK": Input Clock,
+            "A": Input Data[30], "B": Input Data[18], "C": Input Data[48], "D": Input Data[27],
+            "CARRYIN": Input CarryIn,
+            "ACIN": Input Cascade[30], "BCIN": Input Cascade[18], "PCIN": Input Cascade[48],
+            "CARRYCASCIN": Input Cascade, "MULTSIGNIN": Input Cascade,
+            "ALUMODE": Input Control[4], "CARRYINSEL": Input Control[3],
+            "INMODE": Input Control[5], "OPMODE": Input Control[9],
+            "CEA1": Input ClockEnable, "CEA2": Input ClockEnable,
+            "CEB1": Input ClockEnable, "CEB2": Input ClockEnable,
+            "CEC": Input ClockEnable, "CED": Input ClockEnable, "CEAD": Input ClockEnable,
+            "CEM": Input ClockEnable, "CEP": Input ClockEnable,
+            "CEALUMODE": Input ClockEnable, "CECARRYIN": Input ClockEnable,
+            "CECTRL": Input ClockEnable, "CEINMODE": Input ClockEnable,
+            "RSTA": Input Reset, "RSTB": Input Reset, "RSTC": Input Reset,
+            "RSTD": Input Reset, "RSTM": Input Reset, "RSTP": Input Reset,
+            "RSTALLCARRYIN": Input Reset, "RSTALUMODE": Input Reset,
+            "RSTCTRL": Input Reset, "RSTINMODE": Input Reset,
+            "P": Output Output[48], "CARRYOUT": Output Output[4], "XOROUT": Output Output[8],
+            "ACOUT": Output Cascade[30], "BCOUT": Output Cascade[18],
+            "PCOUT": Output Cascade[48], "CARRYCASCOUT": Output Cascade,
+            "MULTSIGNOUT": Output Cascade,
+            "OVERFLOW": Output Output, "UNDERFLOW": Output Output,
+            "PATTERNDETECT": Output Output, "PATTERNBDETECT": Output Output,
+        ],
+        DSP_REG_PROPS
+    ),
+    primitive!("IBUF", PrimitiveKind::Ibuf, BUF_PINS, IBUF_PROPS),
+    primitive!("IBUFG", PrimitiveKind::Ibufg, BUF_PINS, IBUF_PROPS),
+    primitive!(
+        "IBUFDS",
+        PrimitiveKind::Ibufds,
+        pins![
+            "I": Input Data, "IB": Input Data, "O": Output Output,
+        ],
+        props![
+            "IOSTANDARD": String = "DEFAULT",
+            "DIFF_TERM": String = "FALSE",
+            "IBUF_LOW_PWR": String = "TRUE",
+        ]
+    ),
+    primitive!("OBUF", PrimitiveKind::Obuf, BUF_PINS, OBUF_PROPS),
+    primitive!(
+        "OBUFT",
+        PrimitiveKind::Obuft,
+        pins![
+            "I": Input Data, "T": Input Control, "O": Output Output,
+        ],
+        OBUF_PROPS
+    ),
+    primitive!(
+        "OBUFDS",
+        PrimitiveKind::Obufds,
+        pins![
+            "I": Input Data, "O": Output Output, "OB": Output Output,
+        ],
+        props![
+            "IOSTANDARD": String = "DEFAULT",
+            "SLEW": String = "SLOW",
+        ]
+    ),
+    primitive!(
+        "IOBUF",
+        PrimitiveKind::Iobuf,
+        pins![
+            "I": Input Data, "T": Input Control, "O": Output Output, "IO": InOut Data,
+        ],
+        props![
+            "IOSTANDARD": String = "DEFAULT",
+            "DRIVE": Integer = "12",
+            "SLEW": String = "SLOW",
+            "IBUF_LOW_PWR": String = "TRUE",
+        ]
+    ),
+    primitive!("BUFG", PrimitiveKind::Bufg, BUF_PINS, &[]),
+    primitive!(
+        "BUFGCE",
+        PrimitiveKind::Bufgce,
+        pins![
+            "CE": Input ClockEnable, "I": Input Data, "O": Output Output,
+        ],
+        props![
+            "CE_TYPE": String = "SYNC",
+            "IS_CE_INVERTED": Bits(1) = "1'b0",
+            "IS_I_INVERTED": Bits(1) = "1'b0",
+        ]
+    ),
+    primitive!("BUF", PrimitiveKind::Buf, BUF_PINS, &[]),
+    primitive!("INV", PrimitiveKind::Inv, BUF_PINS, &[]),
+    primitive!("GND", PrimitiveKind::Gnd, pins!["G": Output Output], &[]),
+    primitive!("VCC", PrimitiveKind::Vcc, pins!["P": Output Output], &[]),
+];
@@ -0,0 +1,32 @@
+//! Serialization of the shared property maps.
+
+use indexmap::IndexMap;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::hash::{BuildHasher, Hash};
+use std::sync::Arc;
+
+/// A shared map with keys that are not strings, as a sequence of pairs in map order.
+pub mod shared_pairs {
+    use super::*;
+
+    pub fn serialize<K, V, H, S>(map: &Arc<IndexMap<K, V, H>>, s: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        s.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, H, D>(d: D) -> Result<Arc<IndexMap<K, V, H>>, D::Error>
+    where
+        K: Eq + Hash + Deserialize<'de>,
+        V: Deserialize<'de>,
+        H: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        let pairs = Vec::<(K, V)>::deserialize(d)?;
+        Ok(Arc::new(pairs.into_iter().collect()))
+    }
+}
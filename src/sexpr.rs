@@ -1,6 +1,21 @@
+//! S-expressions of EDIF text.
+//!
+//! [`Expr`] owns its strings and interns its symbols as [`Atom`]s, which live as long as the
+//! process. [`BorrowedExpr`] holds slices of the input instead, and an [`Interner`] gives its
+//! symbols IDs for the lifetime of one document.
+//!
+//! The EDIF parser reads only [`Expr`]s, so a borrowed tree must be converted with
+//! [`BorrowedExpr::to_owned_expr`] before it becomes an [`ast`](crate::ast), and interning its
+//! symbols as [`Atom`]s then cannot be avoided. The borrowed types are for tools that only
+//! scan the text.
+
+use crate::ast::Span;
 use crate::atom::Atom;
-use combine::stream::state::SourcePosition;
+use anyhow::{anyhow, Result};
+use combine::stream::state::{Positioner, RangePositioner};
+use combine::stream::Resetable;
 use combine::*;
+use fxhash::FxHashMap;
 use std::fmt;
 
 #[derive(Debug)]
@@ -8,28 +23,92 @@ pub enum ExprKind {
     List(Vec<Expr>),
     Symbol(Atom),
     Str(String),
-    Num(i32),
+    Num(i64),
 }
 
-#[derive(Clone, Copy)]
-pub struct Pos(pub i32, pub i32);
+/// A position in the input, as tracked while parsing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    /// The byte offset.
+    pub offset: usize,
+    pub line: i32,
+    pub column: i32,
+}
 
-impl fmt::Debug for Pos {
+impl Default for Location {
+    fn default() -> Self {
+        Location {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}:{}", self.0, self.1)
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Positioner<char> for Location {
+    type Position = Location;
+
+    fn position(&self) -> Location {
+        *self
+    }
+
+    fn update(&mut self, c: &char) {
+        self.offset += c.len_utf8();
+        if *c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl<'a> RangePositioner<char, &'a str> for Location {
+    fn update_range(&mut self, range: &&'a str) {
+        for c in range.chars() {
+            self.update(&c);
+        }
+    }
+}
+
+impl Resetable for Location {
+    type Checkpoint = Location;
+
+    fn checkpoint(&self) -> Location {
+        *self
+    }
+
+    fn reset(&mut self, checkpoint: Location) {
+        *self = checkpoint;
+    }
+}
+
+/// The span of an expression starting at `start` and ending before `end`.
+fn span(start: Location, end: Location) -> Span {
+    Span {
+        start: start.offset,
+        end: end.offset,
+        line: start.line,
+        column: start.column,
     }
 }
 
 #[derive(Debug)]
 pub struct Expr {
     pub kind: ExprKind,
-    pub pos: Pos,
+    pub span: Span,
 }
 
 impl Expr {
     pub fn list(&self) -> Option<&[Expr]> {
         match &self.kind {
-            ExprKind::List(es) => Some(&es),
+            ExprKind::List(es) => Some(es),
             _ => None,
         }
     }
@@ -41,7 +120,7 @@ impl Expr {
         }
     }
 
-    pub fn num(&self) -> Option<i32> {
+    pub fn num(&self) -> Option<i64> {
         match self.kind {
             ExprKind::Num(n) => Some(n),
             _ => None,
@@ -50,44 +129,215 @@ impl Expr {
 
     pub fn str(&self) -> Option<&str> {
         match &self.kind {
-            ExprKind::Str(n) => Some(&*n),
+            ExprKind::Str(n) => Some(n),
             _ => None,
         }
     }
 }
 
 parser! {
-    pub fn sexpr_parser['a, I]()(I) -> Expr
+    /// An integer with an optional sign, which must fit in 64 bits.
+    fn number['a, I]()(I) -> i64
+    where [I: combine::Stream<Item=char> +
+        combine::RangeStream +
+        combine::StreamOnce<Range = &'a str, Position = Location>]
+    {
+        use combine::error::StreamError;
+        use combine::parser::char::char as cmb_char;
+        use combine::parser::range;
+        use combine::stream::StreamErrorFor;
+
+        range::recognize(
+            optional(cmb_char('-').or(cmb_char('+')))
+                .with(range::take_while1(|c: char| c.is_ascii_digit())),
+        )
+        .and_then(|ds: &str| ds.parse::<i64>().map_err(StreamErrorFor::<I>::other))
+    }
+}
+
+/// An s-expression borrowing its symbols and strings from the input.
+#[derive(Debug)]
+pub struct BorrowedExpr<'a> {
+    pub kind: BorrowedKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum BorrowedKind<'a> {
+    List(Vec<BorrowedExpr<'a>>),
+    Symbol(&'a str),
+    Str(&'a str),
+    Num(i64),
+}
+
+impl<'a> BorrowedExpr<'a> {
+    pub fn list(&self) -> Option<&[BorrowedExpr<'a>]> {
+        match &self.kind {
+            BorrowedKind::List(es) => Some(es),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> Option<&'a str> {
+        match self.kind {
+            BorrowedKind::Symbol(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn num(&self) -> Option<i64> {
+        match self.kind {
+            BorrowedKind::Num(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn str(&self) -> Option<&'a str> {
+        match self.kind {
+            BorrowedKind::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Converts `self` to an [`Expr`], interning its symbols as [`Atom`]s.
+    pub fn to_owned_expr(&self) -> Expr {
+        let kind = match &self.kind {
+            BorrowedKind::List(es) => {
+                ExprKind::List(es.iter().map(|e| e.to_owned_expr()).collect())
+            }
+            BorrowedKind::Symbol(s) => ExprKind::Symbol(Atom::from(*s)),
+            BorrowedKind::Str(s) => ExprKind::Str(s.to_string()),
+            BorrowedKind::Num(n) => ExprKind::Num(*n),
+        };
+        Expr {
+            kind,
+            span: self.span,
+        }
+    }
+}
+
+parser! {
+    pub fn borrowed_sexpr_parser['a, I]()(I) -> BorrowedExpr<'a>
     where [I: combine::Stream<Item=char> +
         combine::RangeStream +
-        combine::StreamOnce<Range = &'a str, Position = SourcePosition>]
+        combine::StreamOnce<Range = &'a str, Position = Location>]
     {
         use combine::parser::char::{char as cmb_char, spaces};
         use combine::parser::range;
         use combine::{between, many, position};
 
-        let num = range::take_while1(|c: char| c.is_ascii_digit())
-            .map(|ds: &str| ExprKind::Num(ds.parse::<i32>().unwrap()));
+        let num = number().map(BorrowedKind::Num);
         let string = cmb_char('"')
             .with(range::take_while(|c: char| c != '"'))
             .skip(cmb_char('"'))
-            .map(|s: &str| ExprKind::Str(s.to_string()));
-        let list = between(cmb_char('('), cmb_char(')'), many(sexpr_parser()))
-            .map(|es| ExprKind::List(es));
+            .map(BorrowedKind::Str);
+        let list = between(cmb_char('('), cmb_char(')'), many(borrowed_sexpr_parser()))
+            .map(BorrowedKind::List);
         let symbol = range::recognize(
             combine::satisfy(|c: char| c.is_ascii_alphabetic() || c == '|' || c == '&').skip(
                 range::take_while(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '&'),
             ),
         )
-        .map(|s| ExprKind::Symbol(Atom::from(s)));
+        .map(BorrowedKind::Symbol);
 
         spaces()
             .with(position())
             .and(choice!(num, string, symbol, list))
-            .map(|(pos, kind): (SourcePosition, ExprKind)| Expr {
-                kind,
-                pos: Pos(pos.line, pos.column),
+            .and(position())
+            .map(|((start, kind), end): ((Location, BorrowedKind<'a>), Location)| {
+                BorrowedExpr {
+                    kind,
+                    span: span(start, end),
+                }
             })
             .skip(spaces())
     }
 }
+
+parser! {
+    /// The borrowed parser followed by [`BorrowedExpr::to_owned_expr`], so both share one
+    /// grammar.
+    pub fn sexpr_parser['a, I]()(I) -> Expr
+    where [I: combine::Stream<Item=char> +
+        combine::RangeStream +
+        combine::StreamOnce<Range = &'a str, Position = Location>]
+    {
+        borrowed_sexpr_parser().map(|e: BorrowedExpr<'a>| e.to_owned_expr())
+    }
+}
+
+/// Parses `s` into a tree of slices of it, without touching the global [`Atom`] table.
+pub fn parse_borrowed(s: &str) -> Result<BorrowedExpr<'_>> {
+    borrowed_sexpr_parser()
+        .easy_parse(stream::state::State::with_positioner(
+            s,
+            Location::default(),
+        ))
+        .map(|(e, _)| e)
+        .map_err(|e| anyhow!("{}", e))
+}
+
+/// The ID of a symbol in an [`Interner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// An interner of the symbols of one document, freed with it.
+#[derive(Debug, Default)]
+pub struct Interner<'a> {
+    ids: FxHashMap<&'a str, Symbol>,
+    names: Vec<&'a str>,
+}
+
+impl<'a> Interner<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interns the symbols of `e` and its descendants.
+    pub fn from_expr(e: &BorrowedExpr<'a>) -> Self {
+        let mut interner = Self::new();
+        let mut stack = vec![e];
+        while let Some(e) = stack.pop() {
+            match &e.kind {
+                BorrowedKind::List(es) => stack.extend(es.iter().rev()),
+                BorrowedKind::Symbol(s) => {
+                    interner.intern(s);
+                }
+                _ => {}
+            }
+        }
+        interner
+    }
+
+    pub fn intern(&mut self, name: &'a str) -> Symbol {
+        let next = Symbol(self.names.len() as u32);
+        let id = *self.ids.entry(name).or_insert(next);
+        if id == next {
+            self.names.push(name);
+        }
+        id
+    }
+
+    /// The ID of `name`, if it is interned.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn resolve(&self, sym: Symbol) -> &'a str {
+        self.names[sym.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
@@ -0,0 +1,382 @@
+//! Cycle-based simulation of netlists of Xilinx primitives.
+//!
+//! A [`Simulator`] compiles the leaf instances of a [`Netlist`](crate::netlist::Netlist) into a
+//! schedule in topological order of the combinational logic. Every call of
+//! [`Simulator::step`] is a rising edge of all the clocks at once; clock pins are not
+//! evaluated. Flip-flops with asynchronous controls are cleared or preset as soon as the
+//! control is asserted.
+
+use crate::ast::{Direction, FxIndexMap, Port};
+use crate::atom::Atom;
+use crate::lut::TruthTable;
+use crate::netlist::{GlobalNet, Instance, Netlist, Path, PortRef};
+use crate::primitives::PrimitiveKind;
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use fxhash::FxHashMap;
+use petgraph::Graph;
+
+pub struct Simulator {
+    nets: Vec<GlobalNet>,
+    values: Vec<bool>,
+    cells: Vec<Cell>,
+    /// Indices of `cells` in evaluation order.
+    order: Vec<usize>,
+    top: Path,
+    interface: FxIndexMap<Atom, Port>,
+    net_of: FxHashMap<PortRef, usize>,
+    cycle: u64,
+}
+
+struct Cell {
+    path: Path,
+    logic: Logic,
+    inputs: Vec<Option<usize>>,
+    outputs: Vec<Option<usize>>,
+}
+
+enum Logic {
+    /// `I0`.. to `O`.
+    Lut(TruthTable),
+    /// `I0`..`I5` to `O5` and `O6`.
+    Lut6_2(TruthTable, TruthTable),
+    Const(bool),
+    /// `I` to `O`.
+    Buf,
+    Inv,
+    /// `I0`, `I1` and `S` to `O`.
+    Mux,
+    /// `CI`, `CYINIT`, `DI[0..4]` and `S[0..4]` to `CO[0..4]` and `O[0..4]`.
+    Carry4,
+    /// `D`, `CE` and the set/reset to `Q`.
+    Ff(Ff),
+}
+
+struct Ff {
+    state: bool,
+    /// The value loaded by the set/reset.
+    set: bool,
+    sync: bool,
+    d_inverted: bool,
+    sr_inverted: bool,
+}
+
+impl Simulator {
+    pub fn new(netlist: &Netlist) -> Result<Self> {
+        let nets = netlist.global_nets();
+        let mut net_of = FxHashMap::default();
+        for (i, net) in nets.iter().enumerate() {
+            for p in &net.pins {
+                net_of.insert(p.clone(), i);
+            }
+        }
+
+        let mut sim = Simulator {
+            values: vec![false; nets.len()],
+            nets,
+            cells: vec![],
+            order: vec![],
+            top: netlist.top.path.clone(),
+            interface: netlist.top.interface.clone(),
+            net_of,
+            cycle: 0,
+        };
+
+        for inst in netlist.top.walk().filter(|inst| inst.is_leaf()) {
+            let cell = sim.compile(inst)?;
+            sim.cells.push(cell);
+        }
+        sim.schedule()?;
+        sim.eval();
+
+        Ok(sim)
+    }
+
+    fn pin(&self, inst: &Instance, port: &str, bit: i32) -> Option<usize> {
+        let port = inst.interface.get(&Atom::from(port))?;
+        let key = PortRef {
+            instance: inst.path.clone(),
+            port: port.name.name.clone(),
+            member: port.kind.member_of_bit(bit),
+        };
+        self.net_of.get(&key).copied()
+    }
+
+    fn pins(&self, inst: &Instance, names: &[&str]) -> Vec<Option<usize>> {
+        names.iter().map(|n| self.pin(inst, n, 0)).collect()
+    }
+
+    fn bus(&self, inst: &Instance, name: &str, width: i32) -> Vec<Option<usize>> {
+        (0..width).map(|bit| self.pin(inst, name, bit)).collect()
+    }
+
+    fn compile(&self, inst: &Instance) -> Result<Cell> {
+        use PrimitiveKind::*;
+
+        let kind = inst
+            .primitive_kind()
+            .ok_or_else(|| anyhow!("cell `{}` of `{}` is not a primitive", inst.cell, inst.path))?;
+
+        let (logic, inputs, outputs) = match kind {
+            Lut(k) => {
+                let names = (0..k).map(|i| format!("I{}", i)).collect::<Vec<_>>();
+                let names = names.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                (
+                    Logic::Lut(inst.truth_table()?.unwrap()),
+                    self.pins(inst, &names),
+                    self.pins(inst, &["O"]),
+                )
+            }
+            Lut6_2 => {
+                let init = match inst.property_bits("INIT")? {
+                    Some(init) => TruthTable::from_init(6, &init)
+                        .with_context(|| format!("INIT of `{}`", inst.path))?,
+                    None => TruthTable::new(6, 0),
+                };
+                (
+                    Logic::Lut6_2(TruthTable::new(5, init.bits()), init),
+                    self.pins(inst, &["I0", "I1", "I2", "I3", "I4", "I5"]),
+                    self.pins(inst, &["O5", "O6"]),
+                )
+            }
+            Gnd => (Logic::Const(false), vec![], self.pins(inst, &["G"])),
+            Vcc => (Logic::Const(true), vec![], self.pins(inst, &["P"])),
+            Inv => (Logic::Inv, self.pins(inst, &["I"]), self.pins(inst, &["O"])),
+            k if k.is_buffer() => (Logic::Buf, self.pins(inst, &["I"]), self.pins(inst, &["O"])),
+            Muxf7 | Muxf8 | Muxf9 => (
+                Logic::Mux,
+                self.pins(inst, &["I0", "I1", "S"]),
+                self.pins(inst, &["O"]),
+            ),
+            Carry4 => {
+                let mut inputs = self.pins(inst, &["CI", "CYINIT"]);
+                inputs.extend(self.bus(inst, "DI", 4));
+                inputs.extend(self.bus(inst, "S", 4));
+                let mut outputs = self.bus(inst, "CO", 4);
+                outputs.extend(self.bus(inst, "O", 4));
+                (Logic::Carry4, inputs, outputs)
+            }
+            Fdre | Fdse | Fdce | Fdpe => {
+                let (sr, set, sync) = match kind {
+                    Fdre => ("R", false, true),
+                    Fdse => ("S", true, true),
+                    Fdce => ("CLR", false, false),
+                    _ => ("PRE", true, false),
+                };
+                let ff = Ff {
+                    state: inst.bit_property("INIT", set)?,
+                    set,
+                    sync,
+                    d_inverted: inst.bit_property("IS_D_INVERTED", false)?,
+                    sr_inverted: inst.bit_property(&format!("IS_{}_INVERTED", sr), false)?,
+                };
+                (
+                    Logic::Ff(ff),
+                    self.pins(inst, &["D", "CE", sr]),
+                    self.pins(inst, &["Q"]),
+                )
+            }
+            _ => bail!(
+                "cell `{}` of `{}` is not supported by the simulator",
+                inst.cell,
+                inst.path
+            ),
+        };
+
+        Ok(Cell {
+            path: inst.path.clone(),
+            logic,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Orders the cells so that every cell is evaluated after the drivers of its
+    /// combinational inputs.
+    fn schedule(&mut self) -> Result<()> {
+        let mut driver = vec![None; self.nets.len()];
+        for (i, cell) in self.cells.iter().enumerate() {
+            for &net in cell.outputs.iter().flatten() {
+                if let Some(j) = driver[net].replace(i) {
+                    bail!(
+                        "net `{}` has multiple drivers: `{}` and `{}`",
+                        self.nets[net].segments[0],
+                        self.cells[j].path,
+                        cell.path
+                    );
+                }
+            }
+        }
+
+        let mut graph = Graph::<usize, ()>::new();
+        let nodes = (0..self.cells.len())
+            .map(|i| graph.add_node(i))
+            .collect::<Vec<_>>();
+        for (i, cell) in self.cells.iter().enumerate() {
+            let comb_inputs = match &cell.logic {
+                Logic::Ff(ff) if ff.sync => &[],
+                Logic::Ff(_) => &cell.inputs[2..],
+                _ => &cell.inputs[..],
+            };
+            for &net in comb_inputs.iter().flatten() {
+                if let Some(j) = driver[net] {
+                    graph.add_edge(nodes[j], nodes[i], ());
+                }
+            }
+        }
+
+        self.order = petgraph::algo::toposort(&graph, None)
+            .map_err(|cycle| {
+                anyhow!(
+                    "combinational loop through `{}`",
+                    self.cells[graph[cycle.node_id()]].path
+                )
+            })?
+            .into_iter()
+            .map(|n| graph[n])
+            .collect();
+
+        Ok(())
+    }
+
+    fn read(values: &[bool], net: Option<usize>) -> bool {
+        net.is_some_and(|i| values[i])
+    }
+
+    /// Propagates the values through the combinational logic.
+    pub fn eval(&mut self) {
+        let values = &mut self.values;
+        for &i in &self.order {
+            let cell = &mut self.cells[i];
+            let inputs = cell
+                .inputs
+                .iter()
+                .map(|&net| Self::read(values, net))
+                .collect::<Vec<_>>();
+
+            let mut outputs = [false; 8];
+            match &mut cell.logic {
+                Logic::Lut(table) => outputs[0] = table.eval(&inputs),
+                Logic::Lut6_2(o5, o6) => {
+                    outputs[0] = o5.eval(&inputs[..5]);
+                    outputs[1] = o6.eval(&inputs);
+                }
+                Logic::Const(v) => outputs[0] = *v,
+                Logic::Buf => outputs[0] = inputs[0],
+                Logic::Inv => outputs[0] = !inputs[0],
+                Logic::Mux => outputs[0] = if inputs[2] { inputs[1] } else { inputs[0] },
+                Logic::Carry4 => {
+                    let mut carry = inputs[0] | inputs[1];
+                    for k in 0..4 {
+                        let (di, s) = (inputs[2 + k], inputs[6 + k]);
+                        outputs[4 + k] = s ^ carry;
+                        carry = if s { carry } else { di };
+                        outputs[k] = carry;
+                    }
+                }
+                Logic::Ff(ff) => {
+                    if !ff.sync && inputs[2] ^ ff.sr_inverted {
+                        ff.state = ff.set;
+                    }
+                    outputs[0] = ff.state;
+                }
+            }
+
+            for (net, &v) in cell.outputs.iter().zip(&outputs) {
+                if let Some(net) = *net {
+                    values[net] = v;
+                }
+            }
+        }
+    }
+
+    /// Advances by a clock cycle: every flip-flop loads its next state at once, and the
+    /// combinational logic is evaluated again.
+    pub fn step(&mut self) {
+        self.eval();
+
+        let values = &self.values;
+        for cell in &mut self.cells {
+            if let Logic::Ff(ff) = &mut cell.logic {
+                let d = Self::read(values, cell.inputs[0]) ^ ff.d_inverted;
+                let ce = Self::read(values, cell.inputs[1]);
+                let sr = Self::read(values, cell.inputs[2]) ^ ff.sr_inverted;
+                if sr {
+                    ff.state = ff.set;
+                } else if ce {
+                    ff.state = d;
+                }
+            }
+        }
+
+        self.cycle += 1;
+        self.eval();
+    }
+
+    /// The number of clock cycles simulated.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    fn top_port(&self, name: &str) -> Result<&Port> {
+        self.interface
+            .get(&Atom::from(name))
+            .ok_or_else(|| anyhow!("`{}` does not have port `{}`", self.top, name))
+    }
+
+    fn port_nets<'a>(&'a self, port: &'a Port) -> impl Iterator<Item = Option<usize>> + 'a {
+        (0..port.kind.width()).map(move |bit| {
+            let key = PortRef {
+                instance: self.top.clone(),
+                port: port.name.name.clone(),
+                member: port.kind.member_of_bit(bit),
+            };
+            self.net_of.get(&key).copied()
+        })
+    }
+
+    /// Drives the top-level input port `name`. Bit `k` of `value` is the `k`-th bit from the
+    /// LSB of an array port. The values are propagated on the next [`eval`](Self::eval) or
+    /// [`step`](Self::step).
+    pub fn set_input(&mut self, name: &str, value: u64) -> Result<()> {
+        let port = self.top_port(name)?;
+        ensure!(
+            port.dir != Direction::Output,
+            "`{}` is an output port",
+            name
+        );
+        let nets = self.port_nets(port).collect::<Vec<_>>();
+        for (bit, net) in nets.into_iter().enumerate() {
+            if let Some(net) = net {
+                self.values[net] = value >> bit & 1 == 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of the top-level port `name`, with bits numbered as in
+    /// [`set_input`](Self::set_input).
+    pub fn port(&self, name: &str) -> Result<u64> {
+        let port = self.top_port(name)?;
+        Ok(self.port_nets(port).enumerate().fold(0, |v, (bit, net)| {
+            v | (Self::read(&self.values, net) as u64) << bit
+        }))
+    }
+
+    /// The nets of the netlist, in the order of [`values`](Self::values).
+    pub fn nets(&self) -> &[GlobalNet] {
+        &self.nets
+    }
+
+    /// The current values of [`nets`](Self::nets).
+    pub fn values(&self) -> &[bool] {
+        &self.values
+    }
+
+    /// The value of the net with the segment `path`, as in `main/inner/clk`.
+    pub fn net(&self, path: &Path) -> Option<bool> {
+        self.nets
+            .iter()
+            .position(|net| net.segments.contains(path))
+            .map(|i| self.values[i])
+    }
+}
@@ -0,0 +1,286 @@
+//! VCD (Value Change Dump) waveform writer.
+//!
+//! Every hierarchical instance becomes a `$scope`, holding a `$var` for each of its ports and
+//! nets under their original `rename` names. Array ports are dumped as vector variables.
+//! Values are given per [`GlobalNet`](crate::netlist::GlobalNet), as kept by a
+//! [`Simulator`](crate::sim::Simulator), so a net spanning several levels of the hierarchy
+//! shares one identifier code in all of them.
+
+use crate::ast::PortKind;
+use crate::netlist::{glob_match, port_name, GlobalNet, Instance, Netlist, Path, PortRef};
+use anyhow::{ensure, Result};
+use fxhash::FxHashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+#[derive(Debug, Clone, Default)]
+pub struct VcdOptions {
+    /// The `$timescale`, such as `1ns`, which is the default.
+    pub timescale: Option<String>,
+    /// Dump only the signals whose paths, as in `main/inner/clk`, match one of these globs.
+    /// `*` matches within a path component and `**` across components. All signals are
+    /// dumped if empty.
+    pub signals: Vec<String>,
+}
+
+struct Var {
+    id: String,
+    /// Indices of the nets of the bits from the LSB.
+    nets: Vec<Option<usize>>,
+    last: Option<String>,
+}
+
+pub struct VcdWriter<W: Write> {
+    w: W,
+    vars: Vec<Var>,
+    num_nets: usize,
+    time: Option<u64>,
+}
+
+/// The identifier code of the `i`-th variable.
+fn id_code(mut i: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            return id;
+        }
+        i -= 1;
+    }
+}
+
+fn scope_name(inst: &Instance) -> String {
+    match &inst.rename_from {
+        Some(s) => s.clone(),
+        None => inst.path.name().to_string(),
+    }
+}
+
+struct Builder<'a> {
+    options: &'a VcdOptions,
+    net_of_pin: FxHashMap<&'a PortRef, usize>,
+    net_of_segment: FxHashMap<&'a Path, usize>,
+    ids: FxHashMap<Vec<Option<usize>>, usize>,
+    vars: Vec<Var>,
+    out: String,
+}
+
+impl<'a> Builder<'a> {
+    fn selected(&self, paths: &[String]) -> bool {
+        self.options.signals.is_empty()
+            || self
+                .options
+                .signals
+                .iter()
+                .any(|g| paths.iter().any(|p| glob_match(g, p)))
+    }
+
+    fn var(&mut self, nets: Vec<Option<usize>>) -> &str {
+        let len = self.vars.len();
+        let i = *self.ids.entry(nets.clone()).or_insert(len);
+        if i == len {
+            self.vars.push(Var {
+                id: id_code(i),
+                nets,
+                last: None,
+            });
+        }
+        &self.vars[i].id
+    }
+
+    /// Writes the scope of `inst`, and returns whether any variable was declared in it.
+    fn scope(&mut self, inst: &Instance, path: &str, orig_path: &str, indent: usize) -> bool {
+        let pad = "  ".repeat(indent);
+        let start = self.out.len();
+        writeln!(self.out, "{}$scope module {} $end", pad, scope_name(inst)).unwrap();
+        let mut any = false;
+
+        let mut ports = inst.interface.values().collect::<Vec<_>>();
+        ports.sort_by(|a, b| a.name.name.cmp(&b.name.name));
+        let mut port_nets = vec![];
+        for port in ports {
+            let name = port_name(port);
+            let paths = [
+                format!("{}/{}", path, port.name.name),
+                format!("{}/{}", orig_path, name),
+            ];
+            if !self.selected(&paths) {
+                continue;
+            }
+
+            let nets = (0..port.kind.width())
+                .map(|bit| {
+                    let key = PortRef {
+                        instance: inst.path.clone(),
+                        port: port.name.name.clone(),
+                        member: port.kind.member_of_bit(bit),
+                    };
+                    self.net_of_pin.get(&key).copied()
+                })
+                .collect::<Vec<_>>();
+            port_nets.extend(nets.iter().flatten().copied());
+
+            let width = nets.len();
+            let id = self.var(nets).to_string();
+            let range = match port.kind {
+                PortKind::Single => String::new(),
+                PortKind::Array(n) => format!(" [{}:0]", n - 1),
+            };
+            writeln!(
+                self.out,
+                "{}  $var wire {} {} {}{} $end",
+                pad, width, id, name, range
+            )
+            .unwrap();
+            any = true;
+        }
+
+        let mut nets = inst.nets.iter().collect::<Vec<_>>();
+        nets.sort_by_key(|(name, _)| *name);
+        for (name, net) in nets {
+            let i = match self.net_of_segment.get(&inst.path.child(name.clone())) {
+                Some(&i) if !port_nets.contains(&i) => i,
+                _ => continue,
+            };
+            let orig = net.rename_from.clone().unwrap_or_else(|| name.to_string());
+            let paths = [
+                format!("{}/{}", path, name),
+                format!("{}/{}", orig_path, orig),
+            ];
+            if !self.selected(&paths) {
+                continue;
+            }
+
+            let id = self.var(vec![Some(i)]).to_string();
+            writeln!(self.out, "{}  $var wire 1 {} {} $end", pad, id, orig).unwrap();
+            any = true;
+        }
+
+        let mut children = inst
+            .instances
+            .values()
+            .filter(|c| !c.is_leaf())
+            .collect::<Vec<_>>();
+        children.sort_by(|a, b| a.path.cmp(&b.path));
+        for child in children {
+            any |= self.scope(
+                child,
+                &format!("{}/{}", path, child.path.name()),
+                &format!("{}/{}", orig_path, scope_name(child)),
+                indent + 1,
+            );
+        }
+
+        if any {
+            writeln!(self.out, "{}$upscope $end", pad).unwrap();
+        } else {
+            self.out.truncate(start);
+        }
+        any
+    }
+}
+
+impl<W: Write> VcdWriter<W> {
+    /// Writes the header of the dump of `netlist`. `nets` are the nets whose values are
+    /// given to [`dump`](Self::dump), as returned by
+    /// [`Netlist::global_nets`](crate::netlist::Netlist::global_nets).
+    pub fn new(
+        netlist: &Netlist,
+        nets: &[GlobalNet],
+        options: &VcdOptions,
+        mut w: W,
+    ) -> Result<Self> {
+        let mut builder = Builder {
+            options,
+            net_of_pin: FxHashMap::default(),
+            net_of_segment: FxHashMap::default(),
+            ids: FxHashMap::default(),
+            vars: vec![],
+            out: String::new(),
+        };
+        for (i, net) in nets.iter().enumerate() {
+            builder.net_of_pin.extend(net.pins.iter().map(|p| (p, i)));
+            builder
+                .net_of_segment
+                .extend(net.segments.iter().map(|s| (s, i)));
+        }
+
+        let top = &netlist.top;
+        builder.scope(top, &top.path.to_string(), &scope_name(top), 0);
+
+        writeln!(w, "$version edif {} $end", env!("CARGO_PKG_VERSION"))?;
+        writeln!(
+            w,
+            "$timescale {} $end",
+            options.timescale.as_deref().unwrap_or("1ns")
+        )?;
+        w.write_all(builder.out.as_bytes())?;
+        writeln!(w, "$enddefinitions $end")?;
+
+        Ok(VcdWriter {
+            w,
+            vars: builder.vars,
+            num_nets: nets.len(),
+            time: None,
+        })
+    }
+
+    /// Dumps the values of the nets at `time`. Only the variables that changed since the last
+    /// dump are written.
+    pub fn dump(&mut self, time: u64, values: &[bool]) -> Result<()> {
+        ensure!(
+            values.len() == self.num_nets,
+            "expected {} values, found {}",
+            self.num_nets,
+            values.len()
+        );
+        ensure!(
+            self.time.is_none_or(|t| time > t),
+            "time {} is not after {}",
+            time,
+            self.time.unwrap_or(0)
+        );
+        let first = self.time.is_none();
+
+        let mut changes = String::new();
+        for var in &mut self.vars {
+            let value = var
+                .nets
+                .iter()
+                .rev()
+                .map(|net| match net {
+                    Some(i) if values[*i] => '1',
+                    Some(_) => '0',
+                    None => 'z',
+                })
+                .collect::<String>();
+            if var.last.as_ref() == Some(&value) {
+                continue;
+            }
+            if value.len() == 1 {
+                writeln!(changes, "{}{}", value, var.id).unwrap();
+            } else {
+                writeln!(changes, "b{} {}", value, var.id).unwrap();
+            }
+            var.last = Some(value);
+        }
+
+        if first {
+            writeln!(self.w, "#{}", time)?;
+            writeln!(self.w, "$dumpvars")?;
+            self.w.write_all(changes.as_bytes())?;
+            writeln!(self.w, "$end")?;
+        } else if !changes.is_empty() {
+            writeln!(self.w, "#{}", time)?;
+            self.w.write_all(changes.as_bytes())?;
+        }
+        self.time = Some(time);
+
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
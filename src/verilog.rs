@@ -0,0 +1,254 @@
+//! Structural Verilog writer for [`Netlist`](crate::netlist::Netlist).
+//!
+//! Every hierarchical cell becomes a `module`, and the leaf instances are instantiated as
+//! library cells, with their properties as parameters. Ports, nets and instances keep the
+//! original names given by `rename`, escaped when they are not Verilog identifiers. Black
+//! boxes get an empty module with the ports inferred for them. Instances of a hierarchical cell
+//! whose contents were edited apart become modules of their own, named with a `_1`, `_2`...
+//! suffix.
+
+use crate::ast::{Direction, Port, PortKind, Property};
+use crate::diff::property_string;
+use crate::literal::BitVec;
+use crate::netlist::{port_name, Definitions, Instance, Netlist, PortRef};
+use anyhow::{ensure, Result};
+use fxhash::{FxHashMap, FxHashSet};
+use std::fmt::Write as _;
+use std::io::Write;
+
+const KEYWORDS: &[&str] = &[
+    "always",
+    "assign",
+    "begin",
+    "case",
+    "default",
+    "else",
+    "end",
+    "endcase",
+    "endmodule",
+    "for",
+    "function",
+    "if",
+    "initial",
+    "inout",
+    "input",
+    "integer",
+    "module",
+    "output",
+    "parameter",
+    "reg",
+    "supply0",
+    "supply1",
+    "tri",
+    "wire",
+];
+
+/// `name` as a Verilog identifier, escaped if needed.
+fn ident(name: &str) -> String {
+    let simple = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if simple && !KEYWORDS.contains(&name) {
+        name.to_string()
+    } else {
+        format!("\\{} ", name)
+    }
+}
+
+fn parameter(value: &Property) -> String {
+    match value {
+        Property::String(s) if s.parse::<BitVec>().is_ok() => s.clone(),
+        Property::String(s) => format!("\"{}\"", s.replace('"', "\\\"")),
+        Property::Integer(i) => i.to_string(),
+        Property::Number(n) => match n.to_i64() {
+            Some(i) => i.to_string(),
+            None => n.to_string(),
+        },
+        Property::Boolean(b) => format!("\"{}\"", if *b { "TRUE" } else { "FALSE" }),
+        // Verilog has no list parameters, and the elements are kept in a string.
+        Property::List(_) => format!("\"{}\"", property_string(value).replace('"', "\\\"")),
+    }
+}
+
+fn write_header<W: Write>(inst: &Instance, name: &str, w: &mut W) -> Result<()> {
+    let ports = inst.interface.values();
+    let names = ports
+        .clone()
+        .map(|p| format!("  {}", ident(&port_name(p))))
+        .collect::<Vec<_>>();
+    writeln!(w, "module {} (", ident(name))?;
+    writeln!(w, "{}", names.join(",\n"))?;
+    writeln!(w, ");")?;
+    for p in ports {
+        let dir = match p.dir {
+            Direction::Input => "input",
+            Direction::Output => "output",
+            Direction::InOut => "inout",
+        };
+        match p.kind {
+            PortKind::Single => writeln!(w, "  {} {};", dir, ident(&port_name(p)))?,
+            PortKind::Array(n) => writeln!(w, "  {} [{}:0] {};", dir, n - 1, ident(&port_name(p)))?,
+        }
+    }
+    Ok(())
+}
+
+/// The expression of the pin `p` of `port`, as in `ret[0]`.
+fn pin_expr(port: &Port, p: &PortRef) -> String {
+    let name = ident(&port_name(port));
+    match p.member {
+        Some(m) => format!("{}[{}]", name, port.kind.width() - 1 - m),
+        None => name,
+    }
+}
+
+fn write_module<W: Write>(defs: &Definitions, inst: &Instance, w: &mut W) -> Result<()> {
+    write_header(inst, defs.name(inst), w)?;
+    writeln!(w)?;
+
+    // Name the nets after the ports of the module they connect to, or declare them.
+    let mut exprs = FxHashMap::<&PortRef, String>::default();
+    let mut wires = vec![];
+    let mut assigns = vec![];
+    let mut used = inst
+        .interface
+        .values()
+        .map(port_name)
+        .collect::<FxHashSet<_>>();
+    for (name, net) in &inst.nets {
+        let mut own = vec![];
+        for p in &net.ports {
+            let port = inst.pin_port(p)?;
+            if p.instance == inst.path {
+                own.push((port, p));
+            }
+        }
+        own.sort_by_key(|&(port, p)| (port.dir != Direction::Input, p));
+        let expr = match own.split_first() {
+            Some((&(first_port, first), rest)) => {
+                let expr = pin_expr(first_port, first);
+                for &(port, p) in rest {
+                    // Inputs sort first, and one cannot be assigned another.
+                    ensure!(
+                        port.dir != Direction::Input,
+                        "net `{}` of `{}` connects the inputs `{}` and `{}`",
+                        name,
+                        inst.cell,
+                        expr,
+                        pin_expr(port, p)
+                    );
+                    assigns.push(format!("  assign {} = {};", pin_expr(port, p), expr));
+                }
+                expr
+            }
+            None => {
+                let orig = net.rename_from.as_deref().unwrap_or(name);
+                let mut wire = orig.to_string();
+                for i in 1.. {
+                    if used.insert(wire.clone()) {
+                        break;
+                    }
+                    wire = format!("{}_{}", orig, i);
+                }
+                let expr = ident(&wire);
+                wires.push(format!("  wire {};", expr));
+                expr
+            }
+        };
+        for p in &net.ports {
+            exprs.insert(p, expr.clone());
+        }
+    }
+
+    let mut body = String::new();
+    let mut unconnected = 0;
+    for child in inst.instances.values() {
+        let props = &child.properties;
+        write!(body, "  {} ", ident(defs.name(child))).unwrap();
+        if !props.is_empty() {
+            let params = props
+                .iter()
+                .map(|(k, v)| format!("    .{}({})", ident(k), parameter(v)))
+                .collect::<Vec<_>>();
+            write!(body, "#(\n{}\n  ) ", params.join(",\n")).unwrap();
+        }
+        let name = child
+            .rename_from
+            .clone()
+            .unwrap_or_else(|| child.path.name().to_string());
+        writeln!(body, "{} (", ident(&name)).unwrap();
+
+        let mut conns = vec![];
+        for port in child.interface.values() {
+            let expr = |member| {
+                let pin = PortRef {
+                    instance: child.path.clone(),
+                    port: port.name.name.clone(),
+                    member,
+                };
+                exprs.get(&pin).cloned()
+            };
+            let conn = match port.kind {
+                PortKind::Single => expr(None).unwrap_or_default(),
+                PortKind::Array(n) => {
+                    let bits = (0..n).map(|m| expr(Some(m))).collect::<Vec<_>>();
+                    if bits.iter().all(Option::is_none) {
+                        String::new()
+                    } else {
+                        // Members are numbered from the MSB, as in a concatenation.
+                        let bits = bits
+                            .into_iter()
+                            .map(|b| {
+                                b.unwrap_or_else(|| loop {
+                                    unconnected += 1;
+                                    let name = format!("unconnected_{}", unconnected);
+                                    if !used.insert(name.clone()) {
+                                        continue;
+                                    }
+                                    wires.push(format!("  wire {};", name));
+                                    break name;
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        format!("{{{}}}", bits.join(", "))
+                    }
+                }
+            };
+            conns.push(format!("    .{}({})", ident(&port_name(port)), conn));
+        }
+        writeln!(body, "{}\n  );", conns.join(",\n")).unwrap();
+    }
+
+    for line in wires.iter().chain(&assigns) {
+        writeln!(w, "{}", line)?;
+    }
+    if !wires.is_empty() || !assigns.is_empty() {
+        writeln!(w)?;
+    }
+    w.write_all(body.as_bytes())?;
+    writeln!(w, "endmodule")?;
+    Ok(())
+}
+
+/// Writes `netlist` in structural Verilog.
+pub fn write_verilog<W: Write>(netlist: &Netlist, mut w: W) -> Result<()> {
+    let defs = Definitions::new(&netlist.top);
+    let modules = defs
+        .cells
+        .iter()
+        .filter(|(inst, _)| inst.black_box || !inst.is_leaf());
+    for (i, (inst, name)) in modules.enumerate() {
+        if i != 0 {
+            writeln!(w)?;
+        }
+        if inst.black_box && inst.is_leaf() {
+            writeln!(w, "(* black_box *)")?;
+            write_header(inst, name, &mut w)?;
+            writeln!(w, "endmodule")?;
+        } else {
+            write_module(&defs, inst, &mut w)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,597 @@
+//! Traversal of the [AST](crate::ast) and the [netlist](crate::netlist).
+//!
+//! Each tree has three traits: [`Visit`](netlist::Visit) borrows the nodes,
+//! [`VisitMut`](netlist::VisitMut) mutates them in place, and [`Fold`](netlist::Fold) rebuilds
+//! the tree by value. Every method has a default which descends into the children of its node
+//! through the free function of the same name, so an implementation overriding a method runs
+//! its pre-order hook before calling that function, its post-order hook after it, and skips
+//! the children by not calling it at all. A visitor stops the whole traversal by returning
+//! [`ControlFlow::Break`], with a value of the type parameter `B`.
+//!
+//! The children of a node are visited in the order of the source.
+//!
+//! ```
+//! use edif::netlist::Instance;
+//! use edif::visit::netlist::{self, Visit};
+//! use std::ops::ControlFlow;
+//!
+//! /// The first leaf instance of the cell `LUT1`.
+//! struct FindLut;
+//!
+//! impl<'a> Visit<'a, &'a Instance> for FindLut {
+//!     fn visit_instance(&mut self, inst: &'a Instance) -> ControlFlow<&'a Instance> {
+//!         if inst.is_leaf() && &*inst.cell == "LUT1" {
+//!             return ControlFlow::Break(inst);
+//!         }
+//!         netlist::visit_instance(self, inst)
+//!     }
+//! }
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! # let s = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test.edf"))?;
+//! let n = edif::netlist::from_str(&s)?;
+//! if let ControlFlow::Break(lut) = FindLut.visit_netlist(&n) {
+//!     println!("{}", lut.path);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+/// Traversal of an [`Edif`](crate::ast::Edif): its libraries, their cells, and the ports,
+/// instances and nets of each cell. The methods of a cell get the name of its library, and
+/// the ones of the nodes below a cell its [`Scope`].
+pub mod ast {
+    use crate::ast::{Cell, Content, Edif, Instance, Library, Name, Net, Port, PortRef, Property};
+    use crate::atom::Atom;
+    use std::ops::ControlFlow;
+    use std::sync::Arc;
+
+    /// The cell containing a node, and its library.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Scope<'s> {
+        pub lib: &'s Atom,
+        pub cell: &'s Atom,
+    }
+
+    pub trait Visit<'a, B = ()> {
+        fn visit_edif(&mut self, edif: &'a Edif) -> ControlFlow<B> {
+            visit_edif(self, edif)
+        }
+
+        fn visit_library(&mut self, lib: &'a Library) -> ControlFlow<B> {
+            visit_library(self, lib)
+        }
+
+        fn visit_cell(&mut self, lib: &'a Atom, cell: &'a Cell) -> ControlFlow<B> {
+            visit_cell(self, lib, cell)
+        }
+
+        fn visit_port(&mut self, _scope: Scope<'a>, _port: &'a Port) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_instance(&mut self, scope: Scope<'a>, inst: &'a Instance) -> ControlFlow<B> {
+            visit_instance(self, scope, inst)
+        }
+
+        fn visit_net(&mut self, scope: Scope<'a>, net: &'a Net) -> ControlFlow<B> {
+            visit_net(self, scope, net)
+        }
+
+        fn visit_portref(&mut self, _scope: Scope<'a>, _pin: &'a PortRef) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        /// A property of the instance `inst` of the cell `scope`.
+        fn visit_property(
+            &mut self,
+            _scope: Scope<'a>,
+            _inst: &'a Atom,
+            _name: &'a Name,
+            _value: &'a Property,
+        ) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+    }
+
+    pub fn visit_edif<'a, B, V>(v: &mut V, edif: &'a Edif) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        for lib in edif.libs.values() {
+            v.visit_library(lib)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_library<'a, B, V>(v: &mut V, lib: &'a Library) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        for cell in lib.cells.values() {
+            v.visit_cell(&lib.name, cell)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_cell<'a, B, V>(v: &mut V, lib: &'a Atom, cell: &'a Cell) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        let scope = Scope {
+            lib,
+            cell: &cell.name,
+        };
+        for port in &cell.view.interface.ports {
+            v.visit_port(scope, port)?;
+        }
+        for c in &cell.view.contents {
+            match c {
+                Content::Instance(inst) => v.visit_instance(scope, inst)?,
+                Content::Net(net) => v.visit_net(scope, net)?,
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_instance<'a, B, V>(
+        v: &mut V,
+        scope: Scope<'a>,
+        inst: &'a Instance,
+    ) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        for (name, value) in inst.properties.iter() {
+            v.visit_property(scope, &inst.name.name, name, value)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_net<'a, B, V>(v: &mut V, scope: Scope<'a>, net: &'a Net) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        for pin in &net.portrefs {
+            v.visit_portref(scope, pin)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub trait VisitMut<B = ()> {
+        fn visit_edif_mut(&mut self, edif: &mut Edif) -> ControlFlow<B> {
+            visit_edif_mut(self, edif)
+        }
+
+        fn visit_library_mut(&mut self, lib: &mut Library) -> ControlFlow<B> {
+            visit_library_mut(self, lib)
+        }
+
+        fn visit_cell_mut(&mut self, lib: &Atom, cell: &mut Cell) -> ControlFlow<B> {
+            visit_cell_mut(self, lib, cell)
+        }
+
+        fn visit_port_mut(&mut self, _scope: Scope, _port: &mut Port) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_instance_mut(&mut self, scope: Scope, inst: &mut Instance) -> ControlFlow<B> {
+            visit_instance_mut(self, scope, inst)
+        }
+
+        fn visit_net_mut(&mut self, scope: Scope, net: &mut Net) -> ControlFlow<B> {
+            visit_net_mut(self, scope, net)
+        }
+
+        fn visit_portref_mut(&mut self, _scope: Scope, _pin: &mut PortRef) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_property_mut(
+            &mut self,
+            _scope: Scope,
+            _inst: &Atom,
+            _name: &Name,
+            _value: &mut Property,
+        ) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+    }
+
+    pub fn visit_edif_mut<B, V>(v: &mut V, edif: &mut Edif) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        for lib in edif.libs.values_mut() {
+            v.visit_library_mut(lib)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_library_mut<B, V>(v: &mut V, lib: &mut Library) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        for cell in lib.cells.values_mut() {
+            v.visit_cell_mut(&lib.name, cell)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_cell_mut<B, V>(v: &mut V, lib: &Atom, cell: &mut Cell) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        let scope = Scope {
+            lib,
+            cell: &cell.name,
+        };
+        for port in &mut cell.view.interface.ports {
+            v.visit_port_mut(scope, port)?;
+        }
+        for c in &mut cell.view.contents {
+            match c {
+                Content::Instance(inst) => v.visit_instance_mut(scope, inst)?,
+                Content::Net(net) => v.visit_net_mut(scope, net)?,
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The properties shared with other instances are copied first.
+    pub fn visit_instance_mut<B, V>(v: &mut V, scope: Scope, inst: &mut Instance) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        if inst.properties.is_empty() {
+            return ControlFlow::Continue(());
+        }
+        for (name, value) in Arc::make_mut(&mut inst.properties).iter_mut() {
+            v.visit_property_mut(scope, &inst.name.name, name, value)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_net_mut<B, V>(v: &mut V, scope: Scope, net: &mut Net) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        for pin in &mut net.portrefs {
+            v.visit_portref_mut(scope, pin)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The scopes have the names of the nodes before they are folded.
+    pub trait Fold {
+        fn fold_edif(&mut self, edif: Edif) -> Edif {
+            fold_edif(self, edif)
+        }
+
+        fn fold_library(&mut self, lib: Library) -> Library {
+            fold_library(self, lib)
+        }
+
+        fn fold_cell(&mut self, lib: &Atom, cell: Cell) -> Cell {
+            fold_cell(self, lib, cell)
+        }
+
+        fn fold_port(&mut self, _scope: Scope, port: Port) -> Port {
+            port
+        }
+
+        fn fold_instance(&mut self, scope: Scope, inst: Instance) -> Instance {
+            fold_instance(self, scope, inst)
+        }
+
+        fn fold_net(&mut self, scope: Scope, net: Net) -> Net {
+            fold_net(self, scope, net)
+        }
+
+        fn fold_portref(&mut self, _scope: Scope, pin: PortRef) -> PortRef {
+            pin
+        }
+
+        fn fold_property(
+            &mut self,
+            _scope: Scope,
+            _inst: &Atom,
+            _name: &Name,
+            value: Property,
+        ) -> Property {
+            value
+        }
+    }
+
+    /// The libraries are stored under their folded names.
+    pub fn fold_edif<F: Fold + ?Sized>(f: &mut F, mut edif: Edif) -> Edif {
+        edif.libs = edif
+            .libs
+            .into_values()
+            .map(|lib| {
+                let lib = f.fold_library(lib);
+                (lib.name.clone(), lib)
+            })
+            .collect();
+        edif
+    }
+
+    /// The cells are stored under their folded names.
+    pub fn fold_library<F: Fold + ?Sized>(f: &mut F, mut lib: Library) -> Library {
+        let name = &lib.name;
+        lib.cells = lib
+            .cells
+            .into_values()
+            .map(|cell| {
+                let cell = f.fold_cell(name, cell);
+                (cell.name.clone(), cell)
+            })
+            .collect();
+        lib
+    }
+
+    pub fn fold_cell<F: Fold + ?Sized>(f: &mut F, lib: &Atom, mut cell: Cell) -> Cell {
+        let scope = Scope {
+            lib,
+            cell: &cell.name,
+        };
+        let view = &mut cell.view;
+        view.interface.ports = std::mem::take(&mut view.interface.ports)
+            .into_iter()
+            .map(|p| f.fold_port(scope, p))
+            .collect();
+        view.contents = std::mem::take(&mut view.contents)
+            .into_iter()
+            .map(|c| match c {
+                Content::Instance(inst) => Content::Instance(f.fold_instance(scope, inst)),
+                Content::Net(net) => Content::Net(f.fold_net(scope, net)),
+            })
+            .collect();
+        cell
+    }
+
+    pub fn fold_instance<F: Fold + ?Sized>(
+        f: &mut F,
+        scope: Scope,
+        mut inst: Instance,
+    ) -> Instance {
+        if inst.properties.is_empty() {
+            return inst;
+        }
+        let props = Arc::try_unwrap(inst.properties).unwrap_or_else(|p| (*p).clone());
+        let name = &inst.name.name;
+        inst.properties = Arc::new(
+            props
+                .into_iter()
+                .map(|(prop, value)| {
+                    let value = f.fold_property(scope, name, &prop, value);
+                    (prop, value)
+                })
+                .collect(),
+        );
+        inst
+    }
+
+    pub fn fold_net<F: Fold + ?Sized>(f: &mut F, scope: Scope, mut net: Net) -> Net {
+        net.portrefs = std::mem::take(&mut net.portrefs)
+            .into_iter()
+            .map(|p| f.fold_portref(scope, p))
+            .collect();
+        net
+    }
+}
+
+/// Traversal of a [`Netlist`](crate::netlist::Netlist): the instance tree, and the ports,
+/// properties and nets of each instance. The methods of the nodes below an instance get the
+/// path of the instance, and the ones of a net and its pins the path of the net.
+///
+/// An instance is visited in the order of its interface, its properties, its nets and their
+/// pins, and then its children, so the pins of a scope referring to its children are seen
+/// before the children.
+pub mod netlist {
+    use crate::ast::{Port, Property};
+    use crate::atom::Atom;
+    use crate::netlist::{Instance, Net, Netlist, Path, PortRef};
+    use std::mem;
+    use std::ops::ControlFlow;
+
+    pub trait Visit<'a, B = ()> {
+        fn visit_netlist(&mut self, netlist: &'a Netlist) -> ControlFlow<B> {
+            self.visit_instance(&netlist.top)
+        }
+
+        fn visit_instance(&mut self, inst: &'a Instance) -> ControlFlow<B> {
+            visit_instance(self, inst)
+        }
+
+        fn visit_port(&mut self, _scope: &Path, _port: &'a Port) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_property(
+            &mut self,
+            _scope: &Path,
+            _name: &'a Atom,
+            _value: &'a Property,
+        ) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_net(&mut self, path: &Path, net: &'a Net) -> ControlFlow<B> {
+            visit_net(self, path, net)
+        }
+
+        fn visit_portref(&mut self, _net: &Path, _pin: &'a PortRef) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+    }
+
+    pub fn visit_instance<'a, B, V>(v: &mut V, inst: &'a Instance) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        for port in inst.interface.values() {
+            v.visit_port(&inst.path, port)?;
+        }
+        for (name, value) in &inst.properties {
+            v.visit_property(&inst.path, name, value)?;
+        }
+        for (name, net) in &inst.nets {
+            v.visit_net(&inst.path.child(name.clone()), net)?;
+        }
+        for child in inst.instances.values() {
+            v.visit_instance(child)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn visit_net<'a, B, V>(v: &mut V, path: &Path, net: &'a Net) -> ControlFlow<B>
+    where
+        V: Visit<'a, B> + ?Sized,
+    {
+        for pin in &net.ports {
+            v.visit_portref(path, pin)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The keys of the maps are not updated, so a visitor renaming a node should rename it in
+    /// the map of its parent as well.
+    pub trait VisitMut<B = ()> {
+        fn visit_netlist_mut(&mut self, netlist: &mut Netlist) -> ControlFlow<B> {
+            self.visit_instance_mut(&mut netlist.top)
+        }
+
+        fn visit_instance_mut(&mut self, inst: &mut Instance) -> ControlFlow<B> {
+            visit_instance_mut(self, inst)
+        }
+
+        fn visit_port_mut(&mut self, _scope: &Path, _port: &mut Port) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_property_mut(
+            &mut self,
+            _scope: &Path,
+            _name: &Atom,
+            _value: &mut Property,
+        ) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+
+        fn visit_net_mut(&mut self, path: &Path, net: &mut Net) -> ControlFlow<B> {
+            visit_net_mut(self, path, net)
+        }
+
+        fn visit_portref_mut(&mut self, _net: &Path, _pin: &mut PortRef) -> ControlFlow<B> {
+            ControlFlow::Continue(())
+        }
+    }
+
+    pub fn visit_instance_mut<B, V>(v: &mut V, inst: &mut Instance) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        for port in inst.interface.values_mut() {
+            v.visit_port_mut(&inst.path, port)?;
+        }
+        for (name, value) in &mut inst.properties {
+            v.visit_property_mut(&inst.path, name, value)?;
+        }
+        for (name, net) in &mut inst.nets {
+            v.visit_net_mut(&inst.path.child(name.clone()), net)?;
+        }
+        for child in inst.instances.values_mut() {
+            v.visit_instance_mut(child)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The pins are hashed, so the set of them is rebuilt, in the same order.
+    pub fn visit_net_mut<B, V>(v: &mut V, path: &Path, net: &mut Net) -> ControlFlow<B>
+    where
+        V: VisitMut<B> + ?Sized,
+    {
+        let mut flow = ControlFlow::Continue(());
+        net.ports = mem::take(&mut net.ports)
+            .into_iter()
+            .map(|mut pin| {
+                if flow.is_continue() {
+                    flow = v.visit_portref_mut(path, &mut pin);
+                }
+                pin
+            })
+            .collect();
+        flow
+    }
+
+    pub trait Fold {
+        fn fold_netlist(&mut self, netlist: Netlist) -> Netlist {
+            Netlist {
+                top: Box::new(self.fold_instance(*netlist.top)),
+            }
+        }
+
+        fn fold_instance(&mut self, inst: Instance) -> Instance {
+            fold_instance(self, inst)
+        }
+
+        fn fold_port(&mut self, _scope: &Path, port: Port) -> Port {
+            port
+        }
+
+        fn fold_property(&mut self, _scope: &Path, _name: &Atom, value: Property) -> Property {
+            value
+        }
+
+        fn fold_net(&mut self, path: &Path, net: Net) -> Net {
+            fold_net(self, path, net)
+        }
+
+        fn fold_portref(&mut self, _net: &Path, pin: PortRef) -> PortRef {
+            pin
+        }
+    }
+
+    /// The ports and the children are stored under their folded names.
+    pub fn fold_instance<F: Fold + ?Sized>(f: &mut F, mut inst: Instance) -> Instance {
+        let path = inst.path.clone();
+        inst.interface = mem::take(&mut inst.interface)
+            .into_values()
+            .map(|port| {
+                let port = f.fold_port(&path, port);
+                (port.name.name.clone(), port)
+            })
+            .collect();
+        inst.properties = mem::take(&mut inst.properties)
+            .into_iter()
+            .map(|(name, value)| {
+                let value = f.fold_property(&path, &name, value);
+                (name, value)
+            })
+            .collect();
+        inst.nets = mem::take(&mut inst.nets)
+            .into_iter()
+            .map(|(name, net)| {
+                let net = f.fold_net(&path.child(name.clone()), net);
+                (name, net)
+            })
+            .collect();
+        inst.instances = mem::take(&mut inst.instances)
+            .into_values()
+            .map(|child| {
+                let child = f.fold_instance(child);
+                (child.path.name(), child)
+            })
+            .collect();
+        inst
+    }
+
+    pub fn fold_net<F: Fold + ?Sized>(f: &mut F, path: &Path, mut net: Net) -> Net {
+        net.ports = mem::take(&mut net.ports)
+            .into_iter()
+            .map(|pin| f.fold_portref(path, pin))
+            .collect();
+        net
+    }
+}
@@ -0,0 +1,281 @@
+//! EDIF writer for [`Netlist`](crate::netlist::Netlist).
+//!
+//! Every distinct cell becomes a `cell` of its library, written before the cells using it.
+//! Names which are not EDIF identifiers, such as the `inner/x_reg_0_` of a flattened
+//! netlist, are legalized and keep their original form in a `rename`. Instances of a
+//! hierarchical cell whose contents were edited apart are written as cells of their own,
+//! named with a `_1`, `_2`... suffix.
+
+use crate::ast::{Direction, Port, PortKind, Property};
+use crate::atom::Atom;
+use crate::mangle::Scope;
+use crate::netlist::{Definitions, Instance, Netlist, PortRef};
+use anyhow::{anyhow, Result};
+use fxhash::FxHashMap;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The identifier of `name` in `scope`, renamed from `orig` or from `name` itself if it is not
+/// legal.
+fn rename(scope: &mut Scope, name: &Atom, orig: Option<&str>) -> String {
+    let ident = scope.ident(name);
+    match orig {
+        Some(orig) => format!("(rename {} \"{}\")", ident, orig.replace('"', "'")),
+        None if ident != name => format!("(rename {} \"{}\")", ident, name.replace('"', "'")),
+        None => ident.to_string(),
+    }
+}
+
+fn property_kind(p: &Property) -> &'static str {
+    match p {
+        Property::String(_) => "string",
+        Property::Integer(_) => "integer",
+        Property::Number(_) => "number",
+        Property::Boolean(_) => "boolean",
+        Property::List(_) => "array",
+    }
+}
+
+/// The typed value of `p`. A list is written with the elements of one kind, as in
+/// `(integer 1 2 3)`, or in an `array` otherwise.
+fn property_value(p: &Property) -> String {
+    let element = |p: &Property| match p {
+        Property::String(s) => format!("\"{}\"", s.replace('"', "'")),
+        Property::Integer(i) => i.to_string(),
+        Property::Number(n) => format!("(e {} {})", n.mantissa, n.exponent),
+        Property::Boolean(b) => format!("({})", b),
+        Property::List(_) => unreachable!(),
+    };
+    match p {
+        Property::List(l)
+            if !l.is_empty()
+                && l.iter().all(|e| {
+                    !matches!(e, Property::List(_)) && property_kind(e) == property_kind(&l[0])
+                }) =>
+        {
+            let elems = l.iter().map(element).collect::<Vec<_>>();
+            format!("({} {})", property_kind(&l[0]), elems.join(" "))
+        }
+        Property::List(l) => {
+            let elems = l.iter().map(property_value).collect::<Vec<_>>();
+            format!("(array {})", elems.join(" "))
+        }
+        p => format!("({} {})", property_kind(p), element(p)),
+    }
+}
+
+/// A library and the name of a definition in it.
+type CellKey = (Atom, Atom);
+
+struct EdifWriter<'a> {
+    defs: Definitions<'a>,
+    libs: Scope,
+    /// Cell names per library.
+    cell_names: FxHashMap<Atom, Scope>,
+    /// Port names per cell.
+    port_names: FxHashMap<CellKey, Scope>,
+}
+
+/// The `timeStamp` of the current UTC time, or of `SOURCE_DATE_EPOCH` if set, for
+/// reproducible output.
+fn timestamp() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+
+    // Days to a civil date, after Howard Hinnant's `civil_from_days`.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "(timeStamp {} {:02} {:02} {:02} {:02} {:02})",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+impl EdifWriter<'_> {
+    fn key(&self, inst: &Instance) -> CellKey {
+        (inst.lib.clone(), self.defs.name(inst).clone())
+    }
+
+    /// The `portref` of the pin `p` of a net of `scope`.
+    fn portref(&mut self, scope: &Instance, p: &PortRef, insts: &mut Scope) -> Result<String> {
+        scope.pin_port(p)?;
+        let (cell, inst) = match scope.instances.get(&p.instance.name()) {
+            Some(child) if child.path == p.instance => (
+                self.key(child),
+                Some(insts.ident(&child.path.name()).to_string()),
+            ),
+            _ => (self.key(scope), None),
+        };
+        let port = self
+            .port_names
+            .get_mut(&cell)
+            .ok_or_else(|| anyhow!("cell `{}` is not written yet", cell.1))?
+            .ident(&p.port)
+            .to_string();
+        let port = match p.member {
+            Some(m) => format!("(member {} {})", port, m),
+            None => port,
+        };
+        Ok(match inst {
+            Some(inst) => format!("(portref {} (instanceref {}))", port, inst),
+            None => format!("(portref {})", port),
+        })
+    }
+
+    fn write_cell<W: Write>(&mut self, inst: &Instance, w: &mut W) -> Result<()> {
+        let cell = self.key(inst);
+        let names = self.cell_names.entry(inst.lib.clone()).or_default();
+        let name = rename(names, &cell.1, None);
+        writeln!(w, "    (cell {} (celltype GENERIC)", name)?;
+        writeln!(w, "      (view netlist (viewtype NETLIST)")?;
+
+        writeln!(w, "        (interface")?;
+        let mut names = Scope::default();
+        for Port {
+            kind, dir, name, ..
+        } in inst.interface.values()
+        {
+            let name = rename(&mut names, &name.name, name.rename_from.as_deref());
+            let name = match kind {
+                PortKind::Single => name,
+                PortKind::Array(n) => format!("(array {} {})", name, n),
+            };
+            let dir = match dir {
+                Direction::Input => "INPUT",
+                Direction::Output => "OUTPUT",
+                Direction::InOut => "INOUT",
+            };
+            writeln!(w, "          (port {} (direction {}))", name, dir)?;
+        }
+        self.port_names.insert(cell, names);
+        writeln!(w, "        )")?;
+
+        if !inst.is_leaf() {
+            writeln!(w, "        (contents")?;
+            let mut insts = Scope::default();
+            for child in inst.instances.values() {
+                let name = rename(&mut insts, &child.path.name(), child.rename_from.as_deref());
+                let cell = self.cell_names[&child.lib]
+                    .get(self.defs.name(child))
+                    .unwrap()
+                    .clone();
+                let lib = self.libs.ident(&child.lib).to_string();
+                write!(
+                    w,
+                    "          (instance {} (viewref netlist (cellref {} (libraryref {})))",
+                    name, cell, lib
+                )?;
+                let mut prop_names = Scope::default();
+                for (name, value) in &child.properties {
+                    let value = property_value(value);
+                    write!(
+                        w,
+                        " (property {} {})",
+                        rename(&mut prop_names, name, None),
+                        value
+                    )?;
+                }
+                writeln!(w, ")")?;
+            }
+
+            let mut net_names = Scope::default();
+            for (name, net) in &inst.nets {
+                let pins = net
+                    .ports
+                    .iter()
+                    .map(|p| self.portref(inst, p, &mut insts))
+                    .collect::<Result<Vec<_>>>()?;
+                writeln!(
+                    w,
+                    "          (net {} (joined {}))",
+                    rename(&mut net_names, name, net.rename_from.as_deref()),
+                    pins.join(" ")
+                )?;
+            }
+            writeln!(w, "        )")?;
+        }
+
+        writeln!(w, "      )")?;
+        writeln!(w, "    )")?;
+        Ok(())
+    }
+}
+
+/// Writes `netlist` in EDIF.
+pub fn write_edif<W: Write>(netlist: &Netlist, mut w: W) -> Result<()> {
+    let top = &netlist.top;
+    let mut writer = EdifWriter {
+        defs: Definitions::new(top),
+        libs: Scope::default(),
+        cell_names: FxHashMap::default(),
+        port_names: FxHashMap::default(),
+    };
+
+    // The libraries in the order of their first cells.
+    let mut libs = Vec::<(Atom, Vec<&Instance>)>::new();
+    for &(inst, _) in &writer.defs.cells {
+        match libs.iter_mut().find(|(l, _)| *l == inst.lib) {
+            Some((_, cells)) => cells.push(inst),
+            None => libs.push((inst.lib.clone(), vec![inst])),
+        }
+    }
+
+    let mut names = Scope::default();
+    writeln!(w, "(edif {}", rename(&mut names, &top.path.name(), None))?;
+    writeln!(w, "  (edifversion 2 0 0)")?;
+    writeln!(w, "  (edifLevel 0)")?;
+    writeln!(w, "  (keywordmap (keywordlevel 0))")?;
+    writeln!(
+        w,
+        "  (status (written {} (program \"edif-rs\" (version \"{}\"))))",
+        timestamp(),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    for (lib, cells) in libs {
+        let kind = if cells.iter().all(|c| c.black_box && c.is_leaf()) {
+            "external"
+        } else {
+            "Library"
+        };
+        let name = rename(&mut writer.libs, &lib, None);
+        writeln!(w, "  ({} {}", kind, name)?;
+        writeln!(w, "    (edifLevel 0)")?;
+        writeln!(w, "    (technology (numberDefinition))")?;
+        for inst in cells {
+            writer.write_cell(inst, &mut w)?;
+        }
+        writeln!(w, "  )")?;
+    }
+    writeln!(
+        w,
+        "  (design {} (cellref {} (libraryref {})))",
+        rename(&mut names, &top.path.name(), None),
+        writer.cell_names[&top.lib]
+            .get(writer.defs.name(top))
+            .unwrap(),
+        writer.libs.ident(&top.lib)
+    )?;
+    writeln!(w, ")")?;
+    Ok(())
+}
@@ -0,0 +1,623 @@
+//! Conversion between [`Netlist`](crate::netlist::Netlist) and the JSON netlist format of
+//! Yosys (`write_json` / `read_json`).
+//!
+//! Every hierarchical cell becomes a module, and leaf instances become Yosys cells whose
+//! `type` is the EDIF cell name. Modules are named as the EDIF writer names cells, with a
+//! suffix for the cells of other libraries or edited copies sharing a name. Bits of a module are numbered per net, starting from 2 as
+//! `0` and `1` are reserved for constants by Yosys.
+
+use crate::ast::{self, Direction, FxIndexMap, FxIndexSet, PortKind, Property};
+use crate::atom::Atom;
+use crate::diff::property_string;
+use crate::netlist::{Definitions, Instance, Net, Netlist, Path, PortRef};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use fxhash::{FxHashMap, FxHashSet};
+use serde_json::{json, Map, Value};
+use std::io;
+
+/// Converts `netlist` to a Yosys JSON netlist.
+pub fn to_json(netlist: &Netlist) -> Value {
+    let top = &netlist.top;
+    let defs = Definitions::new(top);
+    let mut modules = Map::new();
+    for (inst, name) in &defs.cells {
+        if !inst.is_leaf() || inst.path == top.path {
+            export_module(&defs, inst, name, inst.path == top.path, &mut modules);
+        }
+    }
+
+    json!({
+        "creator": concat!("edif-rs ", env!("CARGO_PKG_VERSION")),
+        "modules": modules,
+    })
+}
+
+/// Writes `netlist` as a Yosys JSON netlist.
+pub fn write_json<W: io::Write>(netlist: &Netlist, w: W) -> Result<()> {
+    serde_json::to_writer_pretty(w, &to_json(netlist))?;
+    Ok(())
+}
+
+/// Create a [`Netlist`](Netlist) from a string of a Yosys JSON netlist.
+pub fn from_str(s: &str) -> Result<Netlist> {
+    from_json(&serde_json::from_str(s)?)
+}
+
+/// Creates a [`Netlist`](Netlist) from a Yosys JSON netlist.
+///
+/// The top module is the one with a non-zero `top` attribute, or otherwise the only module
+/// that is not instantiated by another one.
+pub fn from_json(json: &Value) -> Result<Netlist> {
+    let modules = json
+        .get("modules")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("`modules` not found"))?;
+
+    let top = find_top(modules)?;
+    let path = Path::root(Atom::from(top));
+    let top = import_module(modules, &path, top, &Map::new(), &mut vec![])
+        .with_context(|| format!("importing module `{}`", top))?;
+
+    Ok(Netlist { top: Box::new(top) })
+}
+
+fn direction_str(dir: Direction) -> &'static str {
+    match dir {
+        Direction::Input => "input",
+        Direction::Output => "output",
+        Direction::InOut => "inout",
+    }
+}
+
+fn property_to_param(p: &Property) -> Value {
+    match p {
+        Property::String(s) => {
+            // Yosys marks strings that would otherwise be read as bit vectors with a
+            // trailing space.
+            if !s.is_empty() && s.chars().all(|c| "01xz".contains(c)) {
+                Value::from(format!("{} ", s))
+            } else {
+                Value::from(s.clone())
+            }
+        }
+        // A number, which Yosys reads as a 32-bit constant, is read back as an integer.
+        Property::Integer(i) => Value::from(*i),
+        Property::Number(n) => Value::from(n.to_string()),
+        Property::Boolean(b) => Value::from(if *b { "1" } else { "0" }),
+        Property::List(_) => Value::from(property_string(p)),
+    }
+}
+
+fn param_to_property(v: &Value) -> Result<Property> {
+    Ok(match v {
+        Value::Number(n) => Property::Integer(
+            n.as_i64()
+                .ok_or_else(|| anyhow!("non-integer parameter {}", n))?,
+        ),
+        Value::String(s)
+            if s.ends_with(' ') && s.trim_end().chars().all(|c| "01xz".contains(c)) =>
+        {
+            Property::String(s.trim_end().to_string())
+        }
+        // Bit vectors keep their width, as in the 16-bit `INIT` of a `LUT4`.
+        Value::String(s) if !s.is_empty() && s.chars().all(|c| "01xz".contains(c)) => {
+            Property::String(format!("{}'b{}", s.len(), s))
+        }
+        Value::String(s) => Property::String(s.clone()),
+        _ => bail!("unsupported parameter value {}", v),
+    })
+}
+
+/// Bit numbering of the nets in a module.
+struct Bits {
+    of_pin: FxHashMap<(Option<Atom>, Atom, Option<i32>), u64>,
+    next: u64,
+}
+
+impl Bits {
+    fn new(inst: &Instance) -> (Self, Vec<(Atom, u64)>) {
+        let mut bits = Bits {
+            of_pin: FxHashMap::default(),
+            next: 2,
+        };
+
+        let mut nets = inst.nets.iter().collect::<Vec<_>>();
+        nets.sort_by_key(|(name, _)| *name);
+
+        let mut netnames = Vec::with_capacity(nets.len());
+        for (name, net) in nets {
+            let bit = bits.fresh();
+            for p in &net.ports {
+                bits.of_pin.insert(Bits::key(inst, p), bit);
+            }
+            netnames.push((name.clone(), bit));
+        }
+
+        (bits, netnames)
+    }
+
+    fn key(parent: &Instance, p: &PortRef) -> (Option<Atom>, Atom, Option<i32>) {
+        let inst = if p.instance == parent.path {
+            None
+        } else {
+            Some(p.instance.name())
+        };
+        (inst, p.port.clone(), p.member)
+    }
+
+    fn fresh(&mut self) -> u64 {
+        self.next += 1;
+        self.next - 1
+    }
+
+    fn port(&mut self, inst: Option<&Atom>, port: &ast::Port) -> Vec<Value> {
+        (0..port.kind.width())
+            .map(|k| {
+                let key = (
+                    inst.cloned(),
+                    port.name.name.clone(),
+                    port.kind.member_of_bit(k),
+                );
+                let bit = match self.of_pin.get(&key) {
+                    Some(bit) => *bit,
+                    None => self.fresh(),
+                };
+                Value::from(bit)
+            })
+            .collect()
+    }
+}
+
+fn sorted_ports(inst: &Instance) -> Vec<&ast::Port> {
+    let mut ports = inst.interface.values().collect::<Vec<_>>();
+    ports.sort_by(|a, b| a.name.name.cmp(&b.name.name));
+    ports
+}
+
+fn export_module(
+    defs: &Definitions,
+    inst: &Instance,
+    name: &Atom,
+    is_top: bool,
+    modules: &mut Map<String, Value>,
+) {
+    let (mut bits, netnames) = Bits::new(inst);
+
+    let mut ports = Map::new();
+    for port in sorted_ports(inst) {
+        ports.insert(
+            port.name.name.to_string(),
+            json!({
+                "direction": direction_str(port.dir),
+                "bits": bits.port(None, port),
+            }),
+        );
+    }
+
+    let mut children = inst.instances.iter().collect::<Vec<_>>();
+    children.sort_by_key(|(name, _)| *name);
+
+    let mut cells = Map::new();
+    for (name, child) in children {
+        let mut parameters = Map::new();
+        for (k, v) in &child.properties {
+            parameters.insert(k.to_string(), property_to_param(v));
+        }
+
+        let mut port_directions = Map::new();
+        let mut connections = Map::new();
+        for port in sorted_ports(child) {
+            let port_name = port.name.name.to_string();
+            port_directions.insert(port_name.clone(), Value::from(direction_str(port.dir)));
+            connections.insert(port_name, Value::from(bits.port(Some(name), port)));
+        }
+
+        cells.insert(
+            name.to_string(),
+            json!({
+                "hide_name": 0,
+                "type": defs.name(child).as_ref(),
+                "parameters": parameters,
+                "attributes": {},
+                "port_directions": port_directions,
+                "connections": connections,
+            }),
+        );
+    }
+
+    let mut nets = Map::new();
+    for (name, bit) in netnames {
+        nets.insert(
+            name.to_string(),
+            json!({
+                "hide_name": 0,
+                "bits": [bit],
+                "attributes": {},
+            }),
+        );
+    }
+
+    let mut attributes = Map::new();
+    if is_top {
+        attributes.insert("top".to_string(), Value::from(format!("{:032b}", 1)));
+    }
+
+    modules.insert(
+        name.to_string(),
+        json!({
+            "attributes": attributes,
+            "ports": ports,
+            "cells": cells,
+            "netnames": nets,
+        }),
+    );
+}
+
+fn is_truthy(v: &Value) -> bool {
+    match v {
+        Value::Number(n) => n.as_u64().is_some_and(|n| n != 0),
+        Value::String(s) => s.chars().any(|c| c == '1'),
+        _ => false,
+    }
+}
+
+fn find_top(modules: &Map<String, Value>) -> Result<&str> {
+    let mut marked = modules
+        .iter()
+        .filter(|(_, m)| m.pointer("/attributes/top").is_some_and(is_truthy))
+        .map(|(name, _)| name.as_str());
+
+    if let Some(top) = marked.next() {
+        ensure!(
+            marked.next().is_none(),
+            "multiple modules are marked as top"
+        );
+        return Ok(top);
+    }
+
+    let instantiated = modules
+        .values()
+        .filter_map(|m| m.get("cells").and_then(Value::as_object))
+        .flat_map(|cells| cells.values())
+        .filter_map(|c| c.get("type").and_then(Value::as_str))
+        .collect::<FxHashSet<_>>();
+
+    let mut candidates = modules
+        .keys()
+        .map(String::as_str)
+        .filter(|m| !instantiated.contains(m));
+
+    match (candidates.next(), candidates.next()) {
+        (Some(top), None) => Ok(top),
+        (None, _) => bail!("no top module found"),
+        (Some(_), Some(_)) => bail!("cannot determine the top module"),
+    }
+}
+
+/// A connection of a bit in `connections` or `bits`.
+enum Bit {
+    Net(u64),
+    Const(bool),
+    Undef,
+}
+
+fn parse_bits(v: &Value) -> Result<Vec<Bit>> {
+    v.as_array()
+        .ok_or_else(|| anyhow!("expected an array of bits"))?
+        .iter()
+        .map(|b| {
+            Ok(match b {
+                Value::Number(n) => {
+                    Bit::Net(n.as_u64().ok_or_else(|| anyhow!("invalid bit {}", n))?)
+                }
+                Value::String(s) if s == "0" => Bit::Const(false),
+                Value::String(s) if s == "1" => Bit::Const(true),
+                Value::String(s) if s == "x" || s == "z" => Bit::Undef,
+                _ => bail!("invalid bit {}", b),
+            })
+        })
+        .collect()
+}
+
+fn port_kind(width: usize) -> PortKind {
+    if width == 1 {
+        PortKind::Single
+    } else {
+        PortKind::Array(width as i32)
+    }
+}
+
+fn parse_direction(v: Option<&Value>) -> Result<Direction> {
+    Ok(match v.and_then(Value::as_str) {
+        Some("input") => Direction::Input,
+        Some("output") => Direction::Output,
+        Some("inout") | None => Direction::InOut,
+        Some(other) => bail!("unknown direction `{}`", other),
+    })
+}
+
+/// Looks up an object member of `v`, treating a missing member as an empty object.
+fn as_object<'v>(
+    v: &'v Value,
+    key: &str,
+    empty: &'v Map<String, Value>,
+) -> Result<&'v Map<String, Value>> {
+    match v.get(key) {
+        Some(v) => v
+            .as_object()
+            .ok_or_else(|| anyhow!("`{}` is not an object", key)),
+        None => Ok(empty),
+    }
+}
+
+/// Pins connected to each bit, and the constant drivers required by a module.
+#[derive(Default)]
+struct Connections {
+    bits: FxHashMap<u64, Vec<PortRef>>,
+    consts: [Vec<PortRef>; 2],
+}
+
+impl Connections {
+    fn add(&mut self, instance: &Path, port: &Atom, width: usize, bits: Vec<Bit>) {
+        let kind = port_kind(width);
+        for (k, bit) in bits.into_iter().enumerate() {
+            let pin = PortRef {
+                instance: instance.clone(),
+                port: port.clone(),
+                member: kind.member_of_bit(k as i32),
+            };
+            match bit {
+                Bit::Net(b) => self.bits.entry(b).or_default().push(pin),
+                Bit::Const(c) => self.consts[c as usize].push(pin),
+                Bit::Undef => {}
+            }
+        }
+    }
+}
+
+/// Imports the module `name`, instantiated in the modules of `ancestors`.
+fn import_module<'m>(
+    modules: &'m Map<String, Value>,
+    path: &Path,
+    name: &'m str,
+    parameters: &Map<String, Value>,
+    ancestors: &mut Vec<&'m str>,
+) -> Result<Instance> {
+    ensure!(
+        !ancestors.contains(&name),
+        "module `{}` instantiates itself",
+        name
+    );
+    let module = &modules[name];
+    let empty = Map::new();
+    let mut conns = Connections::default();
+
+    let mut interface = FxIndexMap::default();
+    for (port_name, port) in as_object(module, "ports", &empty)? {
+        let port_name = Atom::from(port_name.as_str());
+        let bits = parse_bits(port.get("bits").unwrap_or(&Value::Null))
+            .with_context(|| format!("port `{}`", port_name))?;
+        let width = bits.len();
+        conns.add(path, &port_name, width, bits);
+        interface.insert(
+            port_name.clone(),
+            ast::Port {
+                kind: port_kind(width),
+                dir: parse_direction(port.get("direction"))?,
+                name: ast::Name {
+                    name: port_name,
+                    rename_from: None,
+                },
+                span: Default::default(),
+            },
+        );
+    }
+
+    let mut instances = FxIndexMap::default();
+    for (cell_name, cell) in as_object(module, "cells", &empty)? {
+        let cell_name = Atom::from(cell_name.as_str());
+        let ty = cell
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("cell `{}` has no type", cell_name))?;
+        let cell_path = path.child(cell_name.clone());
+        let cell_params = as_object(cell, "parameters", &empty)?;
+
+        let mut inst = if let Some((ty, _)) = modules.get_key_value(ty) {
+            ancestors.push(name);
+            let inst = import_module(modules, &cell_path, ty, cell_params, ancestors)
+                .with_context(|| format!("importing module `{}`", ty));
+            ancestors.pop();
+            inst?
+        } else {
+            leaf_instance(cell_path.clone(), atom!("hdi_primitives"), ty, cell_params)?
+        };
+
+        let directions = as_object(cell, "port_directions", &empty)?;
+        for (port_name, bits) in as_object(cell, "connections", &empty)? {
+            let port_name = Atom::from(port_name.as_str());
+            let bits = parse_bits(bits)
+                .with_context(|| format!("connection `{}` of `{}`", port_name, cell_name))?;
+            let width = bits.len();
+            if !inst.interface.contains_key(&port_name) {
+                inst.interface.insert(
+                    port_name.clone(),
+                    ast::Port {
+                        kind: port_kind(width),
+                        dir: parse_direction(directions.get(port_name.as_ref()))?,
+                        name: ast::Name {
+                            name: port_name.clone(),
+                            rename_from: None,
+                        },
+                        span: Default::default(),
+                    },
+                );
+            }
+            conns.add(&cell_path, &port_name, width, bits);
+        }
+
+        instances.insert(cell_name, inst);
+    }
+
+    let nets = build_nets(module, path, conns, &mut instances)?;
+
+    let mut inst = leaf_instance(path.clone(), atom!("work"), name, parameters)?;
+    inst.interface = interface;
+    inst.instances = instances;
+    inst.nets = nets;
+    Ok(inst)
+}
+
+fn leaf_instance(
+    path: Path,
+    lib: Atom,
+    cell: &str,
+    parameters: &Map<String, Value>,
+) -> Result<Instance> {
+    Ok(Instance {
+        path,
+        instances: FxIndexMap::default(),
+        nets: FxIndexMap::default(),
+        interface: FxIndexMap::default(),
+        lib,
+        cell: Atom::from(cell),
+        properties: parameters
+            .iter()
+            .map(|(k, v)| {
+                let p = param_to_property(v).with_context(|| format!("parameter `{}`", k))?;
+                Ok((Atom::from(k.as_str()), p))
+            })
+            .collect::<Result<_>>()?,
+        property_spans: FxIndexMap::default(),
+        rename_from: None,
+        black_box: false,
+        span: Default::default(),
+    })
+}
+
+/// Names each bit after a net name covering it, preferring visible and shorter names.
+fn bit_names(module: &Value) -> Result<FxHashMap<u64, Atom>> {
+    let empty = Map::new();
+    let mut netnames = as_object(module, "netnames", &empty)?
+        .iter()
+        .map(|(name, n)| {
+            let hidden = n.get("hide_name").is_some_and(is_truthy);
+            (hidden, name, n)
+        })
+        .collect::<Vec<_>>();
+    netnames.sort_by(|a, b| (a.0, a.1.len(), a.1).cmp(&(b.0, b.1.len(), b.1)));
+
+    let mut names = FxHashMap::default();
+    let mut used = FxHashSet::default();
+    for (_, name, n) in netnames {
+        let bits = parse_bits(n.get("bits").unwrap_or(&Value::Null))
+            .with_context(|| format!("netname `{}`", name))?;
+        let width = bits.len();
+        for (k, bit) in bits.into_iter().enumerate() {
+            if let Bit::Net(b) = bit {
+                if names.contains_key(&b) {
+                    continue;
+                }
+                let mut n = if width == 1 {
+                    name.clone()
+                } else {
+                    format!("{}[{}]", name, k)
+                };
+                if !used.insert(n.clone()) {
+                    n = format!("{}_{}", n, b);
+                    used.insert(n.clone());
+                }
+                names.insert(b, Atom::from(n));
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+fn build_nets(
+    module: &Value,
+    path: &Path,
+    conns: Connections,
+    instances: &mut FxIndexMap<Atom, Instance>,
+) -> Result<FxIndexMap<Atom, Net>> {
+    let mut names = bit_names(module)?;
+    let mut nets = FxIndexMap::default();
+
+    let mut bits = conns.bits.into_iter().collect::<Vec<_>>();
+    bits.sort_by_key(|(b, _)| *b);
+    for (bit, ports) in bits {
+        let name = names
+            .remove(&bit)
+            .unwrap_or_else(|| Atom::from(format!("$bit{}", bit)));
+        nets.insert(
+            name,
+            Net {
+                ports: ports.into_iter().collect(),
+                rename_from: None,
+                span: Default::default(),
+            },
+        );
+    }
+
+    // Constant bits are driven by `GND` and `VCC` cells as Vivado does.
+    let drivers = [
+        (Atom::from("GND"), Atom::from("G"), Atom::from("<const0>")),
+        (Atom::from("VCC"), Atom::from("P"), Atom::from("<const1>")),
+    ];
+    for ((cell, port, net_name), pins) in drivers.iter().zip(conns.consts.iter()) {
+        if pins.is_empty() {
+            continue;
+        }
+
+        let mut inst_name = cell.clone();
+        let mut i = 0;
+        while instances.contains_key(&inst_name) {
+            i += 1;
+            inst_name = Atom::from(format!("{}_{}", cell, i));
+        }
+        ensure!(
+            !nets.contains_key(net_name),
+            "net name `{}` is reserved for constants",
+            net_name
+        );
+
+        let inst_path = path.child(inst_name.clone());
+        let mut driver = leaf_instance(
+            inst_path.clone(),
+            atom!("hdi_primitives"),
+            cell,
+            &Map::new(),
+        )?;
+        driver.interface.insert(
+            port.clone(),
+            ast::Port {
+                kind: PortKind::Single,
+                dir: Direction::Output,
+                name: ast::Name {
+                    name: port.clone(),
+                    rename_from: None,
+                },
+                span: Default::default(),
+            },
+        );
+        instances.insert(inst_name, driver);
+
+        let mut ports = pins.iter().cloned().collect::<FxIndexSet<_>>();
+        ports.insert(PortRef {
+            instance: inst_path,
+            port: port.clone(),
+            member: None,
+        });
+        nets.insert(
+            net_name.clone(),
+            Net {
+                ports,
+                rename_from: None,
+                span: Default::default(),
+            },
+        );
+    }
+
+    Ok(nets)
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use edif::{blif, netlist};
+use std::fs;
+
+#[test]
+fn write_blif() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let n = netlist::from_str(&s)?;
+
+    let mut out = vec![];
+    blif::write_blif(&n, &mut out)?;
+    let out = String::from_utf8(out)?;
+
+    assert!(out.starts_with(".model main\n.inputs a clk rst\n.outputs ret[0] ret[1]\n"));
+    assert!(out.contains(".subckt inner SR_0_=rst_IBUF a_IBUF=a_IBUF"));
+    // `x[0]_i_1` is a LUT1 with INIT 2'h1, i.e. an inverter.
+    assert!(out.contains(".names a_IBUF p_9_out_10_\n0 1\n"));
+    assert!(out.contains(".latch x_reg_0_$next x_reg_n_0__0_ re clk 0\n"));
+    assert_eq!(out.matches(".latch").count(), 11);
+    assert_eq!(out.matches(".model").count(), 2);
+
+    let dangling = netlist::from_str(&s.replace("(portref a)))", "(portref NOPE)))"))?;
+    let err = blif::write_blif(&dangling, &mut vec![]).unwrap_err();
+    assert!(err.to_string().contains("main/NOPE"), "{}", err);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use edif::ast::{Content, Direction::*, Property};
+use edif::builder::{EdifBuilder, Pin};
+use edif::netlist::{self, Netlist};
+use edif::{iso, writer, Atom};
+
+fn primitives() -> EdifBuilder {
+    EdifBuilder::new("top")
+        .external("hdi_primitives")
+        .cell("LUT2")
+        .port("I0", Input, 1)
+        .port("I1", Input, 1)
+        .port("O", Output, 1)
+        .library("work")
+        .cell("top")
+        .port("a", Input, 2)
+        .port("b", Input, 2)
+        .port("y", Output, 2)
+}
+
+#[test]
+fn build() -> Result<()> {
+    let mut b = primitives();
+    for i in 0..2 {
+        let lut = format!("y_{}_i", i);
+        b = b
+            .instance_of(&lut, "hdi_primitives", "LUT2")
+            .property("INIT", Property::String("4'h6".to_string()))
+            .net(
+                &format!("a_{}", i),
+                [Pin::port("a").bit(i), Pin::of(&lut, "I0")],
+            )
+            .net(
+                &format!("b_{}", i),
+                [Pin::port("b").bit(i), Pin::of(&lut, "I1")],
+            )
+            .net(
+                &format!("y_{}", i),
+                [Pin::of(&lut, "O"), Pin::port("y").bit(i)],
+            );
+    }
+    let edif = b
+        .instance_of("x_reg[0]", "hdi_primitives", "LUT2")
+        .net("x[0]", [Pin::of("x_reg[0]", "O")])
+        .build()?;
+    let top = &edif.libs[&Atom::from("work")].cells[&Atom::from("top")];
+    assert!(top.view.contents.iter().any(|c| match c {
+        Content::Net(net) =>
+            &*net.name.name == "x_0_"
+                && net.name.rename_from.as_deref() == Some("x[0]")
+                && net.portrefs[0].instance_ref == Some(Atom::from("x_reg_0_")),
+        Content::Instance(_) => false,
+    }));
+    let n = Netlist::from_ast(&edif);
+    n.verify_references()?;
+    assert_eq!(n.top.instances.len(), 3);
+    let lut = &n.top.instances[&Atom::from("y_1_i")];
+    assert!(lut.black_box);
+    assert!(matches!(&lut.properties[&Atom::from("INIT")], Property::String(s) if s == "4'h6"));
+
+    let mut out = vec![];
+    writer::write_edif(&n, &mut out)?;
+    let new = netlist::from_str(std::str::from_utf8(&out)?)?;
+    let report = iso::compare(&n, &new);
+    assert!(report.is_isomorphic(), "{}", report);
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    let err = |b: EdifBuilder| format!("{:#}", b.build().unwrap_err());
+    assert!(err(primitives().instance("x", "LUT3")).contains("`work/LUT3`"));
+    assert!(err(primitives().port("a", Input, 1)).contains("port `a` already exists"));
+    let bad_bit = primitives()
+        .instance_of("x", "hdi_primitives", "LUT2")
+        .net("n", [Pin::port("a").bit(2), Pin::of("x", "I0")]);
+    assert!(err(bad_bit).contains("bit 2 of port `a`"));
+    let twice = primitives()
+        .instance_of("x", "hdi_primitives", "LUT2")
+        .net("n", [Pin::port("a").bit(0), Pin::of("x", "I0")])
+        .net("m", [Pin::port("b").bit(0), Pin::of("x", "I0")]);
+    assert!(err(twice).contains("already connected to net `n`"));
+    assert!(err(primitives().cell("LUT[2]")).contains("`LUT[2]` is not an EDIF identifier"));
+    assert!(err(EdifBuilder::new("top").library("work")).contains("`top` is not defined"));
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use edif::ast::{Direction, Name, Port, PortKind};
+use edif::netlist;
+use edif::{cache, iso, Atom};
+use std::fs;
+
+#[test]
+fn snapshot() -> Result<()> {
+    let path = format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR"));
+    let s = fs::read_to_string(&path)?;
+    let hash = cache::source_hash(s.as_bytes());
+
+    let old = netlist::from_str(&s)?;
+    let mut bytes = vec![];
+    cache::save(&old, hash, &mut bytes)?;
+    assert_eq!(cache::header(&bytes)?.source_hash, hash);
+    assert!(bytes.len() < s.len() / 3);
+    let new = cache::load(&bytes)?;
+    new.verify_references()?;
+    assert!(iso::compare(&old, &new).is_isomorphic());
+    let inner = Atom::from("inner");
+    assert_eq!(
+        new.top.instances[&inner].instances[&Atom::from("x_reg_3_")].property_spans,
+        old.top.instances[&inner].instances[&Atom::from("x_reg_3_")].property_spans,
+    );
+    let mut again = vec![];
+    cache::save(&new, hash, &mut again)?;
+    assert_eq!(again, bytes);
+    assert!(cache::load(&bytes[..bytes.len() - 1]).is_err());
+    // Corrupt bytes give errors, or another netlist, but never a panic.
+    for i in 0..bytes.len() {
+        let mut corrupt = bytes.clone();
+        corrupt[i] = 0xff;
+        let _ = cache::load(&corrupt);
+    }
+
+    // A port too wide for the netlist is an error.
+    let mut wide = netlist::from_str(&s)?;
+    wide.top.interface.insert(
+        Atom::from("wide"),
+        Port {
+            kind: PortKind::Array(i32::MAX),
+            dir: Direction::Input,
+            name: Name {
+                name: Atom::from("wide"),
+                rename_from: None,
+            },
+            span: Default::default(),
+        },
+    );
+    let mut bytes = vec![];
+    cache::save(&wide, hash, &mut bytes)?;
+    assert!(cache::load(&bytes).is_ok());
+    // The width plus one is the varint of 2^31; make it 2^32.
+    let at = bytes
+        .windows(5)
+        .position(|w| w == [0x80, 0x80, 0x80, 0x80, 0x08])
+        .unwrap();
+    bytes[at + 4] = 0x10;
+    let err = cache::load(&bytes).unwrap_err();
+    assert!(err.to_string().contains("port width"), "{}", err);
+
+    // The flattened netlist, with names containing `/`.
+    let mut flat = netlist::from_str(&s)?;
+    flat.flatten();
+    let mut bytes = vec![];
+    cache::save(&flat, hash + 1, &mut bytes)?;
+    let new = cache::load(&bytes)?;
+    new.verify_references()?;
+    assert!(iso::compare(&old, &new).is_isomorphic());
+
+    // A stale snapshot is replaced.
+    let dir = std::env::temp_dir().join(format!("edif-cache-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let snapshot = dir.join("test.edfnl");
+    fs::write(&snapshot, &bytes)?;
+    let n = cache::load_or_parse(&path, &snapshot)?;
+    assert_eq!(n.top.instances.len(), old.top.instances.len());
+    let written = fs::read(&snapshot)?;
+    assert_eq!(cache::header(&written)?.source_hash, hash);
+    assert!(cache::load(&written)?
+        .top
+        .instances
+        .contains_key(&Atom::from("inner")));
+
+    #[cfg(feature = "mmap")]
+    {
+        use edif::ast::Property;
+        use edif::netlist::Path;
+
+        let mapped = cache::Mapped::open(&snapshot)?;
+        assert_eq!(mapped.header().version, cache::VERSION);
+        assert!(mapped.strings()?.contains(&"FDRE"));
+        assert!(iso::compare(&old, &mapped.netlist()?).is_isomorphic());
+
+        // The views agree with the loaded netlist.
+        let top = mapped.top()?;
+        assert_eq!(
+            (top.name(), top.lib(), top.cell()),
+            ("main", "work", "main")
+        );
+        let names = top
+            .instances()?
+            .iter()
+            .map(|i| i.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            n.top.instances.keys().map(|k| &**k).collect::<Vec<_>>()
+        );
+        let inner = Path::root(Atom::from("main")).child(Atom::from("inner"));
+        let view = mapped.instance(&inner)?.unwrap();
+        let nets = view.nets()?;
+        assert_eq!(nets.len(), n.instance(&inner).unwrap().nets.len());
+        let b = nets.iter().find(|net| net.name == "b").unwrap();
+        assert!(b.pins.contains(&cache::MappedPin {
+            instance: Some("x_reg_10_"),
+            port: "Q",
+            member: None,
+        }));
+        let ff = view.instance("x_reg_3_")?.unwrap();
+        assert_eq!(ff.cell(), "FDRE");
+        assert_eq!(
+            ff.property("INIT")?,
+            Some(Property::String("1'b0".to_string()))
+        );
+        assert!(mapped.instance(&inner.child(Atom::from("nope")))?.is_none());
+    }
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "cli")]
+
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+
+fn edif(args: &[&str]) -> Result<(i32, String)> {
+    let out = Command::new(env!("CARGO_BIN_EXE_edif"))
+        .args(args)
+        .output()?;
+    Ok((out.status.code().unwrap(), String::from_utf8(out.stdout)?))
+}
+
+#[test]
+fn cli() -> Result<()> {
+    let input = format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR"));
+    let s = fs::read_to_string(&input)?;
+    let dir = std::env::temp_dir().join(format!("edif-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let gz = dir.join("test.edf.gz");
+    let mut e = GzEncoder::new(fs::File::create(&gz)?, Compression::default());
+    e.write_all(s.as_bytes())?;
+    e.finish()?;
+    let gz = gz.to_str().unwrap();
+
+    let (code, out) = edif(&["stats", "--json", gz])?;
+    assert_eq!(code, 0);
+    let stats: serde_json::Value = serde_json::from_str(&out)?;
+    assert_eq!(stats["top"], "main");
+    assert_eq!(stats["leaf_instances"], 21);
+
+    assert_eq!(edif(&["diff", &input, gz])?.0, 0);
+    let changed = dir.join("changed.edf");
+    fs::write(
+        &changed,
+        s.replacen("(string \"2'h1\")", "(string \"2'h2\")", 1),
+    )?;
+    let (code, out) = edif(&["diff", &input, changed.to_str().unwrap()])?;
+    assert_eq!(code, 1);
+    assert!(out.contains("INIT"), "{}", out);
+
+    let (code, out) = edif(&["convert", "--to", "verilog", &input])?;
+    assert_eq!(code, 0);
+    assert!(out.contains("module main ("));
+    assert_eq!(edif(&["query", "main/nothing*", &input])?.0, 1);
+
+    let dangling = dir.join("dangling.edf");
+    fs::write(
+        &dangling,
+        s.replace("(instanceref GND)", "(instanceref GNDX)"),
+    )?;
+    for to in ["edif", "verilog", "blif"] {
+        let args = ["convert", "--to", to, dangling.to_str().unwrap()];
+        assert_eq!(edif(&args)?.0, 2, "{}", to);
+    }
+    assert_eq!(
+        edif(&["verify", dir.join("missing.edf").to_str().unwrap()])?.0,
+        2
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
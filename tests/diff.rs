@@ -0,0 +1,51 @@
+use anyhow::Result;
+use edif::ast::Property;
+use edif::diff::{self, Change, DiffOptions};
+use edif::netlist::{self, Path};
+use edif::Atom;
+use std::fs;
+
+#[test]
+fn structural_diff() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let old = netlist::from_str(&s)?;
+    assert!(diff::diff(&old, &old, &DiffOptions::default()).is_empty());
+
+    // Change the inverter into a buffer, and renumber a generated net.
+    let inner = Path::root(Atom::from("main")).child(Atom::from("inner"));
+    let lut = inner.child(Atom::from("x_0__i_1"));
+    let mut new = netlist::from_str(&s)?;
+    new.edit(|tx| {
+        let init = Property::String("2'h2".into());
+        tx.set_property(&lut, "INIT", Some(init))?;
+        tx.rename(&inner.child(Atom::from("p_9_out_10_")), "p_8_out_10_")
+    })?;
+
+    let d = diff::diff(&old, &new, &DiffOptions::default());
+    assert_eq!(d.changes.len(), 3, "{}", d);
+    let span = old.top.get(&lut).unwrap().property_spans[&Atom::from("INIT")];
+    assert_eq!(&s[span.start..span.end], "(property INIT(string \"2'h1\"))");
+    assert!(d.changes.contains(&Change::PropertyChanged {
+        path: lut,
+        name: Atom::from("INIT"),
+        from: Some("2'h1".into()),
+        to: Some("2'h2".into()),
+        span,
+    }));
+    assert!(d.to_string().contains("- net main/inner/p_9_out_10_"));
+    let removed = d.changes.iter().find_map(|c| match c {
+        Change::NetRemoved { span, .. } => Some(*span),
+        _ => None,
+    });
+    assert!(s[removed.unwrap().start..].starts_with("(net(rename p_9_out_10_"));
+
+    let options = DiffOptions {
+        ignore_generated_names: true,
+    };
+    let d = diff::diff(&old, &new, &options);
+    assert_eq!(d.changes.len(), 1, "{}", d);
+    assert_eq!(d.to_json()[0]["kind"], "property_changed");
+    assert_eq!(d.to_json()[0]["column"], span.column);
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use edif::dot::{self, DotMode, DotOptions};
+use edif::netlist;
+use std::fs;
+
+#[test]
+fn dot() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let n = netlist::from_str(&s)?;
+
+    let hier = dot::to_dot(
+        &n,
+        &DotOptions {
+            cells: vec!["FDRE".into()],
+            ..DotOptions::default()
+        },
+    )?;
+    assert!(hier.contains("label=\"inner\\ninner\";"));
+    assert_eq!(hier.matches("FDRE\"]").count(), 11);
+    assert!(!hier.contains("IBUF"));
+
+    let conn = dot::to_dot(
+        &n,
+        &DotOptions {
+            mode: DotMode::Connectivity,
+            max_depth: Some(1),
+            hub_fanout: Some(8),
+            ..DotOptions::default()
+        },
+    )?;
+    // `inner` is drawn as a single node, so the clock net has few pins.
+    assert!(conn.contains("[label=\"{{<SR_0_>SR_0_|<a_IBUF>a_IBUF|<clk>clk}|inner\\ninner|{<ret_OBUF_0_>ret_OBUF_0_}}\"]"));
+    assert!(!conn.contains("x_reg"));
+    assert!(conn.contains("[shape=diamond, label=\"main/&_const0_\"]"));
+
+    let inner = dot::to_dot(
+        &n,
+        &DotOptions {
+            mode: DotMode::Connectivity,
+            path_prefix: Some("main/inner".into()),
+            hub_fanout: Some(8),
+            ..DotOptions::default()
+        },
+    )?;
+    assert!(inner.contains("[shape=diamond, label=\"main/inner/clk\"]"));
+    assert!(!inner.contains("IBUF_inst"));
+
+    // A dangling pin is an error.
+    let n = netlist::from_str(&s.replace(
+        "(portref CE(instanceref x_reg_0_))",
+        "(portref CEX(instanceref x_reg_0_))",
+    ))?;
+    let err = dot::to_dot(
+        &n,
+        &DotOptions {
+            mode: DotMode::Connectivity,
+            ..DotOptions::default()
+        },
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("main/inner/x_reg_0_/CEX"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
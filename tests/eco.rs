@@ -0,0 +1,116 @@
+use anyhow::Result;
+use edif::ast::Property;
+use edif::netlist::{self, Path, PortRef};
+use edif::sim::Simulator;
+use edif::{yosys, Atom};
+use std::fs;
+
+#[test]
+fn insert_buffer() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let mut n = netlist::from_str(&s)?;
+    let inner = Path::root(Atom::from("main")).child(Atom::from("inner"));
+    let ff = inner.child(Atom::from("x_reg_1_"));
+
+    // Insert a LUT1 buffer in front of `x_reg[1]`.
+    n.edit(|tx| {
+        let init = Property::String("2'h2".into());
+        let lut = tx.add_instance(
+            &inner,
+            "eco_i",
+            "hdi_primitives",
+            "LUT1",
+            vec![(Atom::from("INIT"), init)],
+        )?;
+        let net = tx.create_net(&inner, "eco")?;
+        let old = inner.child(Atom::from("x_reg_n_0__0_"));
+        tx.disconnect(&tx.pin(&ff, "D"), &old)?;
+        tx.connect(&tx.pin(&lut, "I0"), &old)?;
+        tx.connect(&tx.pin(&lut, "O"), &net)?;
+        tx.connect(&tx.pin(&ff, "D"), &net)?;
+
+        assert!(tx.connect(&tx.pin(&lut, "I1"), &net).is_err());
+        assert!(tx.connect(&tx.pin(&lut, "O"), &old).is_err());
+        assert!(tx.set_property(&lut, "FOO", None).is_err());
+        assert!(tx
+            .add_instance(&inner, "eco_i", "hdi_primitives", "LUT1", vec![])
+            .is_err());
+        Ok(())
+    })?;
+    n.verify_references()?;
+    assert_eq!(n.instance(&inner).unwrap().instances.len(), 15);
+
+    // The shift register still works.
+    let mut sim = Simulator::new(&n)?;
+    sim.set_input("rst", 0)?;
+    sim.set_input("a", 0)?;
+    for cycle in 1..=11 {
+        sim.step();
+        assert_eq!(sim.port("ret")?, (cycle < 11) as u64, "cycle {}", cycle);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rollback() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let mut n = netlist::from_str(&s)?;
+    let before = yosys::to_json(&n);
+    let main = Path::root(Atom::from("main"));
+
+    let res: Result<()> = n.edit(|tx| {
+        let inner = tx.rename(&main.child(Atom::from("inner")), "core")?;
+        let ff = inner.child(Atom::from("x_reg_3_"));
+        tx.set_property(&ff, "INIT", Some(Property::String("1'b1".into())))?;
+        tx.remove_instance(&inner.child(Atom::from("x_reg_4_")))?;
+        tx.add_instance(&main, "inner2", "work", "inner", vec![])?;
+        tx.disconnect(
+            &PortRef {
+                instance: main.clone(),
+                port: Atom::from("ret"),
+                member: Some(0),
+            },
+            &main.child(Atom::from("ret_1_")),
+        )?;
+        tx.netlist().verify_references()?;
+        anyhow::bail!("abort");
+    });
+    assert!(res.is_err());
+
+    n.verify_references()?;
+    assert_eq!(yosys::to_json(&n), before);
+
+    Ok(())
+}
+
+#[test]
+fn hierarchical_ports() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let mut n = netlist::from_str(&s)?;
+    let main = Path::root(Atom::from("main"));
+    let inner = main.child(Atom::from("inner"));
+    let pin = PortRef {
+        instance: inner.clone(),
+        port: Atom::from("a_IBUF"),
+        member: None,
+    };
+
+    // The port of `inner` is disconnected inside `inner` only.
+    n.edit(|tx| {
+        assert!(tx.disconnect(&pin, &main.child(Atom::from("clk"))).is_err());
+        tx.disconnect(&pin, &inner.child(Atom::from("a_IBUF")))?;
+        tx.connect(&pin, &inner.child(Atom::from("b")))
+    })?;
+    n.verify_references()?;
+    let outer = &n.top.nets[&Atom::from("a_IBUF")];
+    assert!(outer.ports.contains(&pin));
+
+    // A name shared by a net and an instance is ambiguous.
+    n.edit(|tx| tx.create_net(&main, "GND").map(drop))?;
+    assert!(n
+        .edit(|tx| tx.rename(&main.child(Atom::from("GND")), "GND2"))
+        .is_err());
+
+    Ok(())
+}
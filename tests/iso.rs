@@ -0,0 +1,84 @@
+use anyhow::Result;
+use edif::ast::{Direction::*, Property};
+use edif::builder::{EdifBuilder, Pin};
+use edif::iso::{self, IsoOptions};
+use edif::netlist::{self, Netlist, Path};
+use edif::Atom;
+use std::fs;
+
+#[test]
+fn isomorphism() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let old = netlist::from_str(&s)?;
+    let mut new = netlist::from_str(&s)?;
+
+    // Renaming preserves the structure.
+    let inner = Path::root(Atom::from("main")).child(Atom::from("inner"));
+    let lut = inner.child(Atom::from("x_0__i_1"));
+    new.edit(|tx| {
+        tx.rename(&inner.child(Atom::from("p_9_out_10_")), "p_12_out_10_")?;
+        tx.rename(&inner.child(Atom::from("x_reg_3_")), "y_reg")?;
+        Ok(())
+    })?;
+    let report = iso::compare(&old, &new);
+    assert!(report.is_isomorphic(), "{}", report);
+    assert!(report.instances.contains(&(
+        inner.child(Atom::from("x_reg_3_")),
+        inner.child(Atom::from("y_reg"))
+    )));
+    assert_eq!(report.nets.len(), old.global_nets().len());
+
+    // Turn the first inverter into a buffer.
+    new.edit(|tx| tx.set_property(&lut, "INIT", Some(Property::String("2'h2".into()))))?;
+    let report = iso::compare(&old, &new);
+    let mismatch = report.mismatch.expect("not isomorphic");
+    assert_eq!(mismatch.radius, 0);
+    assert_eq!(mismatch.left_instances, [lut]);
+    assert_eq!(mismatch.right_instances, mismatch.left_instances);
+
+    Ok(())
+}
+
+/// Rings of LUT1 buffers, of the given lengths.
+fn rings(lens: &[usize]) -> Result<Netlist> {
+    let mut b = EdifBuilder::new("top")
+        .external("hdi_primitives")
+        .cell("LUT1")
+        .port("I0", Input, 1)
+        .port("O", Output, 1)
+        .library("work")
+        .cell("top");
+    for (r, &len) in lens.iter().enumerate() {
+        for i in 0..len {
+            b = b
+                .instance_of(&format!("b{}_{}", r, i), "hdi_primitives", "LUT1")
+                .property("INIT", Property::String("2'h2".into()));
+        }
+        for i in 0..len {
+            b = b.net(
+                &format!("n{}_{}", r, i),
+                [
+                    Pin::of(&format!("b{}_{}", r, i), "O"),
+                    Pin::of(&format!("b{}_{}", r, (i + 1) % len), "I0"),
+                ],
+            );
+        }
+    }
+    Ok(Netlist::from_ast(&b.build()?))
+}
+
+#[test]
+fn backtracking() -> Result<()> {
+    // The refinement cannot tell one ring of 6 from two rings of 3, but the search can.
+    let (six, threes) = (rings(&[6])?, rings(&[3, 3])?);
+    let report = iso::compare(&six, &threes);
+    assert!(report.mismatch.is_some() && !report.undecided, "{}", report);
+    assert!(iso::compare(&six, &rings(&[6])?).is_isomorphic());
+
+    // Without backtracking, the search gives up.
+    let report = iso::compare_with(&six, &threes, &IsoOptions { max_backtracks: 0 });
+    assert!(report.undecided && !report.is_isomorphic(), "{}", report);
+    assert!(report.mismatch.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use edif::ast::Direction;
+use edif::parser::EdifParser;
+use edif::{iso, link, netlist};
+use std::fs;
+
+/// Splits the test design into a top level with a stub of `inner`, and `inner` in library
+/// `ip`.
+fn split(s: &str) -> (String, String) {
+    let prims = s.find("(Library hdi_primitives").unwrap();
+    let work = s.find("(Library work").unwrap();
+    let inner = s.find("(cell inner").unwrap();
+    let main = s.find("(cell main").unwrap();
+    let contents = inner + s[inner..].find("(contents").unwrap();
+
+    let top = format!("{})){}", &s[..contents], &s[main..]);
+    let ip = format!(
+        "{}{}(Library ip(edifLevel 0)(technology(numberDefinition )){})(design inner(cellref inner(libraryref ip))))",
+        &s[..prims].replace("(edif main", "(edif inner"),
+        &s[prims..work],
+        &s[inner..main],
+    );
+    (top, ip)
+}
+
+#[test]
+fn link_stub() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let (top, ip) = split(&s);
+
+    let n = link::link_netlist(vec![
+        EdifParser::parse_from_str(&top)?,
+        EdifParser::parse_from_str(&ip)?,
+    ])?;
+    n.verify_references()?;
+    assert!(iso::compare(&netlist::from_str(&s)?, &n).is_isomorphic());
+    assert!(n.black_boxes().is_empty());
+
+    // Unlinked, the stub is a black box with the ports it declares.
+    let stub = netlist::from_str(&top)?;
+    let boxes = stub.black_boxes();
+    assert_eq!(boxes.len(), 1);
+    assert_eq!(&*boxes[0].cell, "inner");
+    assert_eq!(boxes[0].interface.len(), 4);
+    assert!(boxes[0]
+        .interface
+        .values()
+        .all(|p| p.dir != Direction::InOut));
+
+    // The stub must match the definition.
+    let ip = ip.replace(
+        "(port a_IBUF(direction INPUT))",
+        "(port a(direction INPUT))",
+    );
+    let err = link::link(vec![
+        EdifParser::parse_from_str(&top)?,
+        EdifParser::parse_from_str(&ip)?,
+    ])
+    .unwrap_err();
+    assert!(format!("{:#}", err).contains("port `a_IBUF`"), "{:#}", err);
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use edif::literal::BitVec;
+use edif::lut::{LutFunction, TruthTable};
+use edif::netlist;
+use std::fs;
+
+#[test]
+fn lut_functions() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let n = netlist::from_str(&s)?;
+
+    let mut luts = 0;
+    for inst in n.top.walk() {
+        if let Some(table) = inst.truth_table()? {
+            // Both LUT1s of the design have `INIT = 2'h1`.
+            assert_eq!(table.classify(), LutFunction::Inverter(0));
+            assert_eq!(table.to_sop().to_string(), "!I0");
+            luts += 1;
+        }
+    }
+    assert_eq!(luts, 2);
+
+    Ok(())
+}
+
+#[test]
+fn truth_tables() -> Result<()> {
+    let init = "64'hFFFF_0000_FFFF_0000".parse::<BitVec>()?;
+    assert_eq!(init.to_u64(), Some(0xFFFF_0000_FFFF_0000));
+    assert_eq!("4'b1010".parse::<BitVec>()?.to_u64(), Some(0b1010));
+    assert_eq!("8'd200".parse::<BitVec>()?.to_string(), "8'hC8");
+    assert!("4'bx0x0".parse::<BitVec>().is_err());
+    assert!("4294967295'h0".parse::<BitVec>().is_err());
+
+    // O = I4
+    let buf = TruthTable::from_init(6, &init)?;
+    assert_eq!(buf.classify(), LutFunction::Buffer(4));
+    assert_eq!(buf.support(), vec![4]);
+
+    // O = I0 & I1 | I2
+    let t = TruthTable::new(3, 0xF8);
+    assert_eq!(t.classify(), LutFunction::Other);
+    let sop = t.to_sop();
+    assert_eq!(sop.to_string(), "I0 & I1 | I2");
+    let bdd = t.to_bdd();
+    assert_eq!(bdd.nodes().len(), 3);
+    for m in 0..8 {
+        let inputs = (0..3).map(|i| m >> i & 1 == 1).collect::<Vec<_>>();
+        let expected = inputs[0] && inputs[1] || inputs[2];
+        assert_eq!(t.eval(&inputs), expected);
+        assert_eq!(sop.eval(&inputs), expected);
+        assert_eq!(bdd.eval(&inputs), expected);
+    }
+
+    assert_eq!(
+        TruthTable::new(2, 0xF).classify(),
+        LutFunction::Constant(true)
+    );
+
+    Ok(())
+}
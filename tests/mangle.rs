@@ -0,0 +1,30 @@
+use edif::mangle::{is_identifier, legalize, Scope};
+use edif::Atom;
+
+#[test]
+fn scope() {
+    assert_eq!(legalize("x_reg[0]"), "x_reg_0_");
+    assert_eq!(legalize("inner/u1.q"), "inner_u1_q");
+    assert_eq!(legalize("0data"), "&0data");
+    assert_eq!(legalize("&a&b"), "&a_b");
+    assert!(is_identifier("&0data") && !is_identifier("a&b"));
+
+    let mut scope = Scope::default();
+    let name = scope.name(&Atom::from("x[0]"));
+    assert_eq!(&*name.name, "x_0_");
+    assert_eq!(name.rename_from.as_deref(), Some("x[0]"));
+    // A legal name taken by another one is made unique, and renamed.
+    let name = scope.name(&Atom::from("x_0_"));
+    assert_eq!(&*name.name, "x_0__1");
+    assert_eq!(name.rename_from.as_deref(), Some("x_0_"));
+    assert!(scope.name(&Atom::from("y")).rename_from.is_none());
+    assert_eq!(scope.ident(&Atom::from("x[0]")), &Atom::from("x_0_"));
+    assert_eq!(scope.original("x_0__1"), Some(&Atom::from("x_0_")));
+    assert_eq!(scope.iter().count(), 3);
+
+    // Identifiers differing only in case are the same.
+    let mut scope = Scope::default();
+    assert_eq!(scope.ident(&Atom::from("A[0]")), &Atom::from("A_0_"));
+    assert_eq!(scope.ident(&Atom::from("a[0]")), &Atom::from("a_0__1"));
+    assert_eq!(scope.original("a_0_"), Some(&Atom::from("A[0]")));
+}
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use edif::ast::{Direction::*, Property};
+use edif::builder::{EdifBuilder, Pin};
+use edif::netlist::{self, Netlist, Path, PortRef};
+use edif::opt::{self, AbsorbOptions};
+use edif::sim::Simulator;
+use edif::Atom;
+use std::fs;
+
+#[test]
+fn constant_propagation() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let mut n = netlist::from_str(&s)?;
+
+    // Nothing is constant but the tie-offs.
+    let report = opt::propagate_constants(&mut n)?;
+    assert_eq!(report.constant_nets.len(), 2);
+    assert!(report.removed_instances.is_empty());
+
+    // A dangling pin is an error.
+    let mut m = netlist::from_str(&s.replace(
+        "(portref P(instanceref VCC))",
+        "(portref PX(instanceref VCC))",
+    ))?;
+    let err = opt::propagate_constants(&mut m).unwrap_err();
+    assert!(err.to_string().contains("main/inner/VCC/PX"), "{}", err);
+
+    // Tie the input of the shift register to VCC.
+    let inner = Path::root(Atom::from("main")).child(Atom::from("inner"));
+    let pin = PortRef {
+        instance: inner.child(Atom::from("x_0__i_1")),
+        port: Atom::from("I0"),
+        member: None,
+    };
+    let inst = n.instance_mut(&inner).unwrap();
+    assert!(inst
+        .nets
+        .get_mut(&Atom::from("a_IBUF"))
+        .unwrap()
+        .ports
+        .shift_remove(&pin));
+    inst.nets
+        .get_mut(&Atom::from("&_const1_"))
+        .unwrap()
+        .ports
+        .insert(pin);
+
+    let report = opt::propagate_constants(&mut n)?;
+    n.verify_references()?;
+
+    // The flip-flops, the input buffers and VCC are gone, and `ret[0]` is driven by a
+    // constant LUT.
+    assert_eq!(report.removed_instances.len(), 17, "{}", report);
+    assert_eq!(
+        report.rewritten_luts,
+        [inner.child(Atom::from("ret_OBUF_0__inst_i_1"))]
+    );
+    let lut = n.instance(&report.rewritten_luts[0]).unwrap();
+    match &lut.properties[&Atom::from("INIT")] {
+        Property::String(s) => assert_eq!(s, "2'h3"),
+        p => panic!("{:?}", p),
+    }
+
+    let mut sim = Simulator::new(&n)?;
+    for a in 0..2 {
+        sim.set_input("a", a)?;
+        sim.step();
+        assert_eq!(sim.port("ret")?, 1);
+    }
+
+    Ok(())
+}
+
+/// Moves the pin `inst.port` in `scope` from the net `from` to `to`.
+fn rewire(n: &mut Netlist, scope: &Path, inst: &str, port: &str, from: &str, to: &str) {
+    let pin = PortRef {
+        instance: scope.child(Atom::from(inst)),
+        port: Atom::from(port),
+        member: None,
+    };
+    let scope = n.instance_mut(scope).unwrap();
+    assert!(scope
+        .nets
+        .get_mut(&Atom::from(from))
+        .unwrap()
+        .ports
+        .shift_remove(&pin));
+    scope
+        .nets
+        .get_mut(&Atom::from(to))
+        .unwrap()
+        .ports
+        .insert(pin);
+}
+
+#[test]
+fn buffer_absorption() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let mut n = netlist::from_str(&s)?;
+
+    // Move the first inverter to the end of the shift register, in front of the other one:
+    // a -> x_reg[0..10] -> x[0]_i_1 -> ret_OBUF[0]_inst_i_1 -> ret[0]
+    let inner = Path::root(Atom::from("main")).child(Atom::from("inner"));
+    rewire(&mut n, &inner, "x_0__i_1", "I0", "a_IBUF", "b");
+    rewire(
+        &mut n,
+        &inner,
+        "ret_OBUF_0__inst_i_1",
+        "I0",
+        "b",
+        "p_9_out_10_",
+    );
+    rewire(&mut n, &inner, "x_reg_0_", "D", "p_9_out_10_", "a_IBUF");
+
+    let report = opt::absorb_buffers(&mut n, &AbsorbOptions::default())?;
+    n.verify_references()?;
+
+    // The I/O and clock buffers, and both LUTs: folding the inverter into the other one makes
+    // it a buffer.
+    assert_eq!(report.removed_instances.len(), 8, "{}", report);
+    assert!(report.rewritten_luts.is_empty());
+    assert!(n
+        .top
+        .walk()
+        .all(|inst| inst.truth_table().unwrap().is_none()));
+    let main = Path::root(Atom::from("main"));
+    assert_eq!(
+        report.renames[&inner.child(Atom::from("ret_OBUF_0_"))],
+        inner.child(Atom::from("b"))
+    );
+    assert_eq!(
+        report.renames[&main.child(Atom::from("clk_IBUF_BUFG"))],
+        main.child(Atom::from("clk"))
+    );
+
+    let mut sim = Simulator::new(&n)?;
+    sim.set_input("rst", 0)?;
+    let pattern = [1, 0, 1, 1, 0, 0, 1, 0, 1, 1, 1, 0, 0, 1];
+    for (i, &a) in pattern.iter().enumerate() {
+        sim.set_input("a", a)?;
+        sim.step();
+        let expected = if i >= 10 { pattern[i - 10] } else { 0 };
+        assert_eq!(sim.port("ret")?, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn chained_inverters() -> Result<()> {
+    // a -> first -> second -> and_i(I0), with the inverters in both orders of the paths.
+    for (first, second) in [("inv_a", "inv_b"), ("inv_b", "inv_a")] {
+        let inv = Property::String("2'h1".to_string());
+        let edif = EdifBuilder::new("top")
+            .external("hdi_primitives")
+            .cell("LUT1")
+            .port("I0", Input, 1)
+            .port("O", Output, 1)
+            .cell("LUT2")
+            .port("I0", Input, 1)
+            .port("I1", Input, 1)
+            .port("O", Output, 1)
+            .library("work")
+            .cell("top")
+            .port("a", Input, 1)
+            .port("b", Input, 1)
+            .port("y", Output, 1)
+            .instance_of(first, "hdi_primitives", "LUT1")
+            .property("INIT", inv.clone())
+            .instance_of(second, "hdi_primitives", "LUT1")
+            .property("INIT", inv)
+            .instance_of("and_i", "hdi_primitives", "LUT2")
+            .property("INIT", Property::String("4'h8".to_string()))
+            .net("a", [Pin::port("a"), Pin::of(first, "I0")])
+            .net("n1", [Pin::of(first, "O"), Pin::of(second, "I0")])
+            .net("n2", [Pin::of(second, "O"), Pin::of("and_i", "I0")])
+            .net("b", [Pin::port("b"), Pin::of("and_i", "I1")])
+            .net("y", [Pin::of("and_i", "O"), Pin::port("y")])
+            .build()?;
+        let mut n = Netlist::from_ast(&edif);
+
+        let report = opt::absorb_buffers(&mut n, &AbsorbOptions::default())?;
+        n.verify_references()?;
+        assert_eq!(n.top.instances.len(), 1, "{}", report);
+
+        let mut sim = Simulator::new(&n)?;
+        for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            sim.set_input("a", a)?;
+            sim.set_input("b", b)?;
+            sim.eval();
+            assert_eq!(sim.port("y")?, a & b, "a={} b={}", a, b);
+        }
+    }
+    Ok(())
+}
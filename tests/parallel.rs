@@ -0,0 +1,52 @@
+#![cfg(feature = "rayon")]
+
+use anyhow::Result;
+use edif::netlist;
+use edif::parser::EdifParser;
+use std::fs;
+
+#[test]
+fn parallel() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    // The same on one line, and with the cells spread over several lines.
+    let multiline = s
+        .replace(")(cell ", ")\n  (cell ")
+        .replace(")(net", ")\n  (net");
+    for s in [&s, &multiline] {
+        let ast = format!("{:?}", EdifParser::parse_from_str(s)?);
+        let netlist = format!("{:?}", netlist::from_str(s)?);
+
+        // The same as the sequential result, with the maps and spans the same too.
+        for threads in [1, 2, 8] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?;
+            pool.install(|| -> Result<()> {
+                assert_eq!(
+                    format!("{:?}", EdifParser::parse_from_str_parallel(s)?),
+                    ast
+                );
+                assert_eq!(format!("{:?}", netlist::from_str_parallel(s)?), netlist);
+                Ok(())
+            })?;
+        }
+    }
+
+    // Errors in a cell are located in the whole source.
+    let broken = s.replacen("(cellref LUT1", "(cellref", 1);
+    let err = EdifParser::parse_from_str_parallel(&broken).unwrap_err();
+    let expected = EdifParser::parse_from_str(&broken).unwrap_err();
+    assert_eq!(err.to_string(), expected.to_string());
+
+    // With several broken cells, the first one in the source is reported.
+    let broken = broken.replacen("(cellref IBUF", "(cellref", 1);
+    let expected = EdifParser::parse_from_str(&broken).unwrap_err().to_string();
+    for threads in [1, 2, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?;
+        let err = pool.install(|| EdifParser::parse_from_str_parallel(&broken).unwrap_err());
+        assert_eq!(err.to_string(), expected);
+    }
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use anyhow::Result;
-use edif::netlist;
+use edif::ast::{Direction, Number, Property};
+use edif::netlist::{self, Path};
+use edif::parser::EdifParser;
+use edif::{cache, writer, Atom};
 use std::fs;
 
 #[test]
@@ -10,3 +13,148 @@ fn parse() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn black_boxes() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+
+    // `inner` refers to a missing library, and the primitives are external.
+    let s = s
+        .replace(
+            "(cellref inner(libraryref work))",
+            "(cellref inner(libraryref ip))",
+        )
+        .replace("(Library hdi_primitives", "(external hdi_primitives");
+    let n = netlist::from_str(&s)?;
+    n.verify_references()?;
+
+    let boxes = n.black_boxes();
+    assert_eq!(boxes.len(), 8);
+    let inner = boxes.iter().find(|i| &*i.cell == "inner").unwrap();
+    assert!(inner.is_leaf());
+    let mut ports = inner
+        .interface
+        .keys()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>();
+    ports.sort();
+    assert_eq!(ports, ["SR_0_", "a_IBUF", "clk", "ret_OBUF_0_"]);
+    assert_eq!(inner.interface[&Atom::from("clk")].dir, Direction::InOut);
+
+    Ok(())
+}
+
+#[test]
+fn spans() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let s = s.replace(")(instance", ")\n    (instance");
+    let n = netlist::from_str(&s)?;
+
+    let top = Path::root(Atom::from("main"));
+    let span = n.span(&top).unwrap();
+    assert!(s[span.start..span.end].starts_with("(design main"));
+
+    let inner = top.child(Atom::from("inner"));
+    let span = n.span(&inner.child(Atom::from("x_reg_3_"))).unwrap();
+    let text = &s[span.start..span.end];
+    assert!(
+        text.starts_with("(instance(rename x_reg_3_ \"x_reg[3]\")"),
+        "{}",
+        text
+    );
+    assert!(text.ends_with(')'));
+    let line = s[..span.start].matches('\n').count() + 1;
+    assert_eq!(span.line as usize, line);
+    assert_eq!(span.column, 5);
+
+    let span = n.span(&inner.child(Atom::from("p_9_out_10_"))).unwrap();
+    assert!(s[span.start..span.end].starts_with("(net(rename p_9_out_10_"));
+    let port = &n.instance(&inner).unwrap().interface[&Atom::from("clk")];
+    assert_eq!(
+        &s[port.span.start..port.span.end],
+        "(port clk(direction INPUT))"
+    );
+    Ok(())
+}
+
+#[test]
+fn file_order() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let edif = EdifParser::parse_from_str(&s)?;
+    let cells = edif
+        .libs
+        .values()
+        .flat_map(|l| l.cells.keys().map(|c| c.to_string()))
+        .collect::<Vec<_>>();
+    let in_file = s
+        .split("(cell ")
+        .skip(1)
+        .map(|c| c[..c.find('(').unwrap()].to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(cells, in_file);
+
+    // Instances, nets and pins iterate in the order of the source.
+    let n = netlist::from_str(&s)?;
+    let inner = n.instance(&Path::root(Atom::from("main")).child(Atom::from("inner")));
+    for inst in [&*n.top, inner.unwrap()] {
+        let starts = inst.instances.values().map(|i| i.span.start);
+        assert!(starts.clone().zip(starts.skip(1)).all(|(a, b)| a < b));
+        let starts = inst.nets.values().map(|n| n.span.start);
+        assert!(starts.clone().zip(starts.skip(1)).all(|(a, b)| a < b));
+    }
+    let net = &n.top.nets[&Atom::from("clk")];
+    let pins = net
+        .ports
+        .iter()
+        .map(|p| p.port.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(pins, ["I", "clk"]);
+    Ok(())
+}
+
+#[test]
+fn property_values() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let s = s.replace(
+        "(instance GND(viewref netlist(cellref GND(libraryref hdi_primitives))))",
+        "(instance GND(viewref netlist(cellref GND(libraryref hdi_primitives)))\
+         (property WIDE(integer 8589934592))(property SCALE(number(e 15 -1)))\
+         (property TAPS(integer 1 -2 3))(property MIXED(array(string \"a\")(boolean(true))))\
+         (property INIT(string \"48'h0000000000FF\"))(property FLAG(string \"TRUE\")))",
+    );
+    let check = |n: &netlist::Netlist| {
+        let gnd = &n.top.instances[&Atom::from("GND")];
+        let prop = |name: &str| &gnd.properties[&Atom::from(name)];
+        assert_eq!(prop("WIDE").as_i64(), Some(1 << 33));
+        assert_eq!(prop("WIDE").as_bitvec().unwrap().width(), 64);
+        assert!(matches!(prop("SCALE"), Property::Number(n) if n.to_string() == "1.5"));
+        assert_eq!(prop("SCALE").as_i64(), None);
+        assert!(
+            matches!(prop("TAPS"), Property::List(l) if l.iter().filter_map(|p| p.as_i64()).eq([1, -2, 3]))
+        );
+        assert!(matches!(prop("MIXED"), Property::List(l) if l[1].as_bool() == Some(true)));
+        assert_eq!(prop("INIT").as_i64(), Some(0xFF));
+        assert_eq!(prop("INIT").as_bitvec().unwrap().width(), 48);
+        assert_eq!(prop("FLAG").as_bool(), Some(true));
+    };
+    let n = netlist::from_str(&s)?;
+    check(&n);
+
+    // Large exponents are not expanded, and numbers out of 64 bits are errors.
+    let huge = Number {
+        mantissa: 15,
+        exponent: 2_000_000_000,
+    };
+    assert_eq!(huge.to_string(), "15e2000000000");
+    assert_eq!(huge.to_i64(), None);
+    let overflow = s.replace("(integer 8589934592)", "(integer 99999999999999999999)");
+    assert!(netlist::from_str(&overflow).is_err());
+
+    let mut out = vec![];
+    writer::write_edif(&n, &mut out)?;
+    check(&netlist::from_str(std::str::from_utf8(&out)?)?);
+    let mut snapshot = vec![];
+    cache::save(&n, 0, &mut snapshot)?;
+    check(&cache::load(&snapshot)?);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use edif::primitives::{self, PinRole, PrimitiveKind, PropertyType};
+use edif::{netlist, Atom};
+use std::fs;
+
+#[test]
+fn primitive_kinds() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let n = netlist::from_str(&s)?;
+
+    let mut ffs = 0;
+    for inst in n.top.walk() {
+        let prim = match inst.primitive() {
+            Some(prim) => prim,
+            None => {
+                assert!(!inst.is_leaf(), "{} is not in the catalogue", inst.cell);
+                continue;
+            }
+        };
+
+        // The interfaces declared by Vivado agree with the catalogue.
+        for port in inst.interface.values() {
+            let pin = prim.pin(&port.name.name).unwrap();
+            assert_eq!(pin.dir, port.dir);
+            assert_eq!(pin.width as i32, port.kind.width());
+        }
+
+        if prim.kind == PrimitiveKind::Fdre {
+            ffs += 1;
+        }
+    }
+    assert_eq!(ffs, 11);
+
+    let inner = &n.top.instances[&Atom::from("inner")];
+    assert_eq!(inner.primitive_kind(), None);
+    assert_eq!(
+        inner.instances[&Atom::from("x_0__i_1")].primitive_kind(),
+        Some(PrimitiveKind::Lut(1))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn catalogue() {
+    let fdce = primitives::lookup("FDCE").unwrap();
+    assert!(fdce.kind.has_async_control());
+    let roles = |role| {
+        fdce.pins_with_role(role)
+            .map(|p| p.name)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(roles(PinRole::Clock), ["C"]);
+    assert_eq!(roles(PinRole::ClockEnable), ["CE"]);
+    assert_eq!(roles(PinRole::Reset), ["CLR"]);
+    assert_eq!(fdce.property("INIT").unwrap().default, "1'b0");
+
+    let ramb = primitives::lookup("RAMB36E1").unwrap();
+    assert_eq!(
+        ramb.property("INIT_7F").unwrap().ty,
+        PropertyType::Bits(256)
+    );
+    assert!(ramb.property("INIT_80").is_none());
+
+    for prim in primitives::all() {
+        assert_eq!(primitives::lookup(prim.name), Some(prim));
+    }
+}
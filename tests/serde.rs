@@ -0,0 +1,26 @@
+#![cfg(feature = "serde")]
+
+use anyhow::Result;
+use edif::netlist::{self, Netlist};
+use edif::parser::EdifParser;
+use edif::{ast, iso};
+use std::fs;
+
+#[test]
+fn round_trip() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+
+    let ast = EdifParser::parse_from_str(&s)?;
+    let json = serde_json::to_string(&ast)?;
+    let ast: ast::Edif = serde_json::from_str(&json)?;
+    assert_eq!(serde_json::to_string(&ast)?, json);
+
+    let old = netlist::from_str(&s)?;
+    let json = serde_json::to_string_pretty(&old)?;
+    let new: Netlist = serde_json::from_str(&json)?;
+    new.verify_references()?;
+    assert!(iso::compare(&old, &new).is_isomorphic());
+    // The maps of `new` are built in another order, but serialize the same.
+    assert_eq!(serde_json::to_string_pretty(&new)?, json);
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use edif::parser::EdifParser;
+use edif::sexpr::{self, Interner};
+use std::fs;
+
+#[test]
+fn borrowed() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let e = sexpr::parse_borrowed(&s)?;
+
+    // Symbols and strings are slices of the input.
+    let list = e.list().unwrap();
+    let name = list[1].symbol().unwrap();
+    assert_eq!(name, "main");
+    assert!(s.as_bytes().as_ptr_range().contains(&name.as_ptr()));
+    let program = list[5].list().unwrap()[1].list().unwrap()[2]
+        .list()
+        .unwrap();
+    assert_eq!(program[1].str(), Some("Vivado"));
+
+    let interner = Interner::from_expr(&e);
+    let lut = interner.get("LUT1").unwrap();
+    assert_eq!(interner.resolve(lut), "LUT1");
+    assert!(interner.get("x_reg[0]").is_none());
+    assert!(interner.len() < 200);
+
+    // The same document as the owned parser reads.
+    let owned = format!("{:?}", EdifParser::new().parse_expr(&e.to_owned_expr())?);
+    assert_eq!(owned, format!("{:?}", EdifParser::parse_from_str(&s)?));
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use anyhow::Result;
+use edif::ast::{Direction::*, Property};
+use edif::builder::{EdifBuilder, Pin};
+use edif::netlist::{self, Netlist};
+use edif::sim::Simulator;
+use std::fs;
+
+/// A builder with the primitives used below, in the library `hdi_primitives`.
+fn primitives() -> EdifBuilder {
+    EdifBuilder::new("top")
+        .external("hdi_primitives")
+        .cell("CARRY4")
+        .port("CI", Input, 1)
+        .port("CYINIT", Input, 1)
+        .port("DI", Input, 4)
+        .port("S", Input, 4)
+        .port("CO", Output, 4)
+        .port("O", Output, 4)
+        .cell("MUXF7")
+        .port("I0", Input, 1)
+        .port("I1", Input, 1)
+        .port("S", Input, 1)
+        .port("O", Output, 1)
+        .cell("MUXF8")
+        .port("I0", Input, 1)
+        .port("I1", Input, 1)
+        .port("S", Input, 1)
+        .port("O", Output, 1)
+        .cell("LUT6_2")
+        .port("I0", Input, 1)
+        .port("I1", Input, 1)
+        .port("I2", Input, 1)
+        .port("I3", Input, 1)
+        .port("I4", Input, 1)
+        .port("I5", Input, 1)
+        .port("O5", Output, 1)
+        .port("O6", Output, 1)
+        .cell("FDRE")
+        .port("C", Input, 1)
+        .port("CE", Input, 1)
+        .port("D", Input, 1)
+        .port("R", Input, 1)
+        .port("Q", Output, 1)
+        .cell("FDSE")
+        .port("C", Input, 1)
+        .port("CE", Input, 1)
+        .port("D", Input, 1)
+        .port("S", Input, 1)
+        .port("Q", Output, 1)
+        .cell("FDCE")
+        .port("C", Input, 1)
+        .port("CE", Input, 1)
+        .port("CLR", Input, 1)
+        .port("D", Input, 1)
+        .port("Q", Output, 1)
+        .cell("FDPE")
+        .port("C", Input, 1)
+        .port("CE", Input, 1)
+        .port("D", Input, 1)
+        .port("PRE", Input, 1)
+        .port("Q", Output, 1)
+        .library("work")
+        .cell("top")
+}
+
+#[test]
+fn shift_register() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let n = netlist::from_str(&s)?;
+    let mut sim = Simulator::new(&n)?;
+
+    // `ret[0]` is `a` delayed by the 11 flip-flops, through two inverters, and `ret[1]` is tied
+    // to GND.
+    let pattern = [1, 0, 0, 1, 1, 1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 1, 1, 0, 0, 0];
+    sim.set_input("rst", 0)?;
+    for (i, &a) in pattern.iter().enumerate() {
+        sim.set_input("a", a)?;
+        sim.step();
+        let expected = if i >= 10 { pattern[i - 10] } else { 1 };
+        assert_eq!(sim.port("ret")?, expected, "cycle {}", sim.cycle());
+    }
+
+    // The synchronous reset clears the flip-flops.
+    sim.set_input("rst", 1)?;
+    sim.step();
+    assert_eq!(sim.port("ret")?, 1);
+
+    assert!(sim.set_input("ret", 0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn carry4() -> Result<()> {
+    // A 4-bit adder: `DI` is `a` and `S` is `a ^ b`, which the test drives as `p`.
+    let mut b = primitives()
+        .port("a", Input, 4)
+        .port("p", Input, 4)
+        .port("ci", Input, 1)
+        .port("cyinit", Input, 1)
+        .port("sum", Output, 4)
+        .port("co", Output, 4)
+        .instance_of("carry", "hdi_primitives", "CARRY4")
+        .net("ci", [Pin::port("ci"), Pin::of("carry", "CI")])
+        .net("cyinit", [Pin::port("cyinit"), Pin::of("carry", "CYINIT")]);
+    for k in 0..4 {
+        b = b
+            .net(
+                &format!("a{}", k),
+                [Pin::port("a").bit(k), Pin::of("carry", "DI").bit(k)],
+            )
+            .net(
+                &format!("p{}", k),
+                [Pin::port("p").bit(k), Pin::of("carry", "S").bit(k)],
+            )
+            .net(
+                &format!("o{}", k),
+                [Pin::of("carry", "O").bit(k), Pin::port("sum").bit(k)],
+            )
+            .net(
+                &format!("co{}", k),
+                [Pin::of("carry", "CO").bit(k), Pin::port("co").bit(k)],
+            );
+    }
+    let n = Netlist::from_ast(&b.build()?);
+    let mut sim = Simulator::new(&n)?;
+
+    // The carry in is `CI | CYINIT`.
+    for (ci, cyinit) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let cin = ci | cyinit;
+        sim.set_input("ci", ci)?;
+        sim.set_input("cyinit", cyinit)?;
+        for a in 0..16 {
+            for b in 0..16 {
+                sim.set_input("a", a)?;
+                sim.set_input("p", a ^ b)?;
+                sim.eval();
+                let sum = a + b + cin;
+                assert_eq!(sim.port("sum")?, sum & 15, "{} + {} + {}", a, b, cin);
+                // `CO[k]` is the carry out of bit `k`.
+                let co = (0..4).fold(0, |co, k| {
+                    let mask = (2 << k) - 1;
+                    co | (((a & mask) + (b & mask) + cin > mask) as u64) << k
+                });
+                assert_eq!(sim.port("co")?, co, "{} + {} + {}", a, b, cin);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn muxf() -> Result<()> {
+    // A 4:1 multiplexer of two MUXF7s and a MUXF8.
+    let b = primitives()
+        .port("d", Input, 4)
+        .port("s", Input, 2)
+        .port("y", Output, 1)
+        .instance_of("lo", "hdi_primitives", "MUXF7")
+        .instance_of("hi", "hdi_primitives", "MUXF7")
+        .instance_of("mux", "hdi_primitives", "MUXF8")
+        .net("d0", [Pin::port("d").bit(0), Pin::of("lo", "I0")])
+        .net("d1", [Pin::port("d").bit(1), Pin::of("lo", "I1")])
+        .net("d2", [Pin::port("d").bit(2), Pin::of("hi", "I0")])
+        .net("d3", [Pin::port("d").bit(3), Pin::of("hi", "I1")])
+        .net(
+            "s0",
+            [
+                Pin::port("s").bit(0),
+                Pin::of("lo", "S"),
+                Pin::of("hi", "S"),
+            ],
+        )
+        .net("s1", [Pin::port("s").bit(1), Pin::of("mux", "S")])
+        .net("lo", [Pin::of("lo", "O"), Pin::of("mux", "I0")])
+        .net("hi", [Pin::of("hi", "O"), Pin::of("mux", "I1")])
+        .net("y", [Pin::of("mux", "O"), Pin::port("y")]);
+    let n = Netlist::from_ast(&b.build()?);
+    let mut sim = Simulator::new(&n)?;
+
+    for d in 0..16 {
+        for s in 0..4 {
+            sim.set_input("d", d)?;
+            sim.set_input("s", s)?;
+            sim.eval();
+            assert_eq!(sim.port("y")?, d >> s & 1, "d={} s={}", d, s);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn flip_flops() -> Result<()> {
+    // One flip-flop of each kind, with its own set/reset and a shared `D` and `CE`.
+    let mut b = primitives()
+        .port("d", Input, 1)
+        .port("ce", Input, 1)
+        .port("sr", Input, 4)
+        .port("q", Output, 4);
+    let mut d = vec![Pin::port("d")];
+    let mut ce = vec![Pin::port("ce")];
+    for (k, &(name, cell, sr)) in [
+        ("fdre", "FDRE", "R"),
+        ("fdse", "FDSE", "S"),
+        ("fdce", "FDCE", "CLR"),
+        ("fdpe", "FDPE", "PRE"),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let k = k as i32;
+        b = b
+            .instance_of(name, "hdi_primitives", cell)
+            .net(
+                &format!("sr{}", k),
+                [Pin::port("sr").bit(k), Pin::of(name, sr)],
+            )
+            .net(
+                &format!("q{}", k),
+                [Pin::of(name, "Q"), Pin::port("q").bit(k)],
+            );
+        d.push(Pin::of(name, "D"));
+        ce.push(Pin::of(name, "CE"));
+    }
+    let n = Netlist::from_ast(&b.net("d", d).net("ce", ce).build()?);
+    let mut sim = Simulator::new(&n)?;
+
+    // The `INIT`s default to the set/reset values.
+    assert_eq!(sim.port("q")?, 0b1010);
+
+    // Each step is `(d, ce, sr)` and the expected `q` after the rising edge, with the bits
+    // of `sr` and `q` in the order FDRE, FDSE, FDCE, FDPE.
+    let steps = [
+        (1, 1, 0b0000, 0b1111),
+        (0, 0, 0b0000, 0b1111),
+        (0, 1, 0b0000, 0b0000),
+        (1, 0, 0b0000, 0b0000),
+        (1, 1, 0b0011, 0b1110),
+        (0, 1, 0b1100, 0b1000),
+        (1, 0, 0b1111, 0b1010),
+        (1, 1, 0b0000, 0b1111),
+    ];
+    for (cycle, &(d, ce, sr, q)) in steps.iter().enumerate() {
+        sim.set_input("d", d)?;
+        sim.set_input("ce", ce)?;
+        sim.set_input("sr", sr)?;
+        sim.step();
+        assert_eq!(sim.port("q")?, q, "cycle {}", cycle);
+    }
+
+    // The clear and preset act without a clock edge, unlike the synchronous set and reset.
+    sim.set_input("d", 0)?;
+    sim.set_input("sr", 0b1111)?;
+    sim.eval();
+    assert_eq!(sim.port("q")?, 0b1011);
+    sim.step();
+    assert_eq!(sim.port("q")?, 0b1010);
+
+    Ok(())
+}
+
+#[test]
+fn lut6_2() -> Result<()> {
+    // `O5` is the parity of `I0`..`I4`, and `O6` is `I5 | O5`.
+    let mut b = primitives()
+        .port("i", Input, 6)
+        .port("o5", Output, 1)
+        .port("o6", Output, 1)
+        .instance_of("lut", "hdi_primitives", "LUT6_2")
+        .property("INIT", Property::String("64'hFFFFFFFF96696996".into()))
+        .net("o5", [Pin::of("lut", "O5"), Pin::port("o5")])
+        .net("o6", [Pin::of("lut", "O6"), Pin::port("o6")]);
+    for k in 0..6 {
+        b = b.net(
+            &format!("i{}", k),
+            [Pin::port("i").bit(k), Pin::of("lut", &format!("I{}", k))],
+        );
+    }
+    let n = Netlist::from_ast(&b.build()?);
+    let mut sim = Simulator::new(&n)?;
+
+    for i in 0..64u64 {
+        sim.set_input("i", i)?;
+        sim.eval();
+        let parity = (i & 31).count_ones() as u64 & 1;
+        assert_eq!(sim.port("o5")?, parity, "i={}", i);
+        assert_eq!(sim.port("o6")?, i >> 5 | parity, "i={}", i);
+    }
+    Ok(())
+}
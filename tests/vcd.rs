@@ -0,0 +1,43 @@
+use anyhow::Result;
+use edif::netlist;
+use edif::sim::Simulator;
+use edif::vcd::{VcdOptions, VcdWriter};
+use std::fs;
+
+#[test]
+fn simulation_dump() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let n = netlist::from_str(&s)?;
+    let mut sim = Simulator::new(&n)?;
+
+    let options = VcdOptions {
+        signals: vec!["main/ret".into(), "main/inner/p_*".into()],
+        ..Default::default()
+    };
+    let mut vcd = VcdWriter::new(&n, sim.nets(), &options, vec![])?;
+    sim.set_input("rst", 0)?;
+    for _ in 0..12 {
+        vcd.dump(sim.cycle() * 10, sim.values())?;
+        sim.step();
+    }
+    let out = String::from_utf8(vcd.into_inner())?;
+
+    let defs = out.lines().take_while(|l| *l != "$enddefinitions $end");
+    let vars = defs
+        .filter(|l| l.contains("$var") || l.contains("$scope"))
+        .map(|l| l.trim())
+        .collect::<Vec<_>>();
+    assert_eq!(vars[0], "$scope module main $end");
+    assert!(vars[1].ends_with(" ret [1:0] $end"), "{}", vars[1]);
+    assert_eq!(vars[2], "$scope module inner $end");
+    // Original names, as in `p_9_out[10]`.
+    assert!(vars[3..].iter().any(|v| v.ends_with(" p_9_out[10] $end")));
+    assert_eq!(vars.len(), 3 + 10);
+
+    // `ret[0]` falls after `a` has propagated through the 11 flip-flops.
+    let ret = vars[1].split(' ').nth(3).unwrap();
+    assert!(out.contains(&format!("#0\n$dumpvars\nb01 {}\n", ret)));
+    assert!(out.contains(&format!("#110\nb00 {}\n", ret)));
+
+    Ok(())
+}
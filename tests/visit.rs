@@ -0,0 +1,169 @@
+use anyhow::Result;
+use edif::ast::{self, Property};
+use edif::netlist::{self, Instance, Net, Path, PortRef};
+use edif::parser::EdifParser;
+use edif::visit::ast::{Fold as _, Scope, Visit as _};
+use edif::visit::{self, netlist::Visit, netlist::VisitMut};
+use edif::Atom;
+use std::fs;
+use std::ops::ControlFlow;
+
+fn read() -> Result<String> {
+    Ok(fs::read_to_string(format!(
+        "{}/tests/test.edf",
+        env!("CARGO_MANIFEST_DIR")
+    ))?)
+}
+
+/// The paths in pre- and post-order, the number of pins, and the net of a pin to find.
+#[derive(Default)]
+struct Walk {
+    pre: Vec<Path>,
+    post: Vec<Path>,
+    pins: usize,
+    find: Option<PortRef>,
+}
+
+impl<'a> Visit<'a, Path> for Walk {
+    fn visit_instance(&mut self, inst: &'a Instance) -> ControlFlow<Path> {
+        self.pre.push(inst.path.clone());
+        visit::netlist::visit_instance(self, inst)?;
+        self.post.push(inst.path.clone());
+        ControlFlow::Continue(())
+    }
+
+    fn visit_net(&mut self, path: &Path, net: &'a Net) -> ControlFlow<Path> {
+        if self.find.as_ref().is_some_and(|p| net.ports.contains(p)) {
+            return ControlFlow::Break(path.clone());
+        }
+        visit::netlist::visit_net(self, path, net)
+    }
+
+    fn visit_portref(&mut self, _net: &Path, _pin: &'a PortRef) -> ControlFlow<Path> {
+        self.pins += 1;
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn visit_netlist() -> Result<()> {
+    let mut n = netlist::from_str(&read()?)?;
+    let top = n.top.path.clone();
+    let inner = top.child(Atom::from("inner"));
+
+    let mut walk = Walk::default();
+    assert!(walk.visit_netlist(&n).is_continue());
+    assert_eq!(walk.pre.len(), walk.post.len());
+    assert_eq!(walk.pre[0], top);
+    assert_eq!(walk.post.last(), Some(&top));
+    let i = walk.pre.iter().position(|p| *p == inner).unwrap();
+    assert!(walk.pre[i + 1].starts_with(&inner));
+    let pins = n.top.nets.values().map(|n| n.ports.len()).sum::<usize>()
+        + n.top.instances[&Atom::from("inner")]
+            .nets
+            .values()
+            .map(|n| n.ports.len())
+            .sum::<usize>();
+    assert_eq!(walk.pins, pins);
+
+    // Stops at the net of the pin, with its path.
+    let mut walk = Walk {
+        find: Some(PortRef {
+            instance: inner.child(Atom::from("x_reg_0_")),
+            port: Atom::from("Q"),
+            member: None,
+        }),
+        ..Walk::default()
+    };
+    let flow = walk.visit_netlist(&n);
+    assert_eq!(
+        flow,
+        ControlFlow::Break(inner.child(Atom::from("x_reg_n_0__0_")))
+    );
+    assert!(!walk.post.contains(&inner));
+
+    // Sets the `INIT` of every LUT.
+    struct Init;
+    impl VisitMut for Init {
+        fn visit_property_mut(
+            &mut self,
+            _scope: &Path,
+            name: &Atom,
+            value: &mut Property,
+        ) -> ControlFlow<()> {
+            if &**name == "INIT" {
+                *value = Property::String("2'h2".to_string());
+            }
+            ControlFlow::Continue(())
+        }
+    }
+    assert!(Init.visit_netlist_mut(&mut n).is_continue());
+    let lut = &n.instance(&inner.child(Atom::from("x_0__i_1"))).unwrap();
+    assert!(matches!(&lut.properties[&Atom::from("INIT")], Property::String(s) if s == "2'h2"));
+    n.verify_references()?;
+    Ok(())
+}
+
+#[test]
+fn fold_ast() -> Result<()> {
+    // Prefixes every cell name, and the references to them.
+    struct Prefix;
+    impl visit::ast::Fold for Prefix {
+        fn fold_cell(&mut self, lib: &Atom, mut cell: ast::Cell) -> ast::Cell {
+            cell.name = Atom::from(format!("p_{}", cell.name));
+            visit::ast::fold_cell(self, lib, cell)
+        }
+
+        fn fold_instance(&mut self, _scope: Scope, mut inst: ast::Instance) -> ast::Instance {
+            inst.cellref = Atom::from(format!("p_{}", inst.cellref));
+            inst
+        }
+    }
+
+    let mut edif = Prefix.fold_edif(EdifParser::parse_from_str(&read()?)?);
+    let cells = edif.libs.values().flat_map(|l| l.cells.keys());
+    assert!(cells.clone().all(|c| c.starts_with("p_")));
+    assert_eq!(cells.count(), 10);
+    edif.design.cellref = Atom::from(format!("p_{}", edif.design.cellref));
+    netlist::Netlist::from_ast(&edif).verify_references()?;
+    Ok(())
+}
+
+#[test]
+fn visit_ast_scopes() -> Result<()> {
+    // The cells containing the instances with an `INIT`.
+    #[derive(Default)]
+    struct Inits(Vec<(String, String, String)>);
+    impl<'a> visit::ast::Visit<'a> for Inits {
+        fn visit_property(
+            &mut self,
+            scope: Scope<'a>,
+            inst: &'a Atom,
+            name: &'a ast::Name,
+            _value: &'a Property,
+        ) -> ControlFlow<()> {
+            if &*name.name == "INIT" {
+                let path = (
+                    scope.lib.to_string(),
+                    scope.cell.to_string(),
+                    inst.to_string(),
+                );
+                self.0.push(path);
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    let edif = EdifParser::parse_from_str(&read()?)?;
+    let mut inits = Inits::default();
+    assert!(inits.visit_edif(&edif).is_continue());
+    assert_eq!(inits.0.len(), 13);
+    assert!(inits
+        .0
+        .iter()
+        .all(|(lib, cell, _)| lib == "work" && cell == "inner"));
+    assert!(inits
+        .0
+        .contains(&("work".into(), "inner".into(), "x_reg_3_".into())));
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use edif::ast::{Direction::*, Property};
+use edif::builder::{EdifBuilder, Pin};
+use edif::netlist::{self, Netlist, Path};
+use edif::{iso, lint, verilog, writer, Atom};
+use std::fs;
+
+fn read() -> Result<String> {
+    Ok(fs::read_to_string(format!(
+        "{}/tests/test.edf",
+        env!("CARGO_MANIFEST_DIR")
+    ))?)
+}
+
+#[test]
+fn write_edif() -> Result<()> {
+    let s = read()?;
+    let old = netlist::from_str(&s)?;
+    let mut out = vec![];
+    writer::write_edif(&old, &mut out)?;
+    let new = netlist::from_str(std::str::from_utf8(&out)?)?;
+    new.verify_references()?;
+    let report = iso::compare(&old, &new);
+    assert!(report.is_isomorphic(), "{}", report);
+    // The order of the source is kept.
+    assert!(old.top.instances.keys().eq(new.top.instances.keys()));
+    assert!(old.top.nets.keys().eq(new.top.nets.keys()));
+
+    // Flattened names are legalized, and keep their original form.
+    let mut flat = netlist::from_str(&s)?;
+    flat.flatten();
+    let mut out = vec![];
+    writer::write_edif(&flat, &mut out)?;
+    let out = String::from_utf8(out)?;
+    assert!(out.contains("(rename inner_x_reg_0_ \"inner/x_reg[0]\")"));
+    let new = netlist::from_str(&out)?;
+    new.verify_references()?;
+    assert!(iso::compare(&old, &new).is_isomorphic());
+    Ok(())
+}
+
+#[test]
+fn write_verilog_and_lint() -> Result<()> {
+    let n = netlist::from_str(&read()?)?;
+    let mut out = vec![];
+    verilog::write_verilog(&n, &mut out)?;
+    let out = String::from_utf8(out)?;
+    assert!(out.contains("module main ("));
+    assert!(out.contains("  FDRE #(\n    .INIT(1'b0)\n  ) \\x_reg[0]  ("));
+    assert_eq!(out.matches("endmodule").count(), 2);
+
+    assert!(lint::lint(&n).is_empty());
+
+    // Inputs shorted together cannot be assigned.
+    let shorted = read()?
+        .replace("(portref a)))", "(portref a)(portref rst)))")
+        .replace(
+            "(portref I(instanceref rst_IBUF_inst))(portref rst))",
+            "(portref I(instanceref rst_IBUF_inst)))",
+        );
+    let n = netlist::from_str(&shorted)?;
+    let err = verilog::write_verilog(&n, &mut vec![]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("connects the inputs `a` and `rst`"),
+        "{}",
+        err
+    );
+    Ok(())
+}
+
+#[test]
+fn dangling_references() -> Result<()> {
+    let s = read()?;
+    for (from, to, pin) in [
+        ("(instanceref GND)", "(instanceref GNDX)", "main/GNDX/G"),
+        ("(portref a)))", "(portref NOPE)))", "main/NOPE"),
+    ] {
+        let n = netlist::from_str(&s.replace(from, to))?;
+        assert!(n.verify_references().is_err());
+        let err = writer::write_edif(&n, &mut vec![]).unwrap_err();
+        assert!(err.to_string().contains(pin), "{}", err);
+        let err = verilog::write_verilog(&n, &mut vec![]).unwrap_err();
+        assert!(err.to_string().contains(pin), "{}", err);
+    }
+    Ok(())
+}
+
+#[test]
+fn edited_copies() -> Result<()> {
+    let mut b = EdifBuilder::new("top")
+        .external("hdi_primitives")
+        .cell("LUT1")
+        .port("I0", Input, 1)
+        .port("O", Output, 1)
+        .library("work")
+        .cell("sub")
+        .port("i", Input, 1)
+        .port("o", Output, 1)
+        .instance_of("lut", "hdi_primitives", "LUT1")
+        .property("INIT", Property::String("2'h1".into()))
+        .net("i", [Pin::port("i"), Pin::of("lut", "I0")])
+        .net("o", [Pin::of("lut", "O"), Pin::port("o")])
+        .cell("top")
+        .port("a", Input, 2)
+        .port("y", Output, 2);
+    for i in 0..2 {
+        let u = format!("u{}", i);
+        b = b
+            .instance(&u, "sub")
+            .net(
+                &format!("a{}", i),
+                [Pin::port("a").bit(i), Pin::of(&u, "i")],
+            )
+            .net(
+                &format!("y{}", i),
+                [Pin::of(&u, "o"), Pin::port("y").bit(i)],
+            );
+    }
+    let mut n = Netlist::from_ast(&b.build()?);
+    let lut = Path::root(Atom::from("top"))
+        .child(Atom::from("u1"))
+        .child(Atom::from("lut"));
+    n.edit(|tx| tx.set_property(&lut, "INIT", Some(Property::String("2'h2".into()))))?;
+
+    // The edited copy of `sub` is written as a cell of its own.
+    let mut out = vec![];
+    writer::write_edif(&n, &mut out)?;
+    let out = String::from_utf8(out)?;
+    assert!(out.contains("(cell sub_1 "), "{}", out);
+    let new = netlist::from_str(&out)?;
+    new.verify_references()?;
+    let report = iso::compare(&n, &new);
+    assert!(report.is_isomorphic(), "{}", report);
+
+    let mut out = vec![];
+    verilog::write_verilog(&n, &mut out)?;
+    let out = String::from_utf8(out)?;
+    assert!(out.contains("module sub_1 ("), "{}", out);
+    assert!(out.contains("  sub_1 u1 ("), "{}", out);
+    assert_eq!(out.matches("endmodule").count(), 4);
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use edif::ast::{Direction::*, Property};
+use edif::builder::{EdifBuilder, Pin};
+use edif::netlist::{self, Netlist};
+use edif::{iso, yosys, Atom};
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn yosys_roundtrip() -> Result<()> {
+    let s = fs::read_to_string(format!("{}/tests/test.edf", env!("CARGO_MANIFEST_DIR")))?;
+    let mut n = netlist::from_str(&s)?;
+    let gnd = n.top.instances.get_mut(&Atom::from("GND")).unwrap();
+    gnd.properties
+        .insert(Atom::from("WIDTH"), Property::Integer(-40_000_000_000));
+
+    let json = yosys::to_json(&n);
+    let main = &json["modules"]["main"];
+    assert_eq!(main["ports"]["ret"]["bits"].as_array().unwrap().len(), 2);
+    assert_eq!(main["cells"]["inner"]["type"], "inner");
+    assert_eq!(
+        json["modules"]["inner"]["cells"]["x_reg_0_"]["parameters"]["INIT"],
+        "1'b0"
+    );
+
+    let m = yosys::from_json(&json)?;
+    m.verify_references()?;
+    assert_eq!(m.top.cell, n.top.cell);
+    assert_eq!(m.top.instances.len(), n.top.instances.len());
+    assert_eq!(m.top.nets.len(), n.top.nets.len());
+    assert_eq!(
+        m.top.instances[&Atom::from("GND")].properties[&Atom::from("WIDTH")],
+        Property::Integer(-40_000_000_000)
+    );
+    let inner = &m.top.instances[&Atom::from("inner")];
+    assert_eq!(
+        inner.nets.len(),
+        n.top.instances[&Atom::from("inner")].nets.len()
+    );
+
+    // Bits are numbered consistently through the round trip.
+    assert_eq!(yosys::to_json(&m), json);
+
+    Ok(())
+}
+
+#[test]
+fn yosys_import() -> Result<()> {
+    // As written by `synth_xilinx; write_json`.
+    let json = json!({
+        "creator": "Yosys 0.9 (git sha1 1979e0b)",
+        "modules": {
+            "top": {
+                "attributes": { "top": "00000000000000000000000000000001", "src": "top.v:1" },
+                "ports": {
+                    "a": { "direction": "input", "bits": [2, 3] },
+                    "y": { "direction": "output", "bits": [4, "0", "1", "x"] }
+                },
+                "cells": {
+                    "$abc$85$lut$aiger84$7": {
+                        "hide_name": 1,
+                        "type": "LUT2",
+                        "parameters": { "INIT": "1000" },
+                        "attributes": { "src": "top.v:4" },
+                        "port_directions": { "I0": "input", "I1": "input", "O": "output" },
+                        "connections": { "I0": [2], "I1": [3], "O": [4] }
+                    }
+                },
+                "netnames": {
+                    "$abc$85$new_n5_": { "hide_name": 1, "bits": [4], "attributes": {} },
+                    "a": { "hide_name": 0, "bits": [2, 3], "attributes": { "src": "top.v:2" } },
+                    "y": { "hide_name": 0, "bits": [4, "0", "1", "x"], "attributes": { "src": "top.v:3" } }
+                }
+            }
+        }
+    });
+    let n = yosys::from_json(&json)?;
+    n.verify_references()?;
+
+    let lut = &n.top.instances[&Atom::from("$abc$85$lut$aiger84$7")];
+    let init = &lut.properties[&Atom::from("INIT")];
+    assert!(matches!(init, Property::String(s) if s == "4'b1000"));
+    assert_eq!(init.as_i64(), Some(8));
+
+    // Multi-bit netnames name each bit, and visible names win over hidden ones.
+    for name in ["a[0]", "a[1]", "y[0]"] {
+        assert!(n.top.nets.contains_key(&Atom::from(name)), "{}", name);
+    }
+    assert!(!n.top.nets.contains_key(&Atom::from("$abc$85$new_n5_")));
+
+    // `y[1]` and `y[2]` are tied off, and the undefined `y[3]` is left open.
+    for (cell, net) in [("GND", "<const0>"), ("VCC", "<const1>")] {
+        assert!(n.top.instances.values().any(|i| &*i.cell == cell));
+        assert_eq!(n.top.nets[&Atom::from(net)].ports.len(), 2);
+    }
+    assert_eq!(n.top.nets.len(), 5);
+    Ok(())
+}
+
+#[test]
+fn yosys_recursive_module() {
+    let json = json!({
+        "modules": {
+            "top": {
+                "attributes": { "top": "00000000000000000000000000000001" },
+                "cells": { "self": { "type": "top" } }
+            }
+        }
+    });
+    let err = format!("{:#}", yosys::from_json(&json).unwrap_err());
+    assert!(err.contains("module `top` instantiates itself"), "{}", err);
+}
+
+#[test]
+fn yosys_same_cell_names() -> Result<()> {
+    let mut b = EdifBuilder::new("top")
+        .external("hdi_primitives")
+        .cell("INV")
+        .port("I", Input, 1)
+        .port("O", Output, 1)
+        .cell("BUF")
+        .port("I", Input, 1)
+        .port("O", Output, 1);
+    for (lib, leaf) in [("a", "INV"), ("b", "BUF")] {
+        b = b
+            .library(lib)
+            .cell("sub")
+            .port("i", Input, 1)
+            .port("o", Output, 1)
+            .instance_of("g", "hdi_primitives", leaf)
+            .net("i", [Pin::port("i"), Pin::of("g", "I")])
+            .net("o", [Pin::of("g", "O"), Pin::port("o")]);
+    }
+    let n = Netlist::from_ast(
+        &b.cell("top")
+            .port("x", Input, 1)
+            .port("y", Output, 1)
+            .instance_of("ua", "a", "sub")
+            .instance_of("ub", "b", "sub")
+            .net("x", [Pin::port("x"), Pin::of("ua", "i")])
+            .net("m", [Pin::of("ua", "o"), Pin::of("ub", "i")])
+            .net("y", [Pin::of("ub", "o"), Pin::port("y")])
+            .build()?,
+    );
+
+    // The cells named `sub` of `a` and `b` become two modules.
+    let json = yosys::to_json(&n);
+    let top = &json["modules"]["top"]["cells"];
+    assert_eq!(top["ua"]["type"], "sub");
+    assert_eq!(top["ub"]["type"], "sub_1");
+    assert_eq!(json["modules"]["sub_1"]["cells"]["g"]["type"], "BUF");
+    let m = yosys::from_json(&json)?;
+    m.verify_references()?;
+    assert!(iso::compare(&n, &m).is_isomorphic());
+    Ok(())
+}